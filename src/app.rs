@@ -1,6 +1,11 @@
+use std::collections::{HashMap, HashSet};
+
 use eframe::CreationContext;
-use egui::{emath::Rot2, vec2, Color32, Id, Rect, Ui};
-use egui_dock::{DockArea, DockState, NodeIndex, SurfaceIndex};
+use egui::{
+    emath::{RectTransform, Rot2},
+    vec2, Color32, Id, Key, KeyboardShortcut, Modifiers, Rect, RichText, Ui,
+};
+use egui_dock::{DockArea, DockState, NodeIndex, SurfaceIndex, TabIndex};
 use egui_modal::Modal;
 use egui_snarl::{
     ui::{BackgroundPattern, SnarlStyle, Viewport},
@@ -8,13 +13,33 @@ use egui_snarl::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::node::Node;
+use crate::buildings::{points_for_coupon, recipe_names, round, Building, Material};
+use crate::clipboard::Clipboard;
+use crate::docs::RecipeDatabase;
+use crate::group::lost_pins;
+use crate::history::{wires_at, Command, History};
+use crate::library::{update_instances, Library};
+use crate::node::{refresh_node, Node, Output};
+use crate::palette::{search_ui, Palette};
+use crate::unlocks::{Unlocks, ALTERNATE_RECIPES, MAX_TIER};
 use crate::viewer::Viewer;
 
 pub struct App {
     tree: DockState<TabState>,
     show_about: bool,
+    show_recipes: bool,
+    show_unlocks: bool,
+    show_library: bool,
+    show_history: bool,
+    show_stats: bool,
+    /// Savegame to import the unlocked alternates from.
+    savegame_path: String,
+    import_status: Option<String>,
     counter: usize,
+    /// Also installed as the source of all recipe rates.
+    recipe_db: Option<&'static RecipeDatabase>,
+    /// Hand-coded recipes missing from the recipe database.
+    unknown_recipes: Vec<String>,
     tab_viewer: TabViewer,
 }
 
@@ -22,18 +47,199 @@ pub struct App {
 struct TabViewer {
     added_nodes: Vec<(SurfaceIndex, NodeIndex)>,
     group_edits: Vec<(GroupEdit, NodeGraph, Snarl)>,
-    group_saves: Vec<(GroupEdit, NodeGraph, Snarl)>,
+    /// Edit tab id, with the graph to put into the group it edits.
+    group_saves: Vec<(usize, GroupEdit, NodeGraph, Snarl)>,
+    /// Edit tabs that were closed or discarded.
+    finished_edits: Vec<(usize, GroupEdit)>,
+    /// Edit tabs whose group is gone, so their changes can't be applied.
+    orphaned_edits: HashSet<usize>,
     current_tab_index: usize,
+    logistics: Logistics,
+    unlocks: Unlocks,
+    /// AWESOME Sink coupons printed so far, which makes the next one more expensive.
+    coupons: u32,
+    library: Library,
+    clipboard: Option<Clipboard>,
+    palette: Option<Palette>,
+    /// Query of the search in the graph menu.
+    search: String,
+}
+
+/// Links between tabs: central storage rates and export ports, summed over all tabs.
+#[derive(Debug, Default)]
+pub struct Logistics {
+    uploaded: HashMap<Material, f32>,
+    drawn: HashMap<Material, f32>,
+    exports: HashMap<String, Output>,
+    /// Number of import ports per name.
+    importers: HashMap<String, f32>,
+    /// Names whose export ports carry different resources.
+    mismatched: HashSet<String>,
+    sink_points: f32,
+    waste: HashMap<Material, f32>,
+}
+
+impl Logistics {
+    fn from_tabs<'a>(tabs: impl Iterator<Item = &'a TabState>) -> Self {
+        let mut totals = Self::default();
+        for tab in tabs {
+            totals.add_graph(&tab.graph, 1.);
+        }
+        totals
+    }
+
+    /// Adds up `graph`, built `copies` times.
+    fn add_graph(&mut self, graph: &NodeGraph, copies: f32) {
+        for node in graph.node_weights() {
+            match node {
+                Node::Group {
+                    graph, copies: n, ..
+                } => self.add_graph(graph, copies * *n as f32),
+                Node::Building(Building::DimensionalDepot(d)) => {
+                    if let Some((material, speed)) = d.upload() {
+                        *self.uploaded.entry(material).or_default() += speed * copies;
+                    }
+                }
+                Node::Building(Building::CentralStorage(c)) => {
+                    if let Some(material) = c.material {
+                        *self.drawn.entry(material).or_default() += c.speed * copies;
+                    }
+                }
+                Node::Building(Building::AwesomeSink(s)) => {
+                    self.sink_points += s.points_per_minute() * copies;
+                }
+                Node::Building(Building::NuclearPowerPlant(p)) => {
+                    if let Some(material) = p.waste_material() {
+                        *self.waste.entry(material).or_default() += p.waste_speed() * copies;
+                    }
+                }
+                Node::Building(Building::ExportPort(p)) => {
+                    if let Some(ref input) = p.current_input {
+                        match self.exports.get_mut(&p.name) {
+                            Some(export) => {
+                                // ports sharing a name must carry the same resource
+                                if export.resource == input.resource {
+                                    export.speed += input.speed * copies;
+                                } else {
+                                    self.mismatched.insert(p.name.clone());
+                                }
+                            }
+                            None => {
+                                self.exports.insert(
+                                    p.name.clone(),
+                                    Output {
+                                        speed: input.speed * copies,
+                                        resource: input.resource,
+                                    },
+                                );
+                            }
+                        }
+                    }
+                }
+                Node::Building(Building::ImportPort(p)) => {
+                    *self.importers.entry(p.name.clone()).or_default() += copies;
+                }
+                Node::Building(_) => {}
+            }
+        }
+    }
+
+    /// Items/min uploaded by all Dimensional Depots.
+    pub fn uploaded(&self, material: Material) -> f32 {
+        self.uploaded.get(&material).copied().unwrap_or_default()
+    }
+
+    /// Items/min requested by all Central Storage nodes.
+    pub fn drawn(&self, material: Material) -> f32 {
+        self.drawn.get(&material).copied().unwrap_or_default()
+    }
+
+    /// Points/min awarded by all AWESOME Sinks.
+    pub fn sink_points(&self) -> f32 {
+        self.sink_points
+    }
+
+    /// Minutes until the next coupon when `coupons` were printed already.
+    pub fn minutes_to_coupon(&self, coupons: u32) -> Option<f32> {
+        (self.sink_points > 0.).then(|| round(points_for_coupon(coupons) as f32 / self.sink_points))
+    }
+
+    /// Nuclear waste piling up from all power plants, per hour.
+    pub fn waste_per_hour(&self, material: Material) -> f32 {
+        round(self.waste.get(&material).copied().unwrap_or_default() * 60.)
+    }
+
+    /// Kinds of nuclear waste produced by any power plant, sorted by name.
+    pub fn waste_materials(&self) -> Vec<Material> {
+        let mut materials: Vec<_> = self.waste.keys().copied().collect();
+        materials.sort_by_key(|m| m.to_string());
+        materials
+    }
+
+    /// What each import port with this name gets, the exports are split evenly between them.
+    pub fn import(&self, name: &str) -> Option<Output> {
+        let export = self.exports.get(name)?;
+        let importers = self.importers.get(name).copied().unwrap_or_default();
+        Some(Output {
+            speed: round(export.speed / importers.max(1.)),
+            resource: export.resource,
+        })
+    }
+
+    /// True if export ports with this name carry different resources, only
+    /// the first one is sent.
+    pub fn is_mismatched(&self, name: &str) -> bool {
+        self.mismatched.contains(name)
+    }
 }
 
+/// Hands out what comes from other tabs to the buildings in `graph`, also
+/// inside groups, so tabs that aren't shown stay up to date. Returns whether any changed.
+fn update_logistics(graph: &mut NodeGraph, logistics: &Logistics) -> bool {
+    let mut changed = false;
+    let nodes: Vec<_> = graph.node_indices().collect();
+    for idx in nodes {
+        let node_changed = match &mut graph[idx] {
+            Node::Group { graph, .. } => update_logistics(graph, logistics),
+            Node::Building(Building::CentralStorage(c)) => c.update_available(logistics),
+            Node::Building(Building::ImportPort(p)) => p.update_incoming(logistics),
+            Node::Building(_) => false,
+        };
+        if node_changed {
+            refresh_node(graph, idx);
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// A group opened in its own tab. The group itself is found again through
+/// [`Node::editing`], as its node id may be reused once it is removed.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GroupEdit {
-    pub surface: SurfaceIndex,
-    pub node_idx: NodeIndex,
+    /// Id of the tab holding the group.
     pub source_tab: usize,
+    /// Where the group was when the edit was opened.
     pub node_id: NodeId,
 }
 
+/// The node id of the group being edited in tab `edit_tab`, if `tab` still holds it.
+fn edited_group(tab: &TabState, edit_tab: usize) -> Option<NodeId> {
+    tab.snarl
+        .nodes_ids_data()
+        .find(|(_, node)| tab.graph[node.value].editing() == Some(edit_tab))
+        .map(|(id, _)| id)
+}
+
+fn find_tab(tree: &DockState<TabState>, id: usize) -> Option<(SurfaceIndex, NodeIndex, TabIndex)> {
+    let ((surface, node), _) = tree.iter_all_tabs().find(|(_, tab)| tab.id == id)?;
+    let index = tree[surface][node]
+        .tabs()?
+        .iter()
+        .position(|tab| tab.id == id)?;
+    Some((surface, node, TabIndex(index)))
+}
+
 pub type NodeGraph = petgraph::stable_graph::StableDiGraph<Node, EdgeDetails>;
 pub type GraphIdx = petgraph::graph::NodeIndex<u32>;
 
@@ -45,6 +251,9 @@ pub struct EdgeDetails {
     pub input: usize,
     /// The output number from which the edge is coming
     pub output: usize,
+    /// Height difference in meters between the output and the input, for pipes.
+    #[serde(default)]
+    pub height: f32,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -57,6 +266,27 @@ struct TabState {
     style: SnarlStyle,
     index: (SurfaceIndex, NodeIndex),
     group_edit: Option<GroupEdit>,
+    /// Closes the tab on the next frame, without saving.
+    #[serde(skip)]
+    close: bool,
+    #[serde(skip)]
+    history: History,
+    /// From screen to graph positions, as of the last frame.
+    #[serde(skip)]
+    viewport: Option<RectTransform>,
+}
+
+impl TabState {
+    fn snarl_id_source(&self, snarl_ui_id: Id) -> String {
+        format!("{}-{}", self.name, snarl_ui_id.value())
+    }
+
+    fn selected_nodes(&self, ctx: &egui::Context) -> Vec<NodeId> {
+        match self.snarl_ui_id {
+            Some(id) => Snarl::get_selected_nodes_at(self.snarl_id_source(id), id, ctx),
+            None => Vec::new(),
+        }
+    }
 }
 
 impl egui_dock::TabViewer for TabViewer {
@@ -67,16 +297,98 @@ impl egui_dock::TabViewer for TabViewer {
     }
 
     fn ui(&mut self, ui: &mut egui::Ui, tab: &mut Self::Tab) {
+        if let Some(ref group_edit) = tab.group_edit {
+            let orphaned = self.orphaned_edits.contains(&tab.id);
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(!orphaned, egui::Button::new("Apply"))
+                    .clicked()
+                {
+                    self.group_saves.push((
+                        tab.id,
+                        group_edit.clone(),
+                        tab.graph.clone(),
+                        tab.snarl.clone(),
+                    ));
+                }
+                if ui.button("Discard").clicked() {
+                    self.finished_edits.push((tab.id, group_edit.clone()));
+                    tab.close = true;
+                }
+                if orphaned {
+                    ui.colored_label(
+                        Color32::RED,
+                        "The group was removed, the changes can't be applied",
+                    );
+                }
+            });
+        }
+
         tab.snarl_ui_id = Some(ui.id());
-        let snarl_id_source = format!("{}-{}", tab.name, ui.id().value());
+        let positions: HashMap<_, _> = tab
+            .snarl
+            .nodes_pos_ids()
+            .map(|(id, pos, _)| (id, pos))
+            .collect();
+        let snarl_id_source = tab.snarl_id_source(ui.id());
         let mut viewer = Viewer {
             snarl_id_source: snarl_id_source.clone(),
             snarl_ui_id: Some(ui.id()),
             group_edits: &mut self.group_edits,
+            logistics: &self.logistics,
+            unlocks: &self.unlocks,
+            library: &mut self.library,
+            history: &mut tab.history,
+            clipboard: &mut self.clipboard,
+            search: &mut self.search,
             graph: &mut tab.graph,
-            index: (tab.index.0, tab.index.1, tab.id),
+            tab_id: tab.id,
         };
+        ui.ctx()
+            .data_mut(|d| d.remove::<RectTransform>(viewport_id()));
         tab.snarl.show(&mut viewer, &tab.style, snarl_id_source, ui);
+        tab.viewport = ui.ctx().data(|d| d.get_temp(viewport_id()));
+
+        if let Some(palette) = self.palette.as_mut().filter(|p| p.tab == tab.id) {
+            let mut picked = None;
+            let area = egui::Area::new(Id::new("palette"))
+                .fixed_pos(palette.pos)
+                .order(egui::Order::Foreground)
+                .show(ui.ctx(), |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        picked = search_ui(ui, &mut palette.query, &self.unlocks);
+                    });
+                });
+
+            let escape = ui.input(|i| i.key_pressed(Key::Escape));
+            if let Some(building) = picked {
+                let pos = tab
+                    .viewport
+                    .map(|t| t.transform_pos(palette.pos))
+                    .unwrap_or_default();
+                let label = format!("Add {}", building.name());
+                let graph_idx = tab.graph.add_node(Node::Building(building));
+                let id = tab.snarl.insert_node(pos, graph_idx);
+                let command = Command::added(&tab.graph, &tab.snarl, &[id]);
+                tab.history.push(label, command);
+                self.palette = None;
+            } else if escape || area.response.clicked_elsewhere() {
+                self.palette = None;
+            }
+        }
+
+        let moves: Vec<_> = tab
+            .snarl
+            .nodes_pos_ids()
+            .filter_map(|(id, pos, _)| {
+                let old = *positions.get(&id)?;
+                (old != pos).then_some((id, old, pos))
+            })
+            .collect();
+        if !moves.is_empty() {
+            let time = ui.input(|i| i.time);
+            tab.history.push_merged("Move", time, Command::Move(moves));
+        }
     }
 
     fn on_add(&mut self, surface: SurfaceIndex, node: NodeIndex) {
@@ -87,15 +399,27 @@ impl egui_dock::TabViewer for TabViewer {
         if let Some(group_state) = closing_tab.group_edit.take() {
             let current_graph = closing_tab.graph.clone();
             let current_snarl = closing_tab.snarl.clone();
-            self.group_saves
-                .push((group_state, current_graph, current_snarl));
+            self.group_saves.push((
+                closing_tab.id,
+                group_state.clone(),
+                current_graph,
+                current_snarl,
+            ));
+            self.finished_edits.push((closing_tab.id, group_state));
         }
 
         true
     }
+
+    fn force_close(&mut self, tab: &mut Self::Tab) -> bool {
+        tab.close
+    }
 }
 
 const STORAGE_STRING: &'static str = "satisfactory-designer-tree";
+const UNLOCKS_STORAGE_STRING: &str = "satisfactory-designer-unlocks";
+const COUPONS_STORAGE_STRING: &str = "satisfactory-designer-coupons";
+const LIBRARY_STORAGE_STRING: &str = "satisfactory-designer-library";
 
 impl App {
     pub fn new(cx: &CreationContext) -> Self {
@@ -121,6 +445,9 @@ impl App {
                 style: default_style(),
                 group_edit: None,
                 index: (index.0, index.1),
+                close: false,
+                history: History::default(),
+                viewport: None,
             }])
         }
         let mut tab_viewer = TabViewer::default();
@@ -137,16 +464,69 @@ impl App {
                     // style is not persisted atm
                     // TOOD: store zoom details
                     tab.style = default_style();
+                    // ids of new tabs must not clash with the loaded ones
+                    tab_viewer.current_tab_index = tab_viewer.current_tab_index.max(tab.id + 1);
                 }
                 tree
             }
         };
 
+        if let Some(unlocks) = cx
+            .storage
+            .and_then(|storage| storage.get_string(UNLOCKS_STORAGE_STRING))
+            .and_then(|unlocks| serde_json::from_str(&unlocks).ok())
+        {
+            tab_viewer.unlocks = unlocks;
+        }
+
+        if let Some(coupons) = cx
+            .storage
+            .and_then(|storage| storage.get_string(COUPONS_STORAGE_STRING))
+            .and_then(|coupons| coupons.parse().ok())
+        {
+            tab_viewer.coupons = coupons;
+        }
+
+        if let Some(library) = cx
+            .storage
+            .and_then(|storage| storage.get_string(LIBRARY_STORAGE_STRING))
+            .and_then(|library| serde_json::from_str(&library).ok())
+        {
+            tab_viewer.library = library;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let recipe_db = match RecipeDatabase::load(crate::docs::DOCS_PATH) {
+            Ok(db) => Some(crate::docs::install(db)),
+            Err(err) => {
+                log::info!("recipe database not loaded: {err:#}");
+                None
+            }
+        };
+        #[cfg(target_arch = "wasm32")]
+        let recipe_db = None;
+
+        let unknown_recipes = recipe_db
+            .map(|db: &RecipeDatabase| db.unknown_recipes(recipe_names()))
+            .unwrap_or_default();
+        for name in &unknown_recipes {
+            log::warn!("recipe {name:?} not found in the recipe database");
+        }
+
         App {
             tree,
             show_about: false,
+            show_recipes: false,
+            show_unlocks: false,
+            show_library: false,
+            show_history: false,
+            show_stats: false,
+            savegame_path: String::new(),
+            import_status: None,
             counter: 1,
             tab_viewer,
+            recipe_db,
+            unknown_recipes,
         }
     }
 }
@@ -179,11 +559,202 @@ impl eframe::App for App {
                         ui.close_menu();
                     }
 
+                    if ui.button("Recipe Database").clicked() {
+                        self.show_recipes = true;
+                        ui.close_menu();
+                    }
+
+                    if ui.button("Unlocks").clicked() {
+                        self.show_unlocks = true;
+                        ui.close_menu();
+                    }
+
+                    if ui.button("Blueprints").clicked() {
+                        self.show_library = true;
+                        ui.close_menu();
+                    }
+
+                    if ui.button("History").clicked() {
+                        self.show_history = true;
+                        ui.close_menu();
+                    }
+
+                    if ui.button("Factory Stats").clicked() {
+                        self.show_stats = true;
+                        ui.close_menu();
+                    }
+
                     egui::widgets::global_theme_preference_switch(ui);
                 });
             });
         });
 
+        egui::Window::new("Recipe Database")
+            .open(&mut self.show_recipes)
+            .vscroll(true)
+            .show(ctx, |ui| match self.recipe_db {
+                None => {
+                    ui.label("No recipe database loaded.");
+                    ui.label(
+                        "Copy CommunityResources/Docs/en-US.json from the game to assets/Docs.json",
+                    );
+                }
+                Some(db) => show_recipe_db(ui, db, &self.unknown_recipes),
+            });
+
+        egui::Window::new("Unlocks")
+            .open(&mut self.show_unlocks)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                #[cfg(not(target_arch = "wasm32"))]
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.savegame_path)
+                            .hint_text("Path to a .sav file"),
+                    );
+                    if ui.button("Import from savegame").clicked() {
+                        let status = std::fs::read(&self.savegame_path)
+                            .map_err(anyhow::Error::from)
+                            .and_then(|file| crate::savegame::purchased_schematics(&file))
+                            .map(|schematics| {
+                                self.tab_viewer
+                                    .unlocks
+                                    .import_schematics(&schematics, self.recipe_db)
+                            });
+                        self.import_status = Some(match status {
+                            Ok(count) => format!("Imported {count} alternate recipes"),
+                            Err(err) => format!("Import failed: {err:#}"),
+                        });
+                    }
+                });
+                if let Some(ref status) = self.import_status {
+                    ui.label(status);
+                }
+                show_unlocks(ui, &mut self.tab_viewer.unlocks);
+            });
+
+        if !ctx.wants_keyboard_input() {
+            // check for redo first, undo matches with shift held as well
+            let redo = ctx.input_mut(|i| {
+                i.consume_shortcut(&KeyboardShortcut::new(
+                    Modifiers::COMMAND | Modifiers::SHIFT,
+                    Key::Z,
+                ))
+            });
+            let undo = ctx.input_mut(|i| {
+                i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::Z))
+            });
+            let search = ctx.input_mut(|i| {
+                i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::Space))
+            });
+            let events = ctx.input(|i| i.events.clone());
+            if let Some((_, tab)) = self.tree.find_active_focused() {
+                let typed = events.iter().find_map(|e| match e {
+                    egui::Event::Text(text) if !text.trim().is_empty() => Some(text.clone()),
+                    _ => None,
+                });
+                if search || typed.is_some() {
+                    let pos = ctx
+                        .pointer_latest_pos()
+                        .filter(|pos| tab.viewport.is_some_and(|t| t.from().contains(*pos)))
+                        .or_else(|| tab.viewport.map(|t| t.from().center()))
+                        .unwrap_or_default();
+                    self.tab_viewer.palette = Some(Palette {
+                        tab: tab.id,
+                        pos,
+                        query: typed.unwrap_or_default(),
+                    });
+                }
+
+                if redo {
+                    tab.history.redo(&mut tab.graph, &mut tab.snarl);
+                } else if undo {
+                    tab.history.undo(&mut tab.graph, &mut tab.snarl);
+                }
+
+                for event in events {
+                    match event {
+                        egui::Event::Copy | egui::Event::Cut => {
+                            let selected = tab.selected_nodes(ctx);
+                            if selected.is_empty() {
+                                continue;
+                            }
+                            let clipboard = if event == egui::Event::Cut {
+                                let (clipboard, removal) =
+                                    Clipboard::cut(&mut tab.graph, &mut tab.snarl, &selected);
+                                tab.history.push("Cut", removal);
+                                clipboard
+                            } else {
+                                Clipboard::copy(&tab.graph, &tab.snarl, &selected)
+                            };
+                            ctx.copy_text(clipboard.to_json());
+                            self.tab_viewer.clipboard = Some(clipboard);
+                        }
+                        egui::Event::Paste(text) => {
+                            // other text on the clipboard is ignored
+                            if let Some(clipboard) = Clipboard::from_json(&text) {
+                                // at the cursor, if it is over the graph
+                                let cursor = ctx.pointer_latest_pos().and_then(|pos| {
+                                    let t = tab.viewport?;
+                                    t.from().contains(pos).then(|| t.transform_pos(pos))
+                                });
+                                let ids = match cursor {
+                                    Some(pos) => {
+                                        clipboard.paste(&mut tab.graph, &mut tab.snarl, pos)
+                                    }
+                                    None => clipboard.paste_offset(&mut tab.graph, &mut tab.snarl),
+                                };
+                                let command = Command::added(&tab.graph, &tab.snarl, &ids);
+                                tab.history.push("Paste", command);
+                                self.tab_viewer.clipboard = Some(clipboard);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        egui::Window::new("History")
+            .open(&mut self.show_history)
+            .vscroll(true)
+            .show(ctx, |ui| match self.tree.find_active_focused() {
+                Some((_, tab)) => show_history(ui, tab),
+                None => {
+                    ui.label("No tab selected.");
+                }
+            });
+
+        egui::Window::new("Factory Stats")
+            .open(&mut self.show_stats)
+            .show(ctx, |ui| {
+                show_stats(ui, &self.tab_viewer.logistics, &mut self.tab_viewer.coupons)
+            });
+
+        egui::Window::new("Blueprints")
+            .open(&mut self.show_library)
+            .vscroll(true)
+            .show(ctx, |ui| show_library(ui, &mut self.tab_viewer.library));
+
+        self.tab_viewer.logistics =
+            Logistics::from_tabs(self.tree.iter_all_tabs().map(|(_, tab)| tab));
+        for (_, tab) in self.tree.iter_all_tabs_mut() {
+            update_logistics(&mut tab.graph, &self.tab_viewer.logistics);
+        }
+        self.tab_viewer.orphaned_edits = self
+            .tree
+            .iter_all_tabs()
+            .filter_map(|(_, tab)| Some((tab.id, tab.group_edit.as_ref()?)))
+            .filter(|(id, group_edit)| {
+                let source = self
+                    .tree
+                    .iter_all_tabs()
+                    .find(|(_, tab)| tab.id == group_edit.source_tab);
+                source.and_then(|(_, tab)| edited_group(tab, *id)).is_none()
+            })
+            .map(|(id, _)| id)
+            .collect();
+
         egui::CentralPanel::default()
             .frame(egui::Frame::central_panel(&ctx.style()).inner_margin(0.))
             .show(ctx, |ui| {
@@ -196,17 +767,50 @@ impl eframe::App for App {
                     .group_edits
                     .drain(..)
                     .for_each(|(group_state, graph, snarl)| {
-                        self.tree.set_focused_node_and_surface((
-                            group_state.surface,
-                            group_state.node_idx,
-                        ));
+                        let Some((surface, node, _)) = find_tab(&self.tree, group_state.source_tab)
+                        else {
+                            return;
+                        };
+                        let (_, source_tab) = self
+                            .tree
+                            .iter_all_tabs()
+                            .find(|(_, tab)| tab.id == group_state.source_tab)
+                            .unwrap();
+                        let Some(&graph_idx) = source_tab.snarl.get_node(group_state.node_id)
+                        else {
+                            return;
+                        };
+
+                        // show the tab already editing this group instead
+                        if let Some(edit_tab) = source_tab.graph[graph_idx].editing() {
+                            let open = self.tree.iter_all_tabs().any(|(_, tab)| {
+                                tab.id == edit_tab
+                                    && tab.group_edit.as_ref().map(|e| e.source_tab)
+                                        == Some(group_state.source_tab)
+                            });
+                            if open {
+                                if let Some(location) = find_tab(&self.tree, edit_tab) {
+                                    self.tree.set_active_tab(location);
+                                }
+                                return;
+                            }
+                        }
+
+                        let tab_id = self.tab_viewer.current_tab_index;
+                        self.tab_viewer.current_tab_index += 1;
+                        let (_, source_tab) = self
+                            .tree
+                            .iter_all_tabs_mut()
+                            .find(|(_, tab)| tab.id == group_state.source_tab)
+                            .unwrap();
+                        source_tab.graph[graph_idx].set_editing(Some(tab_id));
+
+                        self.tree.set_focused_node_and_surface((surface, node));
                         let mut style = SnarlStyle::new();
                         style
                             .bg_pattern
                             .replace(BackgroundPattern::custom(dot_background));
 
-                        let tab_id = self.tab_viewer.current_tab_index;
-                        self.tab_viewer.current_tab_index += 1;
                         self.tree.push_to_focused_leaf(TabState {
                             name: format!("Editing Group {}", group_state.node_id.0),
                             id: tab_id,
@@ -215,66 +819,100 @@ impl eframe::App for App {
                             snarl,
                             style,
                             group_edit: Some(group_state.clone()),
-                            index: (group_state.surface, group_state.node_idx),
+                            index: (surface, node),
+                            close: false,
+                            history: History::default(),
+                            viewport: None,
                         });
                     });
 
+                let mut changed_blueprints = Vec::new();
                 self.tab_viewer.group_saves.drain(..).for_each(
-                    |(group_state, group_graph, group_snarl)| {
-                        // Grap the current tab
-                        let tab = &mut self.tree[group_state.surface][group_state.node_idx];
-                        match tab {
-                            egui_dock::Node::Leaf { tabs, .. } => {
-                                let source_tab = &mut tabs[group_state.source_tab];
-
-                                let node_info =
-                                    source_tab.snarl.get_node_info(group_state.node_id).unwrap();
-                                let graph_idx = node_info.value;
-                                let node = source_tab.graph.node_weight_mut(graph_idx).unwrap();
-
-                                match node {
-                                    Node::Building(_) => unreachable!("invalid group building"),
-                                    Node::Group {
-                                        snarl,
-                                        graph,
-                                        inputs,
-                                        outputs,
-                                    } => {
-                                        *graph = group_graph;
-                                        *snarl = group_snarl;
-
-                                        // update inputs and outputs
-                                        inputs.clear();
-                                        outputs.clear();
-                                        for (id, node) in snarl.nodes_ids_data() {
-                                            let graph_idx = node.value;
-                                            let node = graph.node_weight(graph_idx).unwrap();
-
-                                            for i in 0..node.outputs() {
-                                                outputs.push((
-                                                    id,
-                                                    graph_idx,
-                                                    i,
-                                                    node.current_output(i),
-                                                ));
-                                            }
-                                            for i in 0..node.inputs() {
-                                                inputs.push((
-                                                    id,
-                                                    graph_idx,
-                                                    i,
-                                                    node.current_input(i),
-                                                ));
-                                            }
-                                        }
-                                    }
-                                }
+                    |(edit_tab, group_state, group_graph, group_snarl)| {
+                        // The tab the group was opened from, which may be
+                        // editing a group itself.
+                        let source_tab = self
+                            .tree
+                            .iter_all_tabs_mut()
+                            .map(|(_, tab)| tab)
+                            .find(|tab| tab.id == group_state.source_tab);
+                        let Some(source_tab) = source_tab else {
+                            log::warn!("group edit dropped, its source tab was closed");
+                            return;
+                        };
+                        let Some(node_id) = edited_group(source_tab, edit_tab) else {
+                            log::warn!("group edit dropped, the group was removed");
+                            return;
+                        };
+                        let graph_idx = source_tab.snarl[node_id];
+                        let node = &source_tab.graph[graph_idx];
+                        let regrouped = node.regroup(group_graph, group_snarl);
+                        let (
+                            Node::Group {
+                                inputs, outputs, ..
+                            },
+                            Node::Group {
+                                inputs: new_inputs,
+                                outputs: new_outputs,
+                                ..
+                            },
+                        ) = (node, &regrouped)
+                        else {
+                            return;
+                        };
+                        // wires on pins the edited group doesn't show anymore
+                        let dropped = wires_at(
+                            &source_tab.graph,
+                            &source_tab.snarl,
+                            node_id,
+                            &lost_pins(inputs, new_inputs),
+                            &lost_pins(outputs, new_outputs),
+                        );
+                        let command = Command::set_node(
+                            &source_tab.graph,
+                            &source_tab.snarl,
+                            node_id,
+                            regrouped,
+                        )
+                        .after_dropping(dropped);
+                        source_tab.history.apply(
+                            "Edit Group",
+                            command,
+                            &mut source_tab.graph,
+                            &mut source_tab.snarl,
+                        );
+
+                        let node = &source_tab.graph[graph_idx];
+                        if let Some(id) = node.blueprint() {
+                            if self.tab_viewer.library.update(id, node) {
+                                changed_blueprints.push(id);
                             }
-                            _ => unreachable!("invalid node"),
                         }
                     },
                 );
 
+                // bring every other instance in line with the edited one
+                for id in changed_blueprints {
+                    let blueprint = self.tab_viewer.library.get(id).unwrap();
+                    for (_, tab) in self.tree.iter_all_tabs_mut() {
+                        update_instances(&mut tab.graph, &mut tab.snarl, blueprint);
+                    }
+                }
+
+                for (edit_tab, group_state) in self.tab_viewer.finished_edits.drain(..) {
+                    let source_tab = self
+                        .tree
+                        .iter_all_tabs_mut()
+                        .map(|(_, tab)| tab)
+                        .find(|tab| tab.id == group_state.source_tab);
+                    if let Some(source_tab) = source_tab {
+                        if let Some(node_id) = edited_group(source_tab, edit_tab) {
+                            let graph_idx = source_tab.snarl[node_id];
+                            source_tab.graph[graph_idx].set_editing(None);
+                        }
+                    }
+                }
+
                 self.tab_viewer
                     .added_nodes
                     .drain(..)
@@ -296,6 +934,9 @@ impl eframe::App for App {
                             style,
                             group_edit: None,
                             index: (surface, node),
+                            close: false,
+                            history: History::default(),
+                            viewport: None,
                         });
                         self.counter += 1;
                     });
@@ -324,7 +965,177 @@ impl eframe::App for App {
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         let tree = serde_json::to_string(&self.tree).unwrap();
         storage.set_string(STORAGE_STRING, tree);
+        let unlocks = serde_json::to_string(&self.tab_viewer.unlocks).unwrap();
+        storage.set_string(UNLOCKS_STORAGE_STRING, unlocks);
+        storage.set_string(COUPONS_STORAGE_STRING, self.tab_viewer.coupons.to_string());
+        let library = serde_json::to_string(&self.tab_viewer.library).unwrap();
+        storage.set_string(LIBRARY_STORAGE_STRING, library);
+    }
+}
+
+fn show_recipe_db(ui: &mut Ui, db: &RecipeDatabase, unknown_recipes: &[String]) {
+    ui.label(format!(
+        "{} recipes, {} items",
+        db.recipes.len(),
+        db.items.len()
+    ));
+    for name in unknown_recipes {
+        ui.colored_label(Color32::RED, format!("Unknown recipe: {name}"));
+    }
+    ui.separator();
+
+    egui::Grid::new("recipe_db").striped(true).show(ui, |ui| {
+        for recipe in &db.recipes {
+            let rates = |amounts: &[crate::docs::ItemAmount]| {
+                amounts
+                    .iter()
+                    .map(|a| format!("{}/min {}", db.per_minute(recipe, a), db.item_name(&a.item)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            let buildings = recipe
+                .produced_in
+                .iter()
+                .map(|b| db.building_name(b))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            if recipe.alternate {
+                ui.label(RichText::new(recipe.short_name()).italics())
+                    .on_hover_text(&recipe.class_name);
+            } else {
+                ui.label(recipe.short_name())
+                    .on_hover_text(&recipe.class_name);
+            }
+            ui.label(buildings);
+            ui.label(format!("{}s", recipe.duration));
+            ui.label(rates(&recipe.ingredients));
+            ui.label(rates(&recipe.products));
+            ui.end_row();
+        }
+    });
+}
+
+/// Totals over all tabs.
+fn show_stats(ui: &mut Ui, logistics: &Logistics, coupons: &mut u32) {
+    ui.heading("AWESOME Sink");
+    let total = logistics.sink_points();
+    ui.label(format!("All sinks: {} points/min", total));
+    ui.horizontal(|ui| {
+        ui.add(egui::DragValue::new(coupons).range(0..=u32::MAX));
+        ui.label("Coupons printed");
+    });
+    if let Some(minutes) = logistics.minutes_to_coupon(*coupons) {
+        ui.label(format!(
+            "Next coupon ({} points) in {} min",
+            points_for_coupon(*coupons),
+            minutes
+        ));
+    }
+    ui.separator();
+
+    ui.heading("Nuclear Waste");
+    let materials = logistics.waste_materials();
+    if materials.is_empty() {
+        ui.label("No waste produced");
+    }
+    for material in materials {
+        ui.label(format!(
+            "{}: {}/h",
+            material,
+            logistics.waste_per_hour(material)
+        ));
+    }
+}
+
+/// Undo and redo of the focused tab, clicking an edit goes back to just after it.
+fn show_history(ui: &mut Ui, tab: &mut TabState) {
+    ui.horizontal(|ui| {
+        if ui.button("Undo").clicked() {
+            tab.history.undo(&mut tab.graph, &mut tab.snarl);
+        }
+        if ui.button("Redo").clicked() {
+            tab.history.redo(&mut tab.graph, &mut tab.snarl);
+        }
+    });
+    ui.separator();
+
+    let mut undo = 0;
+    let mut redo = 0;
+    let done = tab.history.undo_labels().count();
+    for (i, label) in tab.history.undo_labels().enumerate() {
+        if ui.selectable_label(i + 1 == done, label).clicked() {
+            undo = done - i - 1;
+        }
+    }
+    for (i, label) in tab.history.redo_labels().enumerate() {
+        if ui
+            .selectable_label(false, RichText::new(label).weak())
+            .clicked()
+        {
+            redo = i + 1;
+        }
+    }
+    for _ in 0..undo {
+        tab.history.undo(&mut tab.graph, &mut tab.snarl);
+    }
+    for _ in 0..redo {
+        tab.history.redo(&mut tab.graph, &mut tab.snarl);
+    }
+}
+
+/// Rename and delete blueprints. Deleting one leaves its instances as plain groups.
+fn show_library(ui: &mut Ui, library: &mut Library) {
+    if library.blueprints.is_empty() {
+        ui.label("Save a group as a blueprint from its menu to place it again.");
+        return;
     }
+    let mut removed = None;
+    egui::Grid::new("library").num_columns(2).show(ui, |ui| {
+        for blueprint in &mut library.blueprints {
+            ui.text_edit_singleline(&mut blueprint.name);
+            if ui.button("Delete").clicked() {
+                removed = Some(blueprint.id);
+            }
+            ui.end_row();
+        }
+    });
+    if let Some(id) = removed {
+        library.remove(id);
+    }
+}
+
+fn show_unlocks(ui: &mut Ui, unlocks: &mut Unlocks) {
+    ui.horizontal(|ui| {
+        ui.add(egui::Slider::new(&mut unlocks.tier, 0..=MAX_TIER));
+        ui.label("Current tier");
+    });
+    ui.horizontal(|ui| {
+        ui.label(format!(
+            "{}/{} alternates unlocked",
+            unlocks.alternates.len(),
+            ALTERNATE_RECIPES.len()
+        ));
+        if ui.button("Unlock all").clicked() {
+            unlocks.alternates = Unlocks::all().alternates;
+        }
+        if ui.button("Lock all").clicked() {
+            unlocks.alternates.clear();
+        }
+    });
+    ui.separator();
+
+    egui::Grid::new("unlocks").num_columns(3).show(ui, |ui| {
+        for (i, name) in ALTERNATE_RECIPES.iter().enumerate() {
+            let mut unlocked = unlocks.is_unlocked(name);
+            if ui.checkbox(&mut unlocked, *name).changed() {
+                unlocks.set_unlocked(name, unlocked);
+            }
+            if i % 3 == 2 {
+                ui.end_row();
+            }
+        }
+    });
 }
 
 fn default_style() -> SnarlStyle {
@@ -335,6 +1146,11 @@ fn default_style() -> SnarlStyle {
     style
 }
 
+/// Where [`dot_background`] leaves the viewport of the graph it drew.
+fn viewport_id() -> Id {
+    Id::new("snarl-viewport")
+}
+
 fn dot_background(_style: &SnarlStyle, viewport: &Viewport, ui: &mut Ui) {
     // Dot grid background
 
@@ -351,6 +1167,14 @@ fn dot_background(_style: &SnarlStyle, viewport: &Viewport, ui: &mut Ui) {
         viewport.screen_pos_to_graph(viewport.rect.max),
     );
 
+    // for placing buildings at the pointer
+    ui.ctx().data_mut(|d| {
+        d.insert_temp(
+            viewport_id(),
+            RectTransform::from_to(viewport.rect, graph_viewport),
+        )
+    });
+
     let pattern_bounds = graph_viewport.rotate_bb(rot_inv);
 
     let min_x = (pattern_bounds.min.x / spacing.x).ceil();
@@ -374,3 +1198,110 @@ fn dot_background(_style: &SnarlStyle, viewport: &Viewport, ui: &mut Ui) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buildings::{
+        AwesomeSink, ExportPort, Fluid, ImportPort, NuclearFuel, NuclearPowerPlant,
+    };
+    use crate::node::{Input, Resource};
+
+    fn export(name: &str, material: Material, speed: f32) -> Node {
+        Node::Building(Building::ExportPort(ExportPort {
+            name: name.to_string(),
+            fluid: false,
+            current_input: Some(Input {
+                speed,
+                resource: Resource::Material(material),
+            }),
+        }))
+    }
+
+    fn import(name: &str) -> Node {
+        Node::Building(Building::ImportPort(ImportPort {
+            name: name.to_string(),
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn test_imports_split_export() {
+        let mut graph = NodeGraph::default();
+        graph.add_node(export("screws", Material::Screw, 90.));
+        let a = graph.add_node(import("screws"));
+        let b = graph.add_node(import("screws"));
+
+        let mut logistics = Logistics::default();
+        logistics.add_graph(&graph, 1.);
+        assert!(update_logistics(&mut graph, &logistics));
+        for idx in [a, b] {
+            assert_eq!(graph[idx].current_output(0).unwrap().speed, 45.);
+        }
+        assert!(!logistics.is_mismatched("screws"));
+
+        graph.add_node(export("screws", Material::IronRod, 30.));
+        let mut logistics = Logistics::default();
+        logistics.add_graph(&graph, 1.);
+        assert!(logistics.is_mismatched("screws"));
+        assert!(!update_logistics(&mut graph, &logistics));
+    }
+
+    #[test]
+    fn test_sink_totals() {
+        let sink = Node::Building(Building::AwesomeSink(AwesomeSink {
+            current_input: Some(Input {
+                speed: 30.,
+                resource: Resource::Material(Material::ReinforcedIronPlate),
+            }),
+        }));
+        let mut inner = NodeGraph::default();
+        inner.add_node(sink.clone());
+        let mut graph = NodeGraph::default();
+        graph.add_node(sink);
+        graph.add_node(Node::Group {
+            snarl: Snarl::new(),
+            graph: inner,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            blueprint: None,
+            copies: 2,
+            editing: None,
+        });
+
+        let mut logistics = Logistics::default();
+        logistics.add_graph(&graph, 1.);
+        assert_eq!(logistics.sink_points(), 10800.);
+        // 9 coupons printed, the next needs 5500 points
+        assert_eq!(logistics.minutes_to_coupon(9), Some(round(5500. / 10800.)));
+        assert_eq!(Logistics::default().minutes_to_coupon(0), None);
+    }
+
+    #[test]
+    fn test_waste_totals() {
+        let plant = |fuel: NuclearFuel, rods: f32| {
+            Node::Building(Building::NuclearPowerPlant(NuclearPowerPlant {
+                fuel: Some(fuel),
+                current_input_fuel: Some(Input {
+                    speed: rods,
+                    resource: Resource::Material(fuel.fuel_rod()),
+                }),
+                current_input_water: Some(Input {
+                    speed: 240.,
+                    resource: Resource::Fluid(Fluid::Water),
+                }),
+                ..Default::default()
+            }))
+        };
+        let mut graph = NodeGraph::default();
+        graph.add_node(plant(NuclearFuel::Uranium, 0.2));
+        graph.add_node(plant(NuclearFuel::Uranium, 0.2));
+        graph.add_node(plant(NuclearFuel::Ficsonium, 1.));
+
+        let mut logistics = Logistics::default();
+        logistics.add_graph(&graph, 1.);
+        // 10 waste/min for each uranium plant
+        assert_eq!(logistics.waste_per_hour(Material::UraniumWaste), 1200.);
+        assert_eq!(logistics.waste_materials(), vec![Material::UraniumWaste]);
+    }
+}
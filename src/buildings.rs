@@ -1,71 +1,308 @@
-use egui::Color32;
+use egui::{Color32, Ui};
 
 mod assembler;
 mod blender;
 mod constructor;
 mod converter;
+mod dimensional_depot;
 mod encoder;
 mod foundry;
 mod manufacturer;
 mod merger;
 mod miner;
+mod nuclear_power_plant;
 mod oil_extractor;
 mod packager;
 mod particle_accelerator;
 mod pipeline_junction;
+mod pipeline_pump;
+mod port;
 mod refinery;
+mod resource_well_pressurizer;
 mod sink;
 mod smelter;
 mod splitter;
 mod storage_container;
 mod water_extractor;
 
-use crate::node::{Input, Output};
+use crate::app::Logistics;
+use crate::docs::RecipeDatabase;
+use crate::node::{Input, Output, Resource};
+use crate::unlocks::{Gated, Unlock, Unlocks};
 use crate::util::load_img;
 
 pub use self::assembler::Assembler;
 pub use self::blender::Blender;
 pub use self::constructor::Constructor;
 pub use self::converter::Converter;
+pub use self::dimensional_depot::{CentralStorage, DimensionalDepot};
 pub use self::encoder::QuantumEncoder;
 pub use self::foundry::Foundry;
 pub use self::manufacturer::Manufacturer;
 pub use self::merger::Merger;
 pub use self::miner::{Miner, MinerLevel, ResourcePurity};
+#[cfg(test)]
+pub use self::nuclear_power_plant::NuclearFuel;
+pub use self::nuclear_power_plant::NuclearPowerPlant;
 pub use self::oil_extractor::OilExtractor;
 pub use self::packager::Packager;
 pub use self::particle_accelerator::ParticleAccelerator;
 pub use self::pipeline_junction::PipelineJunction;
+pub use self::pipeline_pump::{PipelinePump, BUILDING_HEAD_LIFT};
+pub use self::port::{ExportPort, ImportPort, Transport};
 pub use self::refinery::Refinery;
-pub use self::sink::AwesomeSink;
+pub use self::resource_well_pressurizer::ResourceWellPressurizer;
+pub use self::sink::{points_for_coupon, AwesomeSink};
 pub use self::smelter::Smelter;
 pub use self::splitter::Splitter;
 pub use self::storage_container::StorageContainer;
 pub use self::water_extractor::WaterExtractor;
 
-#[derive(Clone, serde::Serialize, serde::Deserialize, Debug)]
-pub enum Building {
-    Miner(Miner),
-    Smelter(Smelter),
-    Splitter(Splitter),
-    Merger(Merger),
-    Constructor(Constructor),
-    StorageContainer(StorageContainer),
-    WaterExtractor(WaterExtractor),
-    OilExtractor(OilExtractor),
-    Packager(Packager),
-    Refinery(Refinery),
-    Foundry(Foundry),
-    Assembler(Assembler),
-    PipelineJunction(PipelineJunction),
-    Manufacturer(Manufacturer),
-    AwesomeSink(AwesomeSink),
-    Blender(Blender),
-    ParticleAccelerator(ParticleAccelerator),
-    QuantumEncoder(QuantumEncoder),
-    Converter(Converter),
+/// Names of all hand-coded recipes, to check them against the game data.
+pub fn recipe_names() -> Vec<String> {
+    fn names<T: Selectable>() -> impl Iterator<Item = String> {
+        T::VARIANTS.iter().map(|r| r.name())
+    }
+
+    names::<smelter::SmelterRecipe>()
+        .chain(names::<constructor::ConstructorRecipe>())
+        .chain(names::<assembler::AssemblerRecipe>())
+        .chain(names::<manufacturer::ManufacturerRecipe>())
+        .chain(names::<foundry::FoundryRecipe>())
+        .chain(names::<refinery::RefineryRecipe>())
+        .chain(names::<packager::PackagerRecipe>())
+        .chain(names::<blender::BlenderRecipe>())
+        .chain(names::<particle_accelerator::ParticleAcceleratorRecipe>())
+        .chain(names::<encoder::QuantumEncoderRecipe>())
+        .chain(names::<converter::ConverterRecipe>())
+        .collect()
+}
+
+/// Buildings with each of their recipes or resources picked, with the name of
+/// what was picked to check that it is unlocked.
+pub fn presets() -> Vec<(Building, String)> {
+    fn with<T: Selectable>(
+        make: impl Fn(T) -> Building,
+    ) -> impl Iterator<Item = (Building, String)> {
+        T::VARIANTS.iter().map(move |r| (make(r.clone()), r.name()))
+    }
+
+    macro_rules! recipes {
+        ($building:ident) => {
+            with(|recipe| {
+                Building::$building($building {
+                    recipe: Some(recipe),
+                    ..Default::default()
+                })
+            })
+        };
+    }
+
+    with(|resource| {
+        Building::Miner(Miner {
+            resource: Some(resource),
+            ..Default::default()
+        })
+    })
+    .chain(recipes!(Smelter))
+    .chain(recipes!(Constructor))
+    .chain(recipes!(Assembler))
+    .chain(recipes!(Manufacturer))
+    .chain(recipes!(Foundry))
+    .chain(recipes!(Refinery))
+    .chain(recipes!(Packager))
+    .chain(recipes!(Blender))
+    .chain(recipes!(ParticleAccelerator))
+    .chain(recipes!(QuantumEncoder))
+    .chain(recipes!(Converter))
+    .collect()
+}
+
+/// What a building expects at an input, or can deliver at most at an output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PinSpec {
+    /// `None` until a recipe or resource is selected.
+    pub resource: Option<Resource>,
+    /// Items/min (m^3/min for fluids).
+    pub max_speed: f32,
+}
+
+/// What a building's body UI can see of, and report back to, the graph.
+pub struct BodyContext<'a> {
+    pub logistics: &'a Logistics,
+    pub unlocks: &'a Unlocks,
+    /// An input that changed its type, its wires get dropped.
+    pub removed_input: Option<usize>,
+    /// An output that changed its type or went away, its wires get dropped.
+    pub removed_output: Option<usize>,
+}
+
+/// Everything the graph and the node editor need to know about a building.
+///
+/// Adding a building means implementing this in its own file, listing it
+/// in [`buildings!`] and adding it to the graph menu, and to [`presets`] if it
+/// has recipes.
+pub trait Machine {
+    fn header_image(&self) -> String;
+    fn name(&self) -> String;
+    fn description(&self) -> String;
+
+    fn num_inputs(&self) -> usize;
+    fn num_outputs(&self) -> usize;
+    fn input_resource(&self, input_id: usize) -> crate::node::ResourceType;
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType;
+
+    /// Shown next to an input, `None` if it takes whatever arrives.
+    fn input_spec(&self, _input_id: usize) -> Option<PinSpec> {
+        None
+    }
+
+    /// Shown next to an output, `None` if it only passes on what arrives.
+    fn output_spec(&self, _output_id: usize) -> Option<PinSpec> {
+        None
+    }
+
+    fn current_input(&self, _input_id: usize) -> Option<Input> {
+        unreachable!("no inputs")
+    }
+
+    fn current_input_mut(&mut self, _input_id: usize) -> &mut Option<Input> {
+        unreachable!("no inputs")
+    }
+
+    fn set_current_input(&mut self, input: Output, input_id: usize) {
+        self.current_input_mut(input_id).replace(input.into());
+    }
+
+    fn clear_current_input(&mut self, input_id: usize) {
+        *self.current_input_mut(input_id) = None;
+    }
+
+    fn current_output(&self, _output_id: usize) -> Option<Output> {
+        None
+    }
+
+    /// Track wires on outputs, for buildings that split between them.
+    fn set_output_connected(&mut self, _output_id: usize, _connected: bool) {}
+
+    /// True if the output delivers less than is sent to it, or than is produced.
+    fn is_output_limited(&self, _output_id: usize) -> bool {
+        false
+    }
+
+    /// Head lift in meters provided at the given fluid output.
+    fn head_lift(&self, _output_id: usize) -> f32 {
+        BUILDING_HEAD_LIFT
+    }
+
+    /// Fluid nodes that only route fluids, without pushing them.
+    fn is_fluid_passthrough(&self) -> bool {
+        false
+    }
+
+    /// The milestone or research that makes this building available.
+    fn unlocked_by(&self) -> Unlock {
+        Unlock::ONBOARDING
+    }
+
+    /// Clock speed in percent, for buildings that can be under- and overclocked.
+    fn clock_speed(&self) -> Option<f32> {
+        None
+    }
+
+    /// The clock speed at which the connected inputs are used up exactly,
+    /// limited to what the building supports. `None` if nothing is connected.
+    fn input_limited_clock(&self) -> Option<f32> {
+        let clock = self.clock_speed()?;
+        let ratio = (0..self.num_inputs())
+            .filter_map(|i| {
+                let spec = self.input_spec(i)?;
+                let resource = spec.resource?;
+                if spec.max_speed <= 0. {
+                    return None;
+                }
+                let input = self.current_input(i)?;
+                let supplied = if input.resource == resource {
+                    input.speed
+                } else {
+                    0.
+                };
+                Some(supplied / spec.max_speed)
+            })
+            .reduce(f32::min)?;
+        Some(round(clock * ratio).clamp(MIN_CLOCK_SPEED, MAX_CLOCK_SPEED))
+    }
+
+    /// Settings in the node body, returns true if anything changed.
+    fn show_body(&mut self, _ui: &mut Ui, _scale: f32, _cx: &mut BodyContext) -> bool {
+        false
+    }
+}
+
+macro_rules! buildings {
+    ($($name:ident),* $(,)?) => {
+        #[derive(Clone, serde::Serialize, serde::Deserialize, Debug)]
+        pub enum Building {
+            $($name($name),)*
+        }
+
+        impl Building {
+            /// One of each building, with nothing picked yet.
+            pub fn all() -> Vec<Self> {
+                vec![$(Self::$name(Default::default()),)*]
+            }
+
+            /// Clone, but with caches reset
+            pub fn clear_clone(&self) -> Self {
+                match self {
+                    $(Self::$name(b) => Self::$name(b.clear_clone()),)*
+                }
+            }
+
+            pub fn machine(&self) -> &dyn Machine {
+                match self {
+                    $(Self::$name(b) => b,)*
+                }
+            }
+
+            pub fn machine_mut(&mut self) -> &mut dyn Machine {
+                match self {
+                    $(Self::$name(b) => b,)*
+                }
+            }
+        }
+    };
 }
 
+buildings!(
+    Miner,
+    Smelter,
+    Splitter,
+    Merger,
+    Constructor,
+    StorageContainer,
+    WaterExtractor,
+    OilExtractor,
+    ResourceWellPressurizer,
+    Packager,
+    Refinery,
+    Foundry,
+    Assembler,
+    PipelineJunction,
+    PipelinePump,
+    Manufacturer,
+    AwesomeSink,
+    DimensionalDepot,
+    CentralStorage,
+    ExportPort,
+    ImportPort,
+    Blender,
+    ParticleAccelerator,
+    QuantumEncoder,
+    Converter,
+    NuclearPowerPlant,
+);
+
 #[derive(
     Debug,
     Clone,
@@ -143,6 +380,7 @@ impl ResourceType {
     serde::Deserialize,
     PartialEq,
     Eq,
+    Hash,
     strum::Display,
     strum::VariantArray,
 )]
@@ -251,11 +489,11 @@ pub enum Material {
     Coal,
     #[strum(to_string = "Quickwire")]
     Quickwire,
-    #[strum(to_string = "Packaged Alumnia Solution")]
+    #[strum(to_string = "Packaged Alumina Solution")]
     PackagedAluminaSolution,
     #[strum(to_string = "Packaged Fuel")]
     PackagedFuel,
-    #[strum(to_string = "Packaged HeavyOil Residue")]
+    #[strum(to_string = "Packaged Heavy Oil Residue")]
     PackagedHeavyOilResidue,
     #[strum(to_string = "Packaged Ionized Fuel")]
     PackagedIonizedFuel,
@@ -311,7 +549,7 @@ pub enum Material {
     Nobelisk,
     #[strum(to_string = "Electromagnetic Control Rod")]
     ElectromagneticControlRod,
-    #[strum(to_string = "EncasedIndustrial Beam")]
+    #[strum(to_string = "Encased Industrial Beam")]
     EncasedIndustrialBeam,
     #[strum(to_string = "Plutonium Pellet")]
     PlutoniumPellet,
@@ -375,11 +613,11 @@ pub enum Material {
     SuperpositionOscillator,
     #[strum(to_string = "Gas Filter")]
     GasFilter,
-    #[strum(to_string = "Iodine Infused Filter")]
+    #[strum(to_string = "Iodine-Infused Filter")]
     IodineInfusedFilter,
     #[strum(to_string = "Modular Engine")]
     ModularEngine,
-    #[strum(to_string = "Encased UraniumCell")]
+    #[strum(to_string = "Encased Uranium Cell")]
     EncasedUraniumCell,
     #[strum(to_string = "Nuke Nobelisk")]
     NukeNobelisk,
@@ -393,7 +631,7 @@ pub enum Material {
     FusedModularFrame,
     #[strum(to_string = "Turbo Rifle Ammo")]
     TurboRifleAmmo,
-    #[strum(to_string = "Uranium FuelRod")]
+    #[strum(to_string = "Uranium Fuel Rod")]
     UraniumFuelRod,
     #[strum(to_string = "Battery")]
     Battery,
@@ -574,7 +812,178 @@ impl Selectable for Material {
     }
 }
 
+/// What the stats, power and storage views need to know about a material or fluid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metadata {
+    /// Items per inventory slot, `None` for fluids.
+    pub stack_size: Option<usize>,
+    /// AWESOME Sink points per item, `None` if it can't be sunk.
+    pub sink_points: Option<u32>,
+    /// MJ released per item or m^3 when burned, `None` if it isn't a fuel.
+    pub energy: Option<f32>,
+    /// Radioactive decay per item, `None` if it isn't radioactive.
+    pub radioactivity: Option<f32>,
+    /// The canister or tank a fluid is packaged into.
+    pub packaged: Option<Material>,
+    /// The fluid a packaged item holds.
+    pub unpackaged: Option<Fluid>,
+}
+
 impl Material {
+    /// Class of the item in the game's `Docs.json`, see [`crate::docs`].
+    pub fn class_name(&self) -> &'static str {
+        match self {
+            Self::CopperOre => "Desc_OreCopper_C",
+            Self::IronOre => "Desc_OreIron_C",
+            Self::CateriumOre => "Desc_OreGold_C",
+            Self::CopperIngot => "Desc_CopperIngot_C",
+            Self::IronIngot => "Desc_IronIngot_C",
+            Self::CateriumIngot => "Desc_GoldIngot_C",
+            Self::AluminumScrap => "Desc_AluminumScrap_C",
+            Self::AluminumIngot => "Desc_AluminumIngot_C",
+            Self::Bauxite => "Desc_OreBauxite_C",
+            Self::Limestone => "Desc_Stone_C",
+            Self::RawQuartz => "Desc_RawQuartz_C",
+            Self::Sulfur => "Desc_Sulfur_C",
+            Self::Uranium => "Desc_OreUranium_C",
+            Self::AlienProtein => "Desc_AlienProtein_C",
+            Self::AlienDnaCapsule => "Desc_AlienDNACapsule_C",
+            Self::Leaves => "Desc_Leaves_C",
+            Self::Mycelia => "Desc_Mycelia_C",
+            Self::Wood => "Desc_Wood_C",
+            Self::Wire => "Desc_Wire_C",
+            Self::Plastic => "Desc_Plastic_C",
+            Self::FicsiteIngot => "Desc_FicsiteIngot_C",
+            Self::HatcherRemains => "Desc_HatcherParts_C",
+            Self::HogRemains => "Desc_HogParts_C",
+            Self::BluePowerSlug => "Desc_Crystal_C",
+            Self::YellowPowerSlug => "Desc_Crystal_mk2_C",
+            Self::PurplePowerSlug => "Desc_Crystal_mk3_C",
+            Self::Sam => "Desc_SAM_C",
+            Self::IronRod => "Desc_IronRod_C",
+            Self::Biomass => "Desc_GenericBiomass_C",
+            Self::SpitterRemains => "Desc_SpitterParts_C",
+            Self::SteelIngot => "Desc_SteelIngot_C",
+            Self::StingerRemains => "Desc_StingerParts_C",
+            Self::SteelBeam => "Desc_SteelPlate_C",
+            Self::AluminumCasing => "Desc_AluminumCasing_C",
+            Self::Cable => "Desc_Cable_C",
+            Self::Concrete => "Desc_Cement_C",
+            Self::CopperPowder => "Desc_CopperDust_C",
+            Self::CopperSheet => "Desc_CopperSheet_C",
+            Self::EmptyCanister => "Desc_FluidCanister_C",
+            Self::EmptyFluidTank => "Desc_GasTank_C",
+            Self::FicsiteTrigon => "Desc_FicsiteMesh_C",
+            Self::IronPlate => "Desc_IronPlate_C",
+            Self::IronRebar => "Desc_SpikedRebar_C",
+            Self::PowerShard => "Desc_CrystalShard_C",
+            Self::QuartzCrystal => "Desc_QuartzCrystal_C",
+            Self::ReanimatedSAM => "Desc_SAMIngot_C",
+            Self::Screw => "Desc_IronScrew_C",
+            Self::Silica => "Desc_Silica_C",
+            Self::SolidBiofuel => "Desc_Biofuel_C",
+            Self::SteelPipe => "Desc_SteelPipe_C",
+            Self::Coal => "Desc_Coal_C",
+            Self::Quickwire => "Desc_HighSpeedWire_C",
+            Self::PackagedAluminaSolution => "Desc_PackagedAlumina_C",
+            Self::PackagedFuel => "Desc_Fuel_C",
+            Self::PackagedHeavyOilResidue => "Desc_PackagedOilResidue_C",
+            Self::PackagedIonizedFuel => "Desc_PackagedIonizedFuel_C",
+            Self::PackagedLiquidBiofuel => "Desc_PackagedBiofuel_C",
+            Self::PackagedNitricAcid => "Desc_PackagedNitricAcid_C",
+            Self::PackagedNitrogenGas => "Desc_PackagedNitrogenGas_C",
+            Self::PackagedOil => "Desc_PackagedOil_C",
+            Self::PackagedRocketFuel => "Desc_PackagedRocketFuel_C",
+            Self::PackagedSulfuricAcid => "Desc_PackagedSulfuricAcid_C",
+            Self::PackagedTurbofuel => "Desc_TurboFuel_C",
+            Self::PackagedWater => "Desc_PackagedWater_C",
+            Self::PolymerResin => "Desc_PolymerResin_C",
+            Self::BlackPowder => "Desc_Gunpowder_C",
+            Self::PetroleumCoke => "Desc_PetroleumCoke_C",
+            Self::Rubber => "Desc_Rubber_C",
+            Self::CompactedCoal => "Desc_CompactedCoal_C",
+            Self::SmokelessPowder => "Desc_GunpowderMK2_C",
+            Self::Fabric => "Desc_Fabric_C",
+            Self::AILimiter => "Desc_CircuitBoardHighSpeed_C",
+            Self::AdaptiveControlUnit => "Desc_SpaceElevatorPart_6_C",
+            Self::AlcladAluminumSheet => "Desc_AluminumPlate_C",
+            Self::Supercomputer => "Desc_ComputerSuper_C",
+            Self::AssemblyDirectorSystem => "Desc_SpaceElevatorPart_7_C",
+            Self::Stator => "Desc_Stator_C",
+            Self::AutomatedWiring => "Desc_SpaceElevatorPart_4_C",
+            Self::CircuitBoard => "Desc_CircuitBoard_C",
+            Self::ClusterNobelisk => "Desc_NobeliskCluster_C",
+            Self::Nobelisk => "Desc_NobeliskExplosive_C",
+            Self::ElectromagneticControlRod => "Desc_ElectromagneticControlRod_C",
+            Self::EncasedIndustrialBeam => "Desc_SteelPlateReinforced_C",
+            Self::PlutoniumPellet => "Desc_PlutoniumPellet_C",
+            Self::GasNobelisk => "Desc_NobeliskGas_C",
+            Self::EncasedPlutoniumCell => "Desc_PlutoniumCell_C",
+            Self::RifleAmmo => "Desc_CartridgeStandard_C",
+            Self::HeatSink => "Desc_AluminumPlateReinforced_C",
+            Self::HomingRifleAmmo => "Desc_CartridgeSmartProjectile_C",
+            Self::HighSpeedConnector => "Desc_HighSpeedConnector_C",
+            Self::VersatileFramework => "Desc_SpaceElevatorPart_3_C",
+            Self::MagneticFieldGenerator => "Desc_SpaceElevatorPart_8_C",
+            Self::Rotor => "Desc_Rotor_C",
+            Self::CrystalOscillator => "Desc_CrystalOscillator_C",
+            Self::Computer => "Desc_Computer_C",
+            Self::Motor => "Desc_Motor_C",
+            Self::CoolingSystem => "Desc_CoolingSystem_C",
+            Self::PressureConversionCube => "Desc_PressureConversionCube_C",
+            Self::RadioControlUnit => "Desc_ModularFrameLightweight_C",
+            Self::PlutoniumFuelRod => "Desc_PlutoniumFuelRod_C",
+            Self::ModularFrame => "Desc_ModularFrame_C",
+            Self::ReinforcedIronPlate => "Desc_IronPlateReinforced_C",
+            Self::PulseNobelisk => "Desc_NobeliskShockwave_C",
+            Self::ShatterRebar => "Desc_Rebar_Spreadshot_C",
+            Self::SmartPlating => "Desc_SpaceElevatorPart_1_C",
+            Self::StunRebar => "Desc_Rebar_Stunshot_C",
+            Self::PortableMiner => "BP_ItemDescriptorPortableMiner_C",
+            Self::HeavyModularFrame => "Desc_ModularFrameHeavy_C",
+            Self::ThermalPropulsionRocket => "Desc_SpaceElevatorPart_5_C",
+            Self::SingularityCell => "Desc_SingularityCell_C",
+            Self::DarkMatterCrystal => "Desc_DarkMatter_C",
+            Self::BallisticWarpDrive => "Desc_SpaceElevatorPart_11_C",
+            Self::SuperpositionOscillator => "Desc_QuantumOscillator_C",
+            Self::GasFilter => "Desc_Filter_C",
+            Self::IodineInfusedFilter => "Desc_HazmatFilter_C",
+            Self::ModularEngine => "Desc_SpaceElevatorPart_2_C",
+            Self::EncasedUraniumCell => "Desc_UraniumCell_C",
+            Self::NukeNobelisk => "Desc_NobeliskNuke_C",
+            Self::SAMFluctuator => "Desc_SAMFluctuator_C",
+            Self::NuclearPasta => "Desc_SpaceElevatorPart_9_C",
+            Self::TurboMotor => "Desc_MotorLightweight_C",
+            Self::FusedModularFrame => "Desc_ModularFrameFused_C",
+            Self::TurboRifleAmmo => "Desc_CartridgeChaos_C",
+            Self::UraniumFuelRod => "Desc_NuclearFuelRod_C",
+            Self::Battery => "Desc_Battery_C",
+            Self::ExplosiveRebar => "Desc_Rebar_Explosive_C",
+            Self::BiochemicalSculptor => "Desc_SpaceElevatorPart_10_C",
+            Self::NonFissileUranium => "Desc_NonFissibleUranium_C",
+            Self::UraniumWaste => "Desc_NuclearWaste_C",
+            Self::Diamonds => "Desc_Diamond_C",
+            Self::Ficsonium => "Desc_Ficsonium_C",
+            Self::PlutoniumWaste => "Desc_PlutoniumWaste_C",
+            Self::TimeCrystal => "Desc_TimeCrystal_C",
+            Self::NeuralQuantumProcessor => "Desc_TemporalProcessor_C",
+            Self::AIExpansionServer => "Desc_SpaceElevatorPart_12_C",
+            Self::AlienPowerMatrix => "Desc_AlienPowerBooster_C",
+            Self::FicsoniumFuelRod => "Desc_FicsoniumFuelRod_C",
+        }
+    }
+
+    pub fn metadata(&self) -> Metadata {
+        Metadata {
+            stack_size: Some(self.stack_size()),
+            sink_points: self.sink_points(),
+            energy: self.energy(),
+            radioactivity: self.radioactivity(),
+            packaged: None,
+            unpackaged: self.unpackaged(),
+        }
+    }
+
     pub fn color(&self) -> Color32 {
         // Colors based on https://www.reddit.com/r/SatisfactoryGame/comments/154vft6/vencams_colour_list_25/
         let color = match self {
@@ -596,6 +1005,299 @@ impl Material {
         };
         Color32::from_hex(color).unwrap()
     }
+
+    /// Number of items that fit in one inventory slot.
+    pub fn stack_size(&self) -> usize {
+        match self {
+            Self::Leaves
+            | Self::Screw
+            | Self::Wire
+            | Self::Concrete
+            | Self::Quickwire
+            | Self::CopperPowder
+            | Self::AluminumScrap
+            | Self::RifleAmmo
+            | Self::UraniumWaste
+            | Self::NonFissileUranium
+            | Self::PlutoniumWaste => 500,
+            Self::FicsiteTrigon => 400,
+            Self::Wood
+            | Self::Mycelia
+            | Self::Biomass
+            | Self::SolidBiofuel
+            | Self::IronPlate
+            | Self::IronRod
+            | Self::CopperSheet
+            | Self::Cable
+            | Self::SteelPipe
+            | Self::SteelBeam
+            | Self::Silica
+            | Self::QuartzCrystal
+            | Self::PolymerResin
+            | Self::PetroleumCoke
+            | Self::Plastic
+            | Self::Rubber
+            | Self::BlackPowder
+            | Self::SmokelessPowder
+            | Self::AluminumCasing
+            | Self::AlcladAluminumSheet
+            | Self::CircuitBoard
+            | Self::Battery
+            | Self::EncasedUraniumCell
+            | Self::EncasedPlutoniumCell
+            | Self::DarkMatterCrystal
+            | Self::HomingRifleAmmo
+            | Self::TurboRifleAmmo => 200,
+            Self::ModularFrame
+            | Self::SmartPlating
+            | Self::VersatileFramework
+            | Self::AutomatedWiring
+            | Self::Computer
+            | Self::HeavyModularFrame
+            | Self::Motor
+            | Self::Supercomputer
+            | Self::RadioControlUnit
+            | Self::TurboMotor
+            | Self::FusedModularFrame
+            | Self::AdaptiveControlUnit
+            | Self::AssemblyDirectorSystem
+            | Self::MagneticFieldGenerator
+            | Self::ThermalPropulsionRocket
+            | Self::ModularEngine
+            | Self::UraniumFuelRod
+            | Self::PlutoniumFuelRod
+            | Self::FicsoniumFuelRod
+            | Self::PressureConversionCube
+            | Self::BallisticWarpDrive
+            | Self::AlienPowerMatrix
+            | Self::NuclearPasta
+            | Self::BiochemicalSculptor
+            | Self::GasFilter
+            | Self::IodineInfusedFilter
+            | Self::Nobelisk
+            | Self::GasNobelisk
+            | Self::PulseNobelisk
+            | Self::ClusterNobelisk
+            | Self::NukeNobelisk
+            | Self::HatcherRemains
+            | Self::HogRemains
+            | Self::SpitterRemains
+            | Self::StingerRemains
+            | Self::AlienDnaCapsule
+            | Self::BluePowerSlug
+            | Self::YellowPowerSlug
+            | Self::PurplePowerSlug => 50,
+            Self::PortableMiner => 1,
+            _ => 100,
+        }
+    }
+
+    /// Points the AWESOME Sink awards per item, `None` if it can't be sunk.
+    pub fn sink_points(&self) -> Option<u32> {
+        match self {
+            Self::CopperOre => Some(3),
+            Self::IronOre => Some(1),
+            Self::CateriumOre => Some(7),
+            Self::CopperIngot => Some(6),
+            Self::IronIngot => Some(2),
+            Self::CateriumIngot => Some(42),
+            Self::AluminumScrap => Some(27),
+            Self::AluminumIngot => Some(131),
+            Self::Bauxite => Some(8),
+            Self::Limestone => Some(2),
+            Self::RawQuartz => Some(15),
+            Self::Sulfur => Some(11),
+            Self::Uranium => Some(35),
+            Self::AlienProtein => None,
+            Self::AlienDnaCapsule => Some(1000),
+            Self::Leaves => Some(3),
+            Self::Mycelia => Some(10),
+            Self::Wood => Some(30),
+            Self::Wire => Some(6),
+            Self::Plastic => Some(75),
+            Self::FicsiteIngot => Some(1936),
+            Self::HatcherRemains => None,
+            Self::HogRemains => None,
+            Self::BluePowerSlug => None,
+            Self::YellowPowerSlug => None,
+            Self::PurplePowerSlug => None,
+            Self::Sam => None,
+            Self::IronRod => Some(4),
+            Self::Biomass => Some(12),
+            Self::SpitterRemains => None,
+            Self::SteelIngot => Some(8),
+            Self::StingerRemains => None,
+            Self::SteelBeam => Some(64),
+            Self::AluminumCasing => Some(393),
+            Self::Cable => Some(24),
+            Self::Concrete => Some(12),
+            Self::CopperPowder => Some(72),
+            Self::CopperSheet => Some(24),
+            Self::EmptyCanister => Some(60),
+            Self::EmptyFluidTank => Some(170),
+            Self::FicsiteTrigon => Some(291),
+            Self::IronPlate => Some(6),
+            Self::IronRebar => Some(8),
+            Self::PowerShard => None,
+            Self::QuartzCrystal => Some(50),
+            Self::ReanimatedSAM => Some(160),
+            Self::Screw => Some(2),
+            Self::Silica => Some(20),
+            Self::SolidBiofuel => Some(48),
+            Self::SteelPipe => Some(24),
+            Self::Coal => Some(3),
+            Self::Quickwire => Some(17),
+            Self::PackagedAluminaSolution => Some(160),
+            Self::PackagedFuel => Some(270),
+            Self::PackagedHeavyOilResidue => Some(180),
+            Self::PackagedIonizedFuel => Some(5246),
+            Self::PackagedLiquidBiofuel => Some(370),
+            Self::PackagedNitricAcid => Some(412),
+            Self::PackagedNitrogenGas => Some(212),
+            Self::PackagedOil => Some(160),
+            Self::PackagedRocketFuel => Some(1028),
+            Self::PackagedSulfuricAcid => Some(152),
+            Self::PackagedTurbofuel => Some(570),
+            Self::PackagedWater => Some(130),
+            Self::PolymerResin => Some(12),
+            Self::BlackPowder => Some(14),
+            Self::PetroleumCoke => Some(20),
+            Self::Rubber => Some(60),
+            Self::CompactedCoal => Some(28),
+            Self::SmokelessPowder => Some(58),
+            Self::Fabric => Some(140),
+            Self::AILimiter => Some(920),
+            Self::AdaptiveControlUnit => Some(76368),
+            Self::AlcladAluminumSheet => Some(266),
+            Self::Supercomputer => Some(97352),
+            Self::AssemblyDirectorSystem => Some(500176),
+            Self::Stator => Some(240),
+            Self::AutomatedWiring => Some(1440),
+            Self::CircuitBoard => Some(696),
+            Self::ClusterNobelisk => Some(1376),
+            Self::Nobelisk => Some(152),
+            Self::ElectromagneticControlRod => Some(2560),
+            Self::EncasedIndustrialBeam => Some(528),
+            Self::PlutoniumPellet => None,
+            Self::GasNobelisk => Some(544),
+            Self::EncasedPlutoniumCell => None,
+            Self::RifleAmmo => Some(25),
+            Self::HeatSink => Some(2804),
+            Self::HomingRifleAmmo => Some(855),
+            Self::HighSpeedConnector => Some(3776),
+            Self::VersatileFramework => Some(1176),
+            Self::MagneticFieldGenerator => Some(11000),
+            Self::Rotor => Some(140),
+            Self::CrystalOscillator => Some(3072),
+            Self::Computer => Some(17260),
+            Self::Motor => Some(1520),
+            Self::CoolingSystem => Some(12006),
+            Self::PressureConversionCube => Some(255088),
+            Self::RadioControlUnit => Some(32352),
+            Self::PlutoniumFuelRod => Some(153184),
+            Self::ModularFrame => Some(408),
+            Self::ReinforcedIronPlate => Some(120),
+            Self::PulseNobelisk => Some(1533),
+            Self::ShatterRebar => Some(332),
+            Self::SmartPlating => Some(520),
+            Self::StunRebar => Some(186),
+            Self::PortableMiner => Some(608),
+            Self::HeavyModularFrame => Some(10800),
+            Self::ThermalPropulsionRocket => Some(732956),
+            Self::SingularityCell => Some(114675),
+            Self::DarkMatterCrystal => Some(1780),
+            Self::BallisticWarpDrive => Some(2895334),
+            Self::SuperpositionOscillator => Some(37292),
+            Self::GasFilter => Some(830),
+            Self::IodineInfusedFilter => Some(2718),
+            Self::ModularEngine => Some(9960),
+            Self::EncasedUraniumCell => Some(147),
+            Self::NukeNobelisk => Some(19600),
+            Self::SAMFluctuator => Some(1968),
+            Self::NuclearPasta => Some(538976),
+            Self::TurboMotor => Some(242720),
+            Self::FusedModularFrame => Some(62840),
+            Self::TurboRifleAmmo => Some(120),
+            Self::UraniumFuelRod => Some(44092),
+            Self::Battery => Some(465),
+            Self::ExplosiveRebar => Some(360),
+            Self::BiochemicalSculptor => Some(301778),
+            Self::NonFissileUranium => Some(278),
+            Self::UraniumWaste => None,
+            Self::Diamonds => Some(240),
+            Self::Ficsonium => None,
+            Self::PlutoniumWaste => None,
+            Self::TimeCrystal => Some(960),
+            Self::NeuralQuantumProcessor => Some(248034),
+            Self::AIExpansionServer => Some(597652),
+            Self::AlienPowerMatrix => Some(210064),
+            Self::FicsoniumFuelRod => None,
+        }
+    }
+
+    /// MJ per item when burned in a generator.
+    pub fn energy(&self) -> Option<f32> {
+        let mj = match self {
+            Self::Leaves => 15.,
+            Self::Wood => 100.,
+            Self::Mycelia => 20.,
+            Self::Biomass => 180.,
+            Self::SolidBiofuel => 450.,
+            Self::Coal => 300.,
+            Self::CompactedCoal => 630.,
+            Self::PetroleumCoke => 180.,
+            Self::Battery => 6000.,
+            Self::PackagedOil => 320.,
+            Self::PackagedHeavyOilResidue => 400.,
+            Self::PackagedFuel => 750.,
+            Self::PackagedLiquidBiofuel => 750.,
+            Self::PackagedTurbofuel => 2000.,
+            Self::PackagedRocketFuel => 7200.,
+            Self::PackagedIonizedFuel => 10000.,
+            Self::UraniumFuelRod => 750000.,
+            Self::PlutoniumFuelRod => 1500000.,
+            Self::FicsoniumFuelRod => 150000.,
+            _ => return None,
+        };
+        Some(mj)
+    }
+
+    /// Radioactive decay per item.
+    pub fn radioactivity(&self) -> Option<f32> {
+        let decay = match self {
+            Self::Uranium => 0.15,
+            Self::EncasedUraniumCell => 0.5,
+            Self::UraniumFuelRod => 50.,
+            Self::UraniumWaste => 10.,
+            Self::NonFissileUranium => 0.75,
+            Self::PlutoniumPellet => 20.,
+            Self::EncasedPlutoniumCell => 120.,
+            Self::PlutoniumFuelRod => 250.,
+            Self::PlutoniumWaste => 20.,
+            _ => return None,
+        };
+        Some(decay)
+    }
+
+    /// The fluid a packaged item holds.
+    pub fn unpackaged(&self) -> Option<Fluid> {
+        let fluid = match self {
+            Self::PackagedAluminaSolution => Fluid::AluminaSolution,
+            Self::PackagedFuel => Fluid::Fuel,
+            Self::PackagedHeavyOilResidue => Fluid::HeavyOilResidue,
+            Self::PackagedIonizedFuel => Fluid::IonizedFuel,
+            Self::PackagedLiquidBiofuel => Fluid::LiquidBiofuel,
+            Self::PackagedNitricAcid => Fluid::NitricAcid,
+            Self::PackagedNitrogenGas => Fluid::NitrogenGas,
+            Self::PackagedOil => Fluid::CrudeOil,
+            Self::PackagedRocketFuel => Fluid::RocketFuel,
+            Self::PackagedSulfuricAcid => Fluid::SulfuricAcid,
+            Self::PackagedTurbofuel => Fluid::Turbofuel,
+            Self::PackagedWater => Fluid::Water,
+            _ => return None,
+        };
+        Some(fluid)
+    }
 }
 
 #[derive(
@@ -647,6 +1349,61 @@ impl Fluid {
         self.to_string()
     }
 
+    /// Class of the fluid in the game's `Docs.json`, see [`crate::docs`].
+    pub fn class_name(&self) -> &'static str {
+        match self {
+            Self::AluminaSolution => "Desc_AluminaSolution_C",
+            Self::Fuel => "Desc_LiquidFuel_C",
+            Self::HeavyOilResidue => "Desc_HeavyOilResidue_C",
+            Self::IonizedFuel => "Desc_IonizedFuel_C",
+            Self::LiquidBiofuel => "Desc_LiquidBiofuel_C",
+            Self::NitricAcid => "Desc_NitricAcid_C",
+            Self::NitrogenGas => "Desc_NitrogenGas_C",
+            Self::CrudeOil => "Desc_LiquidOil_C",
+            Self::RocketFuel => "Desc_RocketFuel_C",
+            Self::SulfuricAcid => "Desc_SulfuricAcid_C",
+            Self::Turbofuel => "Desc_LiquidTurboFuel_C",
+            Self::Water => "Desc_Water_C",
+            Self::DissolvedSilica => "Desc_QuartzSlurry_C",
+            Self::DarkMatterResidue => "Desc_DarkEnergy_C",
+            Self::ExcitedPhotonicMatter => "Desc_QuantumEnergy_C",
+        }
+    }
+
+    /// Fluids can't be stacked or sunk, only their packaged form can.
+    pub fn metadata(&self) -> Metadata {
+        Metadata {
+            stack_size: None,
+            sink_points: None,
+            energy: self.energy(),
+            radioactivity: None,
+            packaged: self.packaged(),
+            unpackaged: None,
+        }
+    }
+
+    /// MJ per m^3 when burned in a generator.
+    pub fn energy(&self) -> Option<f32> {
+        let mj = match self {
+            Self::CrudeOil => 320.,
+            Self::HeavyOilResidue => 400.,
+            Self::Fuel => 750.,
+            Self::LiquidBiofuel => 750.,
+            Self::Turbofuel => 2000.,
+            Self::RocketFuel => 3600.,
+            Self::IonizedFuel => 5000.,
+            _ => return None,
+        };
+        Some(mj)
+    }
+
+    pub fn packaged(&self) -> Option<Material> {
+        <Material as strum::VariantArray>::VARIANTS
+            .iter()
+            .copied()
+            .find(|m| m.unpackaged() == Some(*self))
+    }
+
     pub fn color(&self) -> Color32 {
         let code = match self {
             Self::AluminaSolution => "#DDDEDF",
@@ -690,1040 +1447,281 @@ impl Fluid {
     }
 }
 
-impl Building {
-    /// Clone, but with caches reset
-    pub fn clear_clone(&self) -> Self {
-        match self {
-            Self::Miner(m) => Self::Miner(m.clear_clone()),
-            Self::Smelter(s) => Self::Smelter(s.clear_clone()),
-            Self::Splitter(s) => Self::Splitter(s.clear_clone()),
-            Self::Merger(s) => Self::Merger(s.clear_clone()),
-            Self::Constructor(s) => Self::Constructor(s.clear_clone()),
-            Self::StorageContainer(s) => Self::StorageContainer(s.clear_clone()),
-            Self::WaterExtractor(s) => Self::WaterExtractor(s.clear_clone()),
-            Self::OilExtractor(s) => Self::OilExtractor(s.clear_clone()),
-            Self::Packager(s) => Self::Packager(s.clear_clone()),
-            Self::Refinery(s) => Self::Refinery(s.clear_clone()),
-            Self::Foundry(s) => Self::Foundry(s.clear_clone()),
-            Self::Assembler(s) => Self::Assembler(s.clear_clone()),
-            Self::PipelineJunction(s) => Self::PipelineJunction(s.clear_clone()),
-            Self::Manufacturer(s) => Self::Manufacturer(s.clear_clone()),
-            Self::AwesomeSink(s) => Self::AwesomeSink(s.clear_clone()),
-            Self::Blender(s) => Self::Blender(s.clear_clone()),
-            Self::ParticleAccelerator(s) => Self::ParticleAccelerator(s.clear_clone()),
-            Self::QuantumEncoder(s) => Self::QuantumEncoder(s.clear_clone()),
-            Self::Converter(s) => Self::Converter(s.clear_clone()),
-        }
+impl Gated for Building {
+    fn unlocked_by(&self) -> Unlock {
+        self.machine().unlocked_by()
     }
+}
 
+impl Building {
     pub fn header_image(&self) -> String {
-        match self {
-            Self::Miner(m) => m.header_image(),
-            Self::Smelter(s) => s.header_image(),
-            Self::Splitter(s) => s.header_image(),
-            Self::Merger(s) => s.header_image(),
-            Self::Constructor(s) => s.header_image(),
-            Self::StorageContainer(s) => s.header_image(),
-            Self::WaterExtractor(s) => s.header_image(),
-            Self::OilExtractor(s) => s.header_image(),
-            Self::Packager(s) => s.header_image(),
-            Self::Refinery(s) => s.header_image(),
-            Self::Foundry(s) => s.header_image(),
-            Self::Assembler(s) => s.header_image(),
-            Self::PipelineJunction(s) => s.header_image(),
-            Self::Manufacturer(s) => s.header_image(),
-            Self::AwesomeSink(s) => s.header_image(),
-            Self::Blender(s) => s.header_image(),
-            Self::ParticleAccelerator(s) => s.header_image(),
-            Self::QuantumEncoder(s) => s.header_image(),
-            Self::Converter(s) => s.header_image(),
-        }
+        self.machine().header_image()
     }
 
     pub fn outputs(&self) -> usize {
-        match self {
-            Self::Miner(m) => m.num_outputs(),
-            Self::Smelter(s) => s.num_outputs(),
-            Self::Splitter(s) => s.num_outputs(),
-            Self::Merger(s) => s.num_outputs(),
-            Self::Constructor(s) => s.num_outputs(),
-            Self::StorageContainer(s) => s.num_outputs(),
-            Self::WaterExtractor(s) => s.num_outputs(),
-            Self::OilExtractor(s) => s.num_outputs(),
-            Self::Packager(s) => s.num_outputs(),
-            Self::Refinery(s) => s.num_outputs(),
-            Self::Foundry(s) => s.num_outputs(),
-            Self::Assembler(s) => s.num_outputs(),
-            Self::PipelineJunction(s) => s.num_outputs(),
-            Self::Manufacturer(s) => s.num_outputs(),
-            Self::AwesomeSink(s) => s.num_outputs(),
-            Self::Blender(s) => s.num_outputs(),
-            Self::ParticleAccelerator(s) => s.num_outputs(),
-            Self::QuantumEncoder(s) => s.num_outputs(),
-            Self::Converter(s) => s.num_outputs(),
-        }
+        self.machine().num_outputs()
     }
 
     pub fn inputs(&self) -> usize {
-        match self {
-            Self::Miner(m) => m.num_inputs(),
-            Self::Smelter(s) => s.num_inputs(),
-            Self::Splitter(s) => s.num_inputs(),
-            Self::Merger(s) => s.num_inputs(),
-            Self::Constructor(s) => s.num_inputs(),
-            Self::StorageContainer(s) => s.num_inputs(),
-            Self::WaterExtractor(s) => s.num_inputs(),
-            Self::OilExtractor(s) => s.num_inputs(),
-            Self::Packager(s) => s.num_inputs(),
-            Self::Refinery(s) => s.num_inputs(),
-            Self::Foundry(s) => s.num_inputs(),
-            Self::Assembler(s) => s.num_inputs(),
-            Self::PipelineJunction(s) => s.num_inputs(),
-            Self::Manufacturer(s) => s.num_inputs(),
-            Self::AwesomeSink(s) => s.num_inputs(),
-            Self::Blender(s) => s.num_inputs(),
-            Self::ParticleAccelerator(s) => s.num_inputs(),
-            Self::QuantumEncoder(s) => s.num_inputs(),
-            Self::Converter(s) => s.num_inputs(),
-        }
+        self.machine().num_inputs()
     }
 
     pub fn name(&self) -> String {
-        match self {
-            Self::Miner(m) => m.name(),
-            Self::Smelter(s) => s.name(),
-            Self::Splitter(s) => s.name(),
-            Self::Merger(s) => s.name(),
-            Self::Constructor(s) => s.name(),
-            Self::StorageContainer(s) => s.name(),
-            Self::WaterExtractor(s) => s.name(),
-            Self::OilExtractor(s) => s.name(),
-            Self::Packager(s) => s.name(),
-            Self::Refinery(s) => s.name(),
-            Self::Foundry(s) => s.name(),
-            Self::Assembler(s) => s.name(),
-            Self::PipelineJunction(s) => s.name(),
-            Self::Manufacturer(s) => s.name(),
-            Self::AwesomeSink(s) => s.name(),
-            Self::Blender(s) => s.name(),
-            Self::ParticleAccelerator(s) => s.name(),
-            Self::QuantumEncoder(s) => s.name(),
-            Self::Converter(s) => s.name(),
-        }
+        self.machine().name()
     }
 
     pub fn description(&self) -> String {
-        match self {
-            Self::Miner(m) => m.description(),
-            Self::Smelter(s) => s.description(),
-            Self::Splitter(s) => s.description(),
-            Self::Merger(s) => s.description(),
-            Self::Constructor(s) => s.description(),
-            Self::StorageContainer(s) => s.description(),
-            Self::WaterExtractor(s) => s.description(),
-            Self::OilExtractor(s) => s.description(),
-            Self::Packager(s) => s.description(),
-            Self::Refinery(s) => s.description(),
-            Self::Foundry(s) => s.description(),
-            Self::Assembler(s) => s.description(),
-            Self::PipelineJunction(s) => s.description(),
-            Self::Manufacturer(s) => s.description(),
-            Self::AwesomeSink(s) => s.description(),
-            Self::Blender(s) => s.description(),
-            Self::ParticleAccelerator(s) => s.description(),
-            Self::QuantumEncoder(s) => s.description(),
-            Self::Converter(s) => s.description(),
-        }
+        self.machine().description()
     }
 
     pub fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
-        match self {
-            Self::Miner(m) => m.input_resource(input_id),
-            Self::Smelter(s) => s.input_resource(input_id),
-            Self::Splitter(s) => s.input_resource(input_id),
-            Self::Merger(s) => s.input_resource(input_id),
-            Self::Constructor(s) => s.input_resource(input_id),
-            Self::StorageContainer(s) => s.input_resource(input_id),
-            Self::WaterExtractor(s) => s.input_resource(input_id),
-            Self::OilExtractor(s) => s.input_resource(input_id),
-            Self::Packager(s) => s.input_resource(input_id),
-            Self::Refinery(s) => s.input_resource(input_id),
-            Self::Foundry(s) => s.input_resource(input_id),
-            Self::Assembler(s) => s.input_resource(input_id),
-            Self::PipelineJunction(s) => s.input_resource(input_id),
-            Self::Manufacturer(s) => s.input_resource(input_id),
-            Self::AwesomeSink(s) => s.input_resource(input_id),
-            Self::Blender(s) => s.input_resource(input_id),
-            Self::ParticleAccelerator(s) => s.input_resource(input_id),
-            Self::QuantumEncoder(s) => s.input_resource(input_id),
-            Self::Converter(s) => s.input_resource(input_id),
-        }
+        self.machine().input_resource(input_id)
     }
 
     pub fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
-        match self {
-            Self::Miner(m) => m.output_resource(output_id),
-            Self::Smelter(s) => s.output_resource(output_id),
-            Self::Splitter(s) => s.output_resource(output_id),
-            Self::Merger(s) => s.output_resource(output_id),
-            Self::Constructor(s) => s.output_resource(output_id),
-            Self::StorageContainer(s) => s.output_resource(output_id),
-            Self::WaterExtractor(s) => s.output_resource(output_id),
-            Self::OilExtractor(s) => s.output_resource(output_id),
-            Self::Packager(s) => s.output_resource(output_id),
-            Self::Refinery(s) => s.output_resource(output_id),
-            Self::Foundry(s) => s.output_resource(output_id),
-            Self::Assembler(s) => s.output_resource(output_id),
-            Self::PipelineJunction(s) => s.output_resource(output_id),
-            Self::Manufacturer(s) => s.output_resource(output_id),
-            Self::AwesomeSink(s) => s.output_resource(output_id),
-            Self::Blender(s) => s.output_resource(output_id),
-            Self::ParticleAccelerator(s) => s.output_resource(output_id),
-            Self::QuantumEncoder(s) => s.output_resource(output_id),
-            Self::Converter(s) => s.output_resource(output_id),
-        }
+        self.machine().output_resource(output_id)
     }
 
     pub fn current_output(&self, output_id: usize) -> Option<Output> {
-        match self {
-            Self::Miner(m) => {
-                assert_eq!(output_id, 0, "1 output");
-                m.current_output()
-            }
-            Self::Smelter(s) => {
-                assert_eq!(output_id, 0, "1 output");
-                s.current_output()
-            }
-            Self::Constructor(s) => {
-                assert_eq!(output_id, 0, "1 output");
-                s.current_output()
-            }
-            Self::StorageContainer(s) => {
-                assert_eq!(output_id, 0, "1 output");
-                s.current_output()
-            }
-            Self::WaterExtractor(w) => {
-                assert_eq!(output_id, 0, "1 output");
-                w.current_output()
-            }
-            Self::OilExtractor(o) => {
-                assert_eq!(output_id, 0, "1 output");
-                o.current_output()
-            }
-            Self::Packager(p) => match output_id {
-                0 => p.current_output_fluid(),
-                1 => p.current_output_material(),
-                _ => unreachable!("only two output"),
-            },
-            Self::Refinery(r) => match output_id {
-                0 => r.current_output_fluid(),
-                1 => r.current_output_material(),
-                _ => unreachable!("only two output"),
-            },
-            Self::Foundry(f) => {
-                assert_eq!(output_id, 0, "1 output");
-                f.current_output()
-            }
-            Self::Assembler(a) => {
-                assert_eq!(output_id, 0, "1 output");
-                a.current_output()
-            }
-            Self::Manufacturer(m) => {
-                assert_eq!(output_id, 0, "1 output");
-                m.current_output()
-            }
-            Self::Splitter(s) => match output_id {
-                0 => s.current_output_0(),
-                1 => s.current_output_1(),
-                2 => s.current_output_2(),
-                _ => unreachable!("3 outputs"),
-            },
-            Self::Merger(m) => {
-                assert_eq!(output_id, 0, "1 output");
-                m.current_output()
-            }
-            Self::PipelineJunction(s) => match output_id {
-                0 => s.current_output_0(),
-                1 => s.current_output_1(),
-                2 => s.current_output_2(),
-                3 => s.current_output_3(),
-                _ => unreachable!("4 outputs"),
-            },
-            Self::AwesomeSink(_) => None,
-            Self::Blender(b) => match output_id {
-                0 => b.current_output_fluid(),
-                1 => b.current_output_material(),
-                _ => unreachable!("2 outputs"),
-            },
-            Self::ParticleAccelerator(b) => match output_id {
-                0 => b.current_output_material(),
-                _ => unreachable!("1 outputs"),
-            },
-            Self::QuantumEncoder(q) => match output_id {
-                0 => q.current_output_fluid(),
-                1 => q.current_output_material(),
-                _ => unreachable!("2 outputs"),
-            },
-            Self::Converter(c) => match output_id {
-                0 => c.current_output_fluid(),
-                1 => c.current_output_material(),
-                _ => unreachable!("2 outputs"),
-            },
-        }
+        self.machine().current_output(output_id)
     }
 
     pub fn current_input(&self, input_id: usize) -> Option<Input> {
-        match self {
-            Self::Miner(_) => {
-                unreachable!("no inputs");
-            }
-            Self::Smelter(s) => {
-                assert_eq!(input_id, 0, "1 input");
-                s.current_input.clone()
-            }
-            Self::Constructor(c) => {
-                assert_eq!(input_id, 0, "1 input");
-                c.current_input.clone()
-            }
-            Self::StorageContainer(_) => {
-                unreachable!("no inputs");
-            }
-            Self::WaterExtractor(_) => {
-                unreachable!("no inputs");
-            }
-            Self::OilExtractor(_) => {
-                unreachable!("no inputs");
-            }
-            Self::Packager(p) => match input_id {
-                0 => p.current_input_fluid.clone(),
-                1 => p.current_input_material.clone(),
-                _ => unreachable!("2 inputs"),
-            },
-            Self::Refinery(r) => match input_id {
-                0 => r.current_input_fluid.clone(),
-                1 => r.current_input_material.clone(),
-                _ => unreachable!("2 inputs"),
-            },
-            Self::Foundry(f) => match input_id {
-                0 => f.current_input_material_0.clone(),
-                1 => f.current_input_material_1.clone(),
-                _ => unreachable!("2 inputs"),
-            },
-            Self::Assembler(a) => match input_id {
-                0 => a.current_input_material_0.clone(),
-                1 => a.current_input_material_1.clone(),
-                _ => unreachable!("2 inputs"),
-            },
-            Self::Manufacturer(m) => match input_id {
-                0 => m.current_input_material_0.clone(),
-                1 => m.current_input_material_1.clone(),
-                2 => m.current_input_material_2.clone(),
-                3 => m.current_input_material_3.clone(),
-                _ => unreachable!("4 inputs"),
-            },
-            Self::Splitter(s) => {
-                assert_eq!(input_id, 0, "1 input");
-                s.current_input.clone()
-            }
-            Self::Merger(m) => match input_id {
-                0 => m.current_input_0.clone(),
-                1 => m.current_input_1.clone(),
-                2 => m.current_input_2.clone(),
-                _ => unreachable!("3 inputs"),
-            },
-            Self::PipelineJunction(s) => match input_id {
-                0 => s.current_input_0.clone(),
-                1 => s.current_input_1.clone(),
-                2 => s.current_input_2.clone(),
-                3 => s.current_input_3.clone(),
-                _ => unreachable!("3 inputs"),
-            },
-            Self::AwesomeSink(s) => {
-                assert_eq!(input_id, 0, "1 input");
-                s.current_input.clone()
-            }
-            Self::Blender(b) => match input_id {
-                0 => b.current_input_fluid_0.clone(),
-                1 => b.current_input_fluid_1.clone(),
-                2 => b.current_input_material_0.clone(),
-                3 => b.current_input_material_1.clone(),
-                _ => unreachable!("4 inputs"),
-            },
-            Self::ParticleAccelerator(b) => match input_id {
-                0 => b.current_input_fluid_0.clone(),
-                1 => b.current_input_material_0.clone(),
-                2 => b.current_input_material_1.clone(),
-                _ => unreachable!("3 inputs"),
-            },
-            Self::QuantumEncoder(q) => match input_id {
-                0 => q.current_input_fluid_0.clone(),
-                1 => q.current_input_material_0.clone(),
-                2 => q.current_input_material_1.clone(),
-                3 => q.current_input_material_2.clone(),
-                _ => unreachable!("4 inputs"),
-            },
-            Self::Converter(c) => match input_id {
-                0 => c.current_input_material_0.clone(),
-                1 => c.current_input_material_1.clone(),
-                _ => unreachable!("2 inputs"),
-            },
-        }
+        self.machine().current_input(input_id)
     }
 
     pub fn set_current_input(&mut self, input: Output, input_id: usize) {
-        match self {
-            Self::Miner(_) => {
-                unreachable!("no inputs");
-            }
-            Self::Smelter(s) => {
-                assert_eq!(input_id, 0, "1 input");
-                s.current_input.replace(input.into());
-            }
-            Self::Constructor(c) => {
-                assert_eq!(input_id, 0, "1 input");
-                c.current_input.replace(input.into());
-            }
-            Self::StorageContainer(_) => {
-                unreachable!("no inputs");
-            }
-            Self::WaterExtractor(_) => {
-                unreachable!("no inputs");
-            }
-            Self::OilExtractor(_) => {
-                unreachable!("no inputs");
-            }
-            Self::Packager(p) => match input_id {
-                0 => {
-                    p.current_input_fluid.replace(input.into());
-                }
-                1 => {
-                    p.current_input_material.replace(input.into());
-                }
-                _ => unreachable!("2 inputs"),
-            },
-            Self::Refinery(r) => match input_id {
-                0 => {
-                    r.current_input_fluid.replace(input.into());
-                }
-                1 => {
-                    r.current_input_material.replace(input.into());
-                }
-                _ => unreachable!("2 inputs"),
-            },
-            Self::Foundry(f) => match input_id {
-                0 => {
-                    f.current_input_material_0.replace(input.into());
-                }
-                1 => {
-                    f.current_input_material_1.replace(input.into());
-                }
-                _ => unreachable!("2 inputs"),
-            },
-            Self::Assembler(a) => match input_id {
-                0 => {
-                    a.current_input_material_0.replace(input.into());
-                }
-                1 => {
-                    a.current_input_material_1.replace(input.into());
-                }
-                _ => unreachable!("2 inputs"),
-            },
-            Self::Manufacturer(m) => match input_id {
-                0 => {
-                    m.current_input_material_0.replace(input.into());
-                }
-                1 => {
-                    m.current_input_material_1.replace(input.into());
-                }
-                2 => {
-                    m.current_input_material_2.replace(input.into());
-                }
-                3 => {
-                    m.current_input_material_3.replace(input.into());
-                }
-                _ => unreachable!("4 inputs"),
-            },
-            Self::Splitter(s) => {
-                assert_eq!(input_id, 0, "1 input");
-                s.current_input.replace(input.into());
-            }
-            Self::Merger(m) => match input_id {
-                0 => {
-                    m.current_input_0.replace(input.into());
-                }
-                1 => {
-                    m.current_input_1.replace(input.into());
-                }
-                2 => {
-                    m.current_input_2.replace(input.into());
-                }
-                _ => unreachable!("3 inputs"),
-            },
-            Self::PipelineJunction(s) => match input_id {
-                0 => {
-                    s.current_input_0.replace(input.into());
-                }
-                1 => {
-                    s.current_input_1.replace(input.into());
-                }
-                2 => {
-                    s.current_input_2.replace(input.into());
-                }
-                3 => {
-                    s.current_input_3.replace(input.into());
-                }
-                _ => unreachable!("3 inputs"),
-            },
-            Self::AwesomeSink(s) => {
-                assert_eq!(input_id, 0, "1 input");
-                s.current_input.replace(input.into());
-            }
-            Self::Blender(b) => match input_id {
-                0 => {
-                    b.current_input_fluid_0.replace(input.into());
-                }
-                1 => {
-                    b.current_input_fluid_1.replace(input.into());
-                }
-                2 => {
-                    b.current_input_material_0.replace(input.into());
-                }
-                3 => {
-                    b.current_input_material_1.replace(input.into());
-                }
-                _ => unreachable!("4 inputs"),
-            },
-            Self::ParticleAccelerator(b) => match input_id {
-                0 => {
-                    b.current_input_fluid_0.replace(input.into());
-                }
-                1 => {
-                    b.current_input_material_0.replace(input.into());
-                }
-                2 => {
-                    b.current_input_material_1.replace(input.into());
-                }
-                _ => unreachable!("3 inputs"),
-            },
-            Self::QuantumEncoder(q) => match input_id {
-                0 => {
-                    q.current_input_fluid_0.replace(input.into());
-                }
-                1 => {
-                    q.current_input_material_0.replace(input.into());
-                }
-                2 => {
-                    q.current_input_material_1.replace(input.into());
-                }
-                3 => {
-                    q.current_input_material_2.replace(input.into());
-                }
-                _ => unreachable!("4 inputs"),
-            },
-            Self::Converter(c) => match input_id {
-                0 => {
-                    c.current_input_material_0.replace(input.into());
-                }
-                1 => {
-                    c.current_input_material_1.replace(input.into());
-                }
-                _ => unreachable!("2 inputs"),
-            },
-        }
+        self.machine_mut().set_current_input(input, input_id)
     }
 
     pub fn clear_current_input(&mut self, input_id: usize) {
-        match self {
-            Self::Miner(_) => {
-                unreachable!("no inputs");
-            }
-            Self::Smelter(s) => {
-                assert_eq!(input_id, 0, "1 input");
-                s.current_input = None;
-            }
-            Self::Constructor(c) => {
-                assert_eq!(input_id, 0, "1 input");
-                c.current_input = None;
-            }
-            Self::StorageContainer(_) => {
-                unreachable!("no inputs");
-            }
-            Self::WaterExtractor(_) => {
-                unreachable!("no inputs");
-            }
-            Self::OilExtractor(_) => {
-                unreachable!("no inputs");
-            }
-            Self::Packager(p) => match input_id {
-                0 => {
-                    p.current_input_fluid = None;
-                }
-                1 => {
-                    p.current_input_material = None;
-                }
-                _ => unreachable!("2 inputs"),
-            },
-            Self::Refinery(r) => match input_id {
-                0 => {
-                    r.current_input_fluid = None;
-                }
-                1 => {
-                    r.current_input_material = None;
-                }
-                _ => unreachable!("2 inputs"),
-            },
-            Self::Foundry(f) => match input_id {
-                0 => {
-                    f.current_input_material_0 = None;
-                }
-                1 => {
-                    f.current_input_material_1 = None;
-                }
-                _ => unreachable!("2 inputs"),
-            },
-            Self::Assembler(a) => match input_id {
-                0 => {
-                    a.current_input_material_0 = None;
-                }
-                1 => {
-                    a.current_input_material_1 = None;
-                }
-                _ => unreachable!("2 inputs"),
-            },
-            Self::Manufacturer(m) => match input_id {
-                0 => {
-                    m.current_input_material_0 = None;
-                }
-                1 => {
-                    m.current_input_material_1 = None;
-                }
-                2 => {
-                    m.current_input_material_2 = None;
-                }
-                3 => {
-                    m.current_input_material_3 = None;
-                }
-                _ => unreachable!("4 inputs"),
-            },
-            Self::Splitter(s) => {
-                assert_eq!(input_id, 0, "1 input");
-                s.current_input = None;
-            }
-            Self::Merger(m) => match input_id {
-                0 => {
-                    m.current_input_0 = None;
-                }
-                1 => {
-                    m.current_input_1 = None;
-                }
-                2 => {
-                    m.current_input_2 = None;
-                }
-                _ => unreachable!("3 inputs"),
-            },
-            Self::PipelineJunction(s) => match input_id {
-                0 => {
-                    s.current_input_0 = None;
-                }
-                1 => {
-                    s.current_input_1 = None;
-                }
-                2 => {
-                    s.current_input_2 = None;
-                }
-                3 => {
-                    s.current_input_3 = None;
-                }
-                _ => unreachable!("4 inputs"),
-            },
-            Self::AwesomeSink(s) => {
-                assert_eq!(input_id, 0, "1 input");
-                s.current_input = None;
-            }
-            Self::Blender(b) => match input_id {
-                0 => {
-                    b.current_input_fluid_0 = None;
-                }
-                1 => {
-                    b.current_input_fluid_1 = None;
-                }
-                2 => {
-                    b.current_input_material_0 = None;
-                }
-                3 => {
-                    b.current_input_material_1 = None;
-                }
-                _ => unreachable!("4 inputs"),
-            },
-            Self::ParticleAccelerator(b) => match input_id {
-                0 => {
-                    b.current_input_fluid_0 = None;
-                }
-                1 => {
-                    b.current_input_material_0 = None;
-                }
-                2 => {
-                    b.current_input_material_1 = None;
-                }
-                _ => unreachable!("3 inputs"),
-            },
-            Self::QuantumEncoder(q) => match input_id {
-                0 => {
-                    q.current_input_fluid_0 = None;
-                }
-                1 => {
-                    q.current_input_material_0 = None;
-                }
-                2 => {
-                    q.current_input_material_1 = None;
-                }
-                3 => {
-                    q.current_input_material_2 = None;
-                }
-                _ => unreachable!("4 inputs"),
-            },
-            Self::Converter(c) => match input_id {
-                0 => {
-                    c.current_input_material_0 = None;
-                }
-                1 => {
-                    c.current_input_material_1 = None;
-                }
-                _ => unreachable!("2 inputs"),
-            },
-        }
+        self.machine_mut().clear_current_input(input_id)
     }
 
     pub fn set_current_output_connected(&mut self, output_id: usize) {
-        match self {
-            Building::Splitter(s) => match output_id {
-                0 => {
-                    s.output_0_connected = true;
-                }
-                1 => {
-                    s.output_1_connected = true;
-                }
-                2 => {
-                    s.output_2_connected = true;
-                }
-                _ => unreachable!("3 outputs"),
-            },
-            Building::PipelineJunction(s) => match output_id {
-                0 => {
-                    s.output_0_connected = true;
-                }
-                1 => {
-                    s.output_1_connected = true;
-                }
-                2 => {
-                    s.output_2_connected = true;
-                }
-                3 => {
-                    s.output_3_connected = true;
-                }
-                _ => unreachable!("4 outputs"),
-            },
-            _ => {}
-        }
+        self.machine_mut().set_output_connected(output_id, true)
     }
 
     pub fn set_current_output_disconnected(&mut self, output_id: usize) {
+        self.machine_mut().set_output_connected(output_id, false)
+    }
+
+    /// Head lift in meters provided at the given fluid output.
+    pub fn head_lift(&self, output_id: usize) -> f32 {
+        assert_eq!(
+            self.output_resource(output_id),
+            crate::node::ResourceType::Fluid,
+            "head lift is only defined for fluid outputs"
+        );
+        self.machine().head_lift(output_id)
+    }
+
+    /// The strongest radiation on any of the building's belts.
+    pub fn radiation(&self) -> Option<f32> {
+        let machine = self.machine();
+        let inputs = (0..machine.num_inputs())
+            .filter_map(|i| machine.current_input(i))
+            .map(|i| (i.resource, i.speed));
+        let outputs = (0..machine.num_outputs())
+            .filter_map(|o| machine.current_output(o))
+            .map(|o| (o.resource, o.speed));
+        inputs
+            .chain(outputs)
+            .filter_map(|(resource, speed)| resource.radiation(speed))
+            .reduce(f32::max)
+    }
+
+    /// MW generated, or used when negative. Only counts buildings that know their power.
+    pub fn power(&self) -> f32 {
         match self {
-            Building::Splitter(s) => match output_id {
-                0 => {
-                    s.output_0_connected = false;
-                }
-                1 => {
-                    s.output_1_connected = false;
-                }
-                2 => {
-                    s.output_2_connected = false;
-                }
-                _ => unreachable!("3 outputs"),
-            },
-            Building::PipelineJunction(s) => match output_id {
-                0 => {
-                    s.output_0_connected = false;
-                }
-                1 => {
-                    s.output_1_connected = false;
-                }
-                2 => {
-                    s.output_2_connected = false;
-                }
-                3 => {
-                    s.output_2_connected = false;
-                }
-                _ => unreachable!("4 outputs"),
-            },
-            _ => {}
+            Self::NuclearPowerPlant(p) => p.power(),
+            Self::ResourceWellPressurizer(p) => -p.power_usage(),
+            _ => 0.,
         }
     }
+
+    /// Fluid nodes that only route fluids, without pushing them.
+    pub fn is_fluid_passthrough(&self) -> bool {
+        self.machine().is_fluid_passthrough()
+    }
 }
 
-fn calc_output(input_size: Option<f32>, duration: f32, output_size: f32, input_base: f32) -> f32 {
-    let a = match input_size {
-        Some(input_size) => {
-            let input_size = (input_size / 60.) * duration;
+/// Lowest clock speed in percent a building can run at.
+pub const MIN_CLOCK_SPEED: f32 = 1.;
+/// Highest clock speed in percent, with all power shard slots filled.
+pub const MAX_CLOCK_SPEED: f32 = 250.;
+/// Clock speed in percent each power shard adds above 100%.
+const POWER_SHARD_CLOCK: f32 = 50.;
 
-            // 45/60 * 4secs = 3
-            if input_size < input_base {
-                input_size / input_base
-            } else {
-                1.
-            }
-        }
-        None => 1.,
-    };
+/// Power shards needed to run at `speed` percent.
+pub fn power_shards(speed: f32) -> u32 {
+    ((speed - 100.) / POWER_SHARD_CLOCK).ceil().max(0.) as u32
+}
 
-    // 60/4 * 1 = 15
-    let b = (60. / duration) * a * output_size;
-    round(b)
+/// Clock speed percentage as a factor, never zero so input rates can be
+/// divided by it.
+///
+/// A building at clock `c` fed `x` behaves like one at 100% fed `x / c`,
+/// producing `c` times as much.
+pub fn clock_factor(speed: f32) -> f32 {
+    speed.max(MIN_CLOCK_SPEED) / 100.
 }
 
-/// Round by satisfactory precision of 6 digits
-fn round(x: f32) -> f32 {
-    (x * 1_000_000.).round() / 1_000_000.
+/// Result of running a recipe with the given input rates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecipeOutput {
+    /// Fraction of a full cycle rate the inputs allow, between 0 and 1.
+    pub factor: f32,
+    /// The ingredient that limits the rate, if any.
+    pub limited_by: Option<usize>,
+    /// Products per minute, in the order of the recipe.
+    pub products: Vec<f32>,
 }
 
-fn calc_output2(
-    input_size: Option<(f32, f32)>,
+/// Evaluate a recipe taking `duration` seconds per cycle.
+///
+/// `ingredients` and `products` are the amounts per cycle, `input_rates` the
+/// amounts per minute arriving for each ingredient. Without input rates the
+/// recipe runs at full speed.
+pub fn evaluate_recipe(
+    input_rates: Option<impl AsRef<[f32]>>,
     duration: f32,
-    output_size: f32,
-    input_base_a: f32,
-    input_base_b: f32,
-) -> f32 {
-    let a = match input_size {
-        Some((input_size_a, input_size_b)) => {
-            let input_size_a = (input_size_a / 60.) * duration;
-            let input_size_b = (input_size_b / 60.) * duration;
-
+    ingredients: &[f32],
+    products: &[f32],
+) -> RecipeOutput {
+    let mut factor = 1.;
+    let mut limited_by = None;
+    if let Some(input_rates) = input_rates {
+        let input_rates = input_rates.as_ref();
+        assert_eq!(
+            input_rates.len(),
+            ingredients.len(),
+            "one rate per ingredient"
+        );
+
+        for (i, (rate, base)) in input_rates.iter().zip(ingredients).enumerate() {
             // 45/60 * 4secs = 3
-            let a = if input_size_a < input_base_a {
-                input_size_a / input_base_a
-            } else {
-                1.
-            };
-            let b = if input_size_b < input_base_b {
-                input_size_b / input_base_b
-            } else {
-                1.
-            };
-            // restrict to the minimum
-            if a < b {
-                a
-            } else {
-                b
+            let per_cycle = (rate / 60.) * duration;
+            if per_cycle < *base && per_cycle / base < factor {
+                factor = per_cycle / base;
+                limited_by = Some(i);
             }
         }
-        None => 1.,
-    };
+    }
 
     // 60/4 * 1 = 15
-    let b = (60. / duration) * a * output_size;
-    round(b)
+    let products = products
+        .iter()
+        .map(|size| round((60. / duration) * factor * size))
+        .collect();
+
+    RecipeOutput {
+        factor,
+        limited_by,
+        products,
+    }
 }
 
-fn calc_output3(
-    input_size: Option<(f32, f32, f32)>,
-    duration: f32,
-    output_size: f32,
-    input_base_a: f32,
-    input_base_b: f32,
-    input_base_c: f32,
-) -> f32 {
-    let a = match input_size {
-        Some((input_size_a, input_size_b, input_size_c)) => {
-            let input_size_a = (input_size_a / 60.) * duration;
-            let input_size_b = (input_size_b / 60.) * duration;
-            let input_size_c = (input_size_c / 60.) * duration;
-
-            // 45/60 * 4secs = 3
-            let a = if input_size_a < input_base_a {
-                input_size_a / input_base_a
-            } else {
-                1.
-            };
-            let b = if input_size_b < input_base_b {
-                input_size_b / input_base_b
-            } else {
-                1.
-            };
-            let c = if input_size_c < input_base_c {
-                input_size_c / input_base_c
-            } else {
-                1.
-            };
-            min3(a, b, c)
-        }
-        None => 1.,
-    };
-
-    // 60/4 * 1 = 15
-    let b = (60. / duration) * a * output_size;
-    round(b)
+/// Duration and per cycle amounts of a recipe, with ingredients and products
+/// in the order the building shows its pins.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecipeStats<const I: usize, const O: usize> {
+    /// Seconds per cycle.
+    pub duration: f32,
+    pub ingredients: [f32; I],
+    pub products: [f32; O],
 }
 
-fn calc_output4(
-    input_size: Option<(f32, f32, f32, f32)>,
-    duration: f32,
-    output_size: f32,
-    input_base_a: f32,
-    input_base_b: f32,
-    input_base_c: f32,
-    input_base_d: f32,
-) -> f32 {
-    let a = match input_size {
-        Some((input_size_a, input_size_b, input_size_c, input_size_d)) => {
-            let input_size_a = (input_size_a / 60.) * duration;
-            let input_size_b = (input_size_b / 60.) * duration;
-            let input_size_c = (input_size_c / 60.) * duration;
-            let input_size_d = (input_size_d / 60.) * duration;
-
-            // 45/60 * 4secs = 3
-            let a = if input_size_a < input_base_a {
-                input_size_a / input_base_a
-            } else {
-                1.
-            };
-            let b = if input_size_b < input_base_b {
-                input_size_b / input_base_b
-            } else {
-                1.
-            };
-            let c = if input_size_c < input_base_c {
-                input_size_c / input_base_c
-            } else {
-                1.
-            };
-            let d = if input_size_d < input_base_d {
-                input_size_d / input_base_d
-            } else {
-                1.
-            };
-
-            // restrict to the minimum
-            min4(a, b, c, d)
+impl<const I: usize, const O: usize> RecipeStats<I, O> {
+    pub fn new(duration: f32, ingredients: [f32; I], products: [f32; O]) -> Self {
+        Self {
+            duration,
+            ingredients,
+            products,
         }
-        None => 1.,
-    };
+    }
 
-    // 60/4 * 1 = 15
-    let b = (60. / duration) * a * output_size;
-    round(b)
-}
+    /// Replaces the hand-coded amounts with those of the installed recipe
+    /// database, see [`crate::docs::install`], when it has the recipe.
+    pub fn or_installed(self, items: &RecipeItems<I, O>) -> Self {
+        match crate::docs::installed() {
+            Some(db) => self.or_from(db, items),
+            None => self,
+        }
+    }
 
-fn calc_output4_2(
-    input_size: Option<(f32, f32, f32, f32)>,
-    duration: f32,
-    output_size_a: f32,
-    output_size_b: f32,
-    input_base_a: f32,
-    input_base_b: f32,
-    input_base_c: f32,
-    input_base_d: f32,
-) -> (f32, f32) {
-    let a = match input_size {
-        Some((input_size_a, input_size_b, input_size_c, input_size_d)) => {
-            let input_size_a = (input_size_a / 60.) * duration;
-            let input_size_b = (input_size_b / 60.) * duration;
-            let input_size_c = (input_size_c / 60.) * duration;
-            let input_size_d = (input_size_d / 60.) * duration;
-
-            let a = if input_size_a < input_base_a {
-                input_size_a / input_base_a
-            } else {
-                1.
-            };
-            let b = if input_size_b < input_base_b {
-                input_size_b / input_base_b
-            } else {
-                1.
-            };
-            let c = if input_size_c < input_base_c {
-                input_size_c / input_base_c
-            } else {
-                1.
-            };
-            let d = if input_size_d < input_base_d {
-                input_size_d / input_base_d
-            } else {
-                1.
-            };
+    /// Takes the duration and amounts from `db`, falling back to `self`
+    /// entirely if the recipe or one of the items is missing.
+    pub fn or_from(self, db: &RecipeDatabase, items: &RecipeItems<I, O>) -> Self {
+        self.find(db, items).unwrap_or(self)
+    }
 
-            // restrict to the minimum
-            min4(a, b, c, d)
-        }
-        None => 1.,
-    };
+    /// The duration and amounts of the recipe in `db`, matching items by
+    /// their class name. Unused pins stay at their hand-coded amount.
+    pub fn find(&self, db: &RecipeDatabase, items: &RecipeItems<I, O>) -> Option<Self> {
+        db.recipes_named(&items.name)
+            .find_map(|recipe| self.find_in(db, recipe, items))
+    }
 
-    // 60/4 * 1 = 15
-    let b_a = round((60. / duration) * a * output_size_a);
-    let b_b = round((60. / duration) * a * output_size_b);
-    (b_a, b_b)
-}
+    fn find_in(
+        &self,
+        db: &RecipeDatabase,
+        recipe: &crate::docs::Recipe,
+        items: &RecipeItems<I, O>,
+    ) -> Option<Self> {
+        let amounts = |resources: &[Option<Resource>],
+                       amounts: &[crate::docs::ItemAmount],
+                       fallback: &[f32]| {
+            resources
+                .iter()
+                .zip(fallback)
+                .map(|(resource, fallback)| match resource {
+                    Some(resource) => amounts
+                        .iter()
+                        .find(|a| a.item == resource.class_name())
+                        .map(|a| db.per_cycle(a)),
+                    None => Some(*fallback),
+                })
+                .collect::<Option<Vec<_>>>()
+        };
+        let ingredients = amounts(&items.ingredients, &recipe.ingredients, &self.ingredients)?;
+        let products = amounts(&items.products, &recipe.products, &self.products)?;
+        Some(Self {
+            duration: recipe.duration,
+            ingredients: ingredients.try_into().unwrap(),
+            products: products.try_into().unwrap(),
+        })
+    }
 
-fn calc_output2_2(
-    input_size: Option<(f32, f32)>,
-    duration: f32,
-    output_size_a: f32,
-    output_size_b: f32,
-    input_base_a: f32,
-    input_base_b: f32,
-) -> (f32, f32) {
-    let a = match input_size {
-        Some((input_size_a, input_size_b)) => {
-            let input_size_a = (input_size_a / 60.) * duration;
-            let input_size_b = (input_size_b / 60.) * duration;
-
-            let a = if input_size_a < input_base_a {
-                input_size_a / input_base_a
-            } else {
-                1.
-            };
-            let b = if input_size_b < input_base_b {
-                input_size_b / input_base_b
-            } else {
-                1.
-            };
-            // restrict to the minimum
-            if a < b {
-                a
-            } else {
-                b
-            }
-        }
-        None => 1.,
-    };
+    /// Amounts per minute each ingredient is used at 100%.
+    pub fn input_speeds(&self) -> [f32; I] {
+        self.ingredients.map(|i| (60. / self.duration) * i)
+    }
 
-    // 60/4 * 1 = 15
-    let b_a = round((60. / duration) * a * output_size_a);
-    let b_b = round((60. / duration) * a * output_size_b);
-    (b_a, b_b)
+    /// See [`evaluate_recipe`].
+    pub fn evaluate(&self, input_rates: Option<[f32; I]>) -> RecipeOutput {
+        evaluate_recipe(
+            input_rates,
+            self.duration,
+            &self.ingredients,
+            &self.products,
+        )
+    }
 }
 
-fn min3(a: f32, b: f32, c: f32) -> f32 {
-    let mut sizes = [a, b, c];
-    sizes.sort_by(|a, b| {
-        if a < b {
-            std::cmp::Ordering::Less
-        } else {
-            std::cmp::Ordering::Greater
-        }
-    });
-    sizes[0]
+/// What goes into and comes out of a recipe, to look it up in a
+/// [`RecipeDatabase`]. Unused pins are `None`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecipeItems<const I: usize, const O: usize> {
+    /// Display name, without the `Alternate: ` prefix.
+    pub name: String,
+    pub ingredients: [Option<Resource>; I],
+    pub products: [Option<Resource>; O],
 }
 
-fn min4(a: f32, b: f32, c: f32, d: f32) -> f32 {
-    let mut sizes = [a, b, c, d];
-    sizes.sort_by(|a, b| {
-        if a < b {
-            std::cmp::Ordering::Less
-        } else {
-            std::cmp::Ordering::Greater
-        }
-    });
-    sizes[0]
+/// Round by satisfactory precision of 6 digits
+pub fn round(x: f32) -> f32 {
+    (x * 1_000_000.).round() / 1_000_000.
 }
 
 #[derive(
@@ -1771,6 +1769,19 @@ impl Selectable for Belt {
     }
 }
 
+impl Gated for Belt {
+    fn unlocked_by(&self) -> Unlock {
+        match self {
+            Self::Mk1 => Unlock::ONBOARDING,
+            Self::Mk2 => Unlock::milestone(2, "Logistics Mk.2"),
+            Self::Mk3 => Unlock::milestone(4, "Logistics Mk.3"),
+            Self::Mk4 => Unlock::milestone(5, "Logistics Mk.4"),
+            Self::Mk5 => Unlock::milestone(7, "Logistics Mk.5"),
+            Self::Mk6 => Unlock::milestone(9, "Logistics Mk.6"),
+        }
+    }
+}
+
 impl Belt {
     pub fn speed(&self) -> f32 {
         match self {
@@ -1801,6 +1812,15 @@ pub enum Pipe {
     Mk2,
 }
 
+impl Gated for Pipe {
+    fn unlocked_by(&self) -> Unlock {
+        match self {
+            Self::Mk1 => Unlock::milestone(3, "Coal Power"),
+            Self::Mk2 => Unlock::milestone(6, "Pipeline Engineering Mk.2"),
+        }
+    }
+}
+
 impl Pipe {
     pub fn name(&self) -> String {
         self.to_string()
@@ -1923,6 +1943,540 @@ impl SomersloopSlot4 {
 mod tests {
     use super::*;
 
+    // The per-arity functions `evaluate_recipe` replaced, kept as a reference.
+
+    fn calc_output(
+        input_size: Option<f32>,
+        duration: f32,
+        output_size: f32,
+        input_base: f32,
+    ) -> f32 {
+        let a = match input_size {
+            Some(input_size) => {
+                let input_size = (input_size / 60.) * duration;
+
+                // 45/60 * 4secs = 3
+                if input_size < input_base {
+                    input_size / input_base
+                } else {
+                    1.
+                }
+            }
+            None => 1.,
+        };
+
+        // 60/4 * 1 = 15
+        let b = (60. / duration) * a * output_size;
+        round(b)
+    }
+
+    fn calc_output2(
+        input_size: Option<(f32, f32)>,
+        duration: f32,
+        output_size: f32,
+        input_base_a: f32,
+        input_base_b: f32,
+    ) -> f32 {
+        let a = match input_size {
+            Some((input_size_a, input_size_b)) => {
+                let input_size_a = (input_size_a / 60.) * duration;
+                let input_size_b = (input_size_b / 60.) * duration;
+
+                // 45/60 * 4secs = 3
+                let a = if input_size_a < input_base_a {
+                    input_size_a / input_base_a
+                } else {
+                    1.
+                };
+                let b = if input_size_b < input_base_b {
+                    input_size_b / input_base_b
+                } else {
+                    1.
+                };
+                // restrict to the minimum
+                if a < b {
+                    a
+                } else {
+                    b
+                }
+            }
+            None => 1.,
+        };
+
+        // 60/4 * 1 = 15
+        let b = (60. / duration) * a * output_size;
+        round(b)
+    }
+
+    fn calc_output3(
+        input_size: Option<(f32, f32, f32)>,
+        duration: f32,
+        output_size: f32,
+        input_base_a: f32,
+        input_base_b: f32,
+        input_base_c: f32,
+    ) -> f32 {
+        let a = match input_size {
+            Some((input_size_a, input_size_b, input_size_c)) => {
+                let input_size_a = (input_size_a / 60.) * duration;
+                let input_size_b = (input_size_b / 60.) * duration;
+                let input_size_c = (input_size_c / 60.) * duration;
+
+                // 45/60 * 4secs = 3
+                let a = if input_size_a < input_base_a {
+                    input_size_a / input_base_a
+                } else {
+                    1.
+                };
+                let b = if input_size_b < input_base_b {
+                    input_size_b / input_base_b
+                } else {
+                    1.
+                };
+                let c = if input_size_c < input_base_c {
+                    input_size_c / input_base_c
+                } else {
+                    1.
+                };
+                min3(a, b, c)
+            }
+            None => 1.,
+        };
+
+        // 60/4 * 1 = 15
+        let b = (60. / duration) * a * output_size;
+        round(b)
+    }
+
+    fn calc_output4(
+        input_size: Option<(f32, f32, f32, f32)>,
+        duration: f32,
+        output_size: f32,
+        input_base_a: f32,
+        input_base_b: f32,
+        input_base_c: f32,
+        input_base_d: f32,
+    ) -> f32 {
+        let a = match input_size {
+            Some((input_size_a, input_size_b, input_size_c, input_size_d)) => {
+                let input_size_a = (input_size_a / 60.) * duration;
+                let input_size_b = (input_size_b / 60.) * duration;
+                let input_size_c = (input_size_c / 60.) * duration;
+                let input_size_d = (input_size_d / 60.) * duration;
+
+                // 45/60 * 4secs = 3
+                let a = if input_size_a < input_base_a {
+                    input_size_a / input_base_a
+                } else {
+                    1.
+                };
+                let b = if input_size_b < input_base_b {
+                    input_size_b / input_base_b
+                } else {
+                    1.
+                };
+                let c = if input_size_c < input_base_c {
+                    input_size_c / input_base_c
+                } else {
+                    1.
+                };
+                let d = if input_size_d < input_base_d {
+                    input_size_d / input_base_d
+                } else {
+                    1.
+                };
+
+                // restrict to the minimum
+                min4(a, b, c, d)
+            }
+            None => 1.,
+        };
+
+        // 60/4 * 1 = 15
+        let b = (60. / duration) * a * output_size;
+        round(b)
+    }
+
+    fn calc_output4_2(
+        input_size: Option<(f32, f32, f32, f32)>,
+        duration: f32,
+        output_size_a: f32,
+        output_size_b: f32,
+        input_base_a: f32,
+        input_base_b: f32,
+        input_base_c: f32,
+        input_base_d: f32,
+    ) -> (f32, f32) {
+        let a = match input_size {
+            Some((input_size_a, input_size_b, input_size_c, input_size_d)) => {
+                let input_size_a = (input_size_a / 60.) * duration;
+                let input_size_b = (input_size_b / 60.) * duration;
+                let input_size_c = (input_size_c / 60.) * duration;
+                let input_size_d = (input_size_d / 60.) * duration;
+
+                let a = if input_size_a < input_base_a {
+                    input_size_a / input_base_a
+                } else {
+                    1.
+                };
+                let b = if input_size_b < input_base_b {
+                    input_size_b / input_base_b
+                } else {
+                    1.
+                };
+                let c = if input_size_c < input_base_c {
+                    input_size_c / input_base_c
+                } else {
+                    1.
+                };
+                let d = if input_size_d < input_base_d {
+                    input_size_d / input_base_d
+                } else {
+                    1.
+                };
+
+                // restrict to the minimum
+                min4(a, b, c, d)
+            }
+            None => 1.,
+        };
+
+        // 60/4 * 1 = 15
+        let b_a = round((60. / duration) * a * output_size_a);
+        let b_b = round((60. / duration) * a * output_size_b);
+        (b_a, b_b)
+    }
+
+    fn calc_output2_2(
+        input_size: Option<(f32, f32)>,
+        duration: f32,
+        output_size_a: f32,
+        output_size_b: f32,
+        input_base_a: f32,
+        input_base_b: f32,
+    ) -> (f32, f32) {
+        let a = match input_size {
+            Some((input_size_a, input_size_b)) => {
+                let input_size_a = (input_size_a / 60.) * duration;
+                let input_size_b = (input_size_b / 60.) * duration;
+
+                let a = if input_size_a < input_base_a {
+                    input_size_a / input_base_a
+                } else {
+                    1.
+                };
+                let b = if input_size_b < input_base_b {
+                    input_size_b / input_base_b
+                } else {
+                    1.
+                };
+                // restrict to the minimum
+                if a < b {
+                    a
+                } else {
+                    b
+                }
+            }
+            None => 1.,
+        };
+
+        // 60/4 * 1 = 15
+        let b_a = round((60. / duration) * a * output_size_a);
+        let b_b = round((60. / duration) * a * output_size_b);
+        (b_a, b_b)
+    }
+
+    fn min3(a: f32, b: f32, c: f32) -> f32 {
+        let mut sizes = [a, b, c];
+        sizes.sort_by(|a, b| {
+            if a < b {
+                std::cmp::Ordering::Less
+            } else {
+                std::cmp::Ordering::Greater
+            }
+        });
+        sizes[0]
+    }
+
+    fn min4(a: f32, b: f32, c: f32, d: f32) -> f32 {
+        let mut sizes = [a, b, c, d];
+        sizes.sort_by(|a, b| {
+            if a < b {
+                std::cmp::Ordering::Less
+            } else {
+                std::cmp::Ordering::Greater
+            }
+        });
+        sizes[0]
+    }
+
+    /// Deterministic pseudo random numbers, so failures are reproducible.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> f32 {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (self.0 >> 40) as f32 / (1u64 << 24) as f32
+        }
+
+        /// Amounts like the ones in recipes, between 0.5 and 100.
+        fn amount(&mut self) -> f32 {
+            round(0.5 + self.next() * 99.5)
+        }
+
+        /// Input rates, sometimes far too low and sometimes more than enough.
+        fn rate(&mut self) -> f32 {
+            round(self.next() * 600.)
+        }
+    }
+
+    #[test]
+    fn test_evaluate_recipe_matches_single_input() {
+        let mut rng = Lcg(1);
+        for _ in 0..10_000 {
+            let (duration, base, out) = (rng.amount(), rng.amount(), rng.amount());
+            let rate = (rng.next() < 0.9).then(|| rng.rate());
+
+            let new = evaluate_recipe(rate.map(|r| [r]), duration, &[base], &[out]);
+            assert_eq!(new.products[0], calc_output(rate, duration, out, base));
+        }
+    }
+
+    #[test]
+    fn test_evaluate_recipe_matches_multiple_inputs() {
+        let mut rng = Lcg(2);
+        for _ in 0..10_000 {
+            let duration = rng.amount();
+            let b = [rng.amount(), rng.amount(), rng.amount(), rng.amount()];
+            let o = [rng.amount(), rng.amount()];
+            let r = [rng.rate(), rng.rate(), rng.rate(), rng.rate()];
+            let some = rng.next() < 0.9;
+            let rates = some.then_some(r);
+
+            let new = evaluate_recipe(rates.map(|r| [r[0], r[1]]), duration, &b[..2], &o[..1]);
+            let old = calc_output2(rates.map(|r| (r[0], r[1])), duration, o[0], b[0], b[1]);
+            assert_eq!(new.products[0], old);
+
+            let new = evaluate_recipe(rates.map(|r| [r[0], r[1]]), duration, &b[..2], &o);
+            let old = calc_output2_2(
+                rates.map(|r| (r[0], r[1])),
+                duration,
+                o[0],
+                o[1],
+                b[0],
+                b[1],
+            );
+            assert_eq!((new.products[0], new.products[1]), old);
+
+            let new = evaluate_recipe(
+                rates.map(|r| [r[0], r[1], r[2]]),
+                duration,
+                &b[..3],
+                &o[..1],
+            );
+            let old = calc_output3(
+                rates.map(|r| (r[0], r[1], r[2])),
+                duration,
+                o[0],
+                b[0],
+                b[1],
+                b[2],
+            );
+            assert_eq!(new.products[0], old);
+
+            let new = evaluate_recipe(rates, duration, &b, &o[..1]);
+            let old = calc_output4(
+                rates.map(|r| (r[0], r[1], r[2], r[3])),
+                duration,
+                o[0],
+                b[0],
+                b[1],
+                b[2],
+                b[3],
+            );
+            assert_eq!(new.products[0], old);
+
+            let new = evaluate_recipe(rates, duration, &b, &o);
+            let old = calc_output4_2(
+                rates.map(|r| (r[0], r[1], r[2], r[3])),
+                duration,
+                o[0],
+                o[1],
+                b[0],
+                b[1],
+                b[2],
+                b[3],
+            );
+            assert_eq!((new.products[0], new.products[1]), old);
+
+            // the factor is the smallest input ratio, capped at 1
+            assert!(new.factor <= 1.);
+            match new.limited_by {
+                Some(i) => assert_eq!(new.factor, (r[i] / 60.) * duration / b[i]),
+                None => assert_eq!(new.factor, 1.),
+            }
+        }
+    }
+
+    #[test]
+    fn test_evaluate_recipe_limited_by() {
+        // 30 ingots/min and 100 screws/min for 6 reinforced plates per 12s
+        let output = evaluate_recipe(Some([30., 100.]), 12., &[6., 12.], &[1.]);
+        assert_eq!(output.limited_by, None);
+        assert_eq!(output.factor, 1.);
+        assert_eq!(output.products, vec![5.]);
+
+        let output = evaluate_recipe(Some([15., 100.]), 12., &[6., 12.], &[1.]);
+        assert_eq!(output.limited_by, Some(0));
+        assert_eq!(output.factor, 0.5);
+        assert_eq!(output.products, vec![2.5]);
+
+        let output = evaluate_recipe(None::<[f32; 2]>, 12., &[6., 12.], &[1., 2.]);
+        assert_eq!(output.limited_by, None);
+        assert_eq!(output.products, vec![5., 10.]);
+    }
+
+    #[test]
+    fn test_recipe_stats_from_docs() {
+        let file = std::fs::read("./tests/docs_sample.json").unwrap();
+        let db = RecipeDatabase::parse(&file).unwrap();
+
+        // the database wins over the hand-coded amounts
+        let plate = RecipeStats::new(4., [1.], [1.]).or_from(
+            &db,
+            &RecipeItems {
+                name: "Iron Plate".to_string(),
+                ingredients: [Some(Resource::Material(Material::IronIngot))],
+                products: [Some(Resource::Material(Material::IronPlate))],
+            },
+        );
+        assert_eq!(plate, RecipeStats::new(6., [3.], [2.]));
+        assert_eq!(plate.input_speeds(), [30.]);
+        assert_eq!(plate.evaluate(None).products, vec![20.]);
+
+        // fluids are converted to m^3, unused pins keep their amount
+        let leached = RecipeStats::new(6., [5., 1.], [10., 0.]).or_from(
+            &db,
+            &RecipeItems {
+                name: "Leached Iron Ingot".to_string(),
+                ingredients: [
+                    Some(Resource::Material(Material::IronOre)),
+                    Some(Resource::Fluid(Fluid::SulfuricAcid)),
+                ],
+                products: [Some(Resource::Material(Material::IronIngot)), None],
+            },
+        );
+        assert_eq!(leached.input_speeds(), [50., 10.]);
+
+        // unknown recipes and items fall back
+        let fallback = RecipeStats::new(4., [1.], [1.]);
+        let unknown = |name: &str, product| {
+            let items = RecipeItems {
+                name: name.to_string(),
+                ingredients: [Some(Resource::Material(Material::IronIngot))],
+                products: [Some(Resource::Material(product))],
+            };
+            assert!(fallback.find(&db, &items).is_none());
+            fallback.or_from(&db, &items)
+        };
+        assert_eq!(unknown("Iron Bar", Material::IronRod), fallback);
+        assert_eq!(unknown("Iron Plate", Material::Screw), fallback);
+    }
+
+    #[test]
+    fn test_items_in_docs() {
+        let file = std::fs::read("./tests/docs_sample.json").unwrap();
+        let db = RecipeDatabase::parse(&file).unwrap();
+
+        let resources = <Material as strum::VariantArray>::VARIANTS
+            .iter()
+            .map(|m| Resource::Material(*m))
+            .chain(
+                <Fluid as strum::VariantArray>::VARIANTS
+                    .iter()
+                    .map(|f| Resource::Fluid(*f)),
+            );
+        for resource in resources {
+            let item = db.items.get(resource.class_name());
+            let item = item.unwrap_or_else(|| panic!("{} is not in the docs", resource.name()));
+            assert_eq!(item.name, resource.name());
+            assert_eq!(item.fluid, matches!(resource, Resource::Fluid(_)));
+        }
+    }
+
+    #[test]
+    fn test_recipes_in_docs() {
+        let file = std::fs::read("./tests/docs_sample.json").unwrap();
+        let db = RecipeDatabase::parse(&file).unwrap();
+
+        let mut missing = Vec::new();
+        macro_rules! check {
+            ($($recipe:ty),*) => {
+                $(
+                    for recipe in <$recipe as strum::VariantArray>::VARIANTS {
+                        if recipe.stats().find(&db, &recipe.items()).is_none() {
+                            missing.push(format!("{recipe:?}"));
+                        }
+                    }
+                )*
+            };
+        }
+        check!(
+            constructor::ConstructorRecipe,
+            smelter::SmelterRecipe,
+            foundry::FoundryRecipe,
+            assembler::AssemblerRecipe,
+            manufacturer::ManufacturerRecipe,
+            refinery::RefineryRecipe,
+            packager::PackagerRecipe,
+            blender::BlenderRecipe,
+            particle_accelerator::ParticleAcceleratorRecipe,
+            encoder::QuantumEncoderRecipe,
+            converter::ConverterRecipe
+        );
+        assert!(missing.is_empty(), "not in the docs: {missing:?}");
+    }
+
+    #[test]
+    fn test_power_shards() {
+        assert_eq!(power_shards(1.), 0);
+        assert_eq!(power_shards(100.), 0);
+        assert_eq!(power_shards(100.5), 1);
+        assert_eq!(power_shards(150.), 1);
+        assert_eq!(power_shards(200.), 2);
+        assert_eq!(power_shards(250.), 3);
+    }
+
+    #[test]
+    fn test_metadata() {
+        let water = Material::PackagedWater.metadata();
+        assert_eq!(water.stack_size, Some(100));
+        assert_eq!(water.sink_points, Some(130));
+        assert_eq!(water.unpackaged, Some(Fluid::Water));
+        assert_eq!(
+            Fluid::Water.metadata().packaged,
+            Some(Material::PackagedWater)
+        );
+
+        assert_eq!(Material::Coal.metadata().energy, Some(300.));
+        assert_eq!(Fluid::Fuel.metadata().energy, Some(750.));
+        assert_eq!(Fluid::Water.metadata().energy, None);
+
+        let waste = Material::UraniumWaste.metadata();
+        assert_eq!(waste.sink_points, None);
+        assert_eq!(waste.radioactivity, Some(10.));
+        assert_eq!(Material::IronPlate.metadata().radioactivity, None);
+
+        for material in <Material as strum::VariantArray>::VARIANTS {
+            if let Some(fluid) = material.unpackaged() {
+                assert_eq!(fluid.packaged(), Some(*material));
+            }
+        }
+    }
+
     #[test]
     fn test_min4() {
         assert_eq!(min4(1., 2., 3., 4.), 1.);
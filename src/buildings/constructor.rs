@@ -1,9 +1,15 @@
+use egui::Ui;
+
 use crate::{
     node::{Input, Output, Resource},
     util::load_img,
+    widgets::{add_somersloop1_ui, add_speed_ui, recipe_selector},
 };
 
-use super::{calc_output, round, Material, Selectable, SomersloopSlot1};
+use super::{
+    clock_factor, round, BodyContext, Machine, Material, PinSpec, RecipeItems, RecipeStats,
+    Selectable, SomersloopSlot1,
+};
 
 #[derive(
     Debug,
@@ -214,51 +220,7 @@ impl ConstructorRecipe {
     }
 
     pub fn input_speed(&self) -> f32 {
-        match self {
-            Self::AlienDnaCapsule => 10.,
-            Self::AluminumCasing => 90.,
-            Self::BiomassAlienProtein => 15.,
-            Self::BiomassLeaves => 120.,
-            Self::BiomassMycelia => 15.,
-            Self::BiomassWood => 60.,
-            Self::Cable => 60.,
-            Self::Concrete => 45.,
-            Self::CopperPowder => 300.,
-            Self::CopperSheet => 20.,
-            Self::EmptyCanister => 30.,
-            Self::EmptyFluidTank => 60.,
-            Self::FicsiteTrigon => 10.,
-            Self::HatcherProtein => 20.,
-            Self::HogProtein => 20.,
-            Self::IronPlate => 30.,
-            Self::IronRebar => 15.,
-            Self::IronRod => 15.,
-            Self::PowerShard1 => 7.5,
-            Self::PowerShard2 => 5.,
-            Self::PowerShard5 => 2.5,
-            Self::QuartzCrystal => 37.5,
-            Self::Quickwire => 12.,
-            Self::ReanimatedSam => 120.,
-            Self::Screw => 10.,
-            Self::Silica => 22.5,
-            Self::SolidBiofuel => 120.,
-            Self::SpitterProtein => 20.,
-            Self::SteelBeam => 60.,
-            Self::SteelPipe => 30.,
-            Self::StingerProtein => 20.,
-            Self::Wire => 15.,
-            Self::AluminumBeam => 22.5,
-            Self::AluminumRod => 7.5,
-            Self::Biocoal => 37.5,
-            Self::CastScrew => 12.5,
-            Self::CateriumWire => 15.,
-            Self::Charcoal => 15.,
-            Self::IronPipe => 100.,
-            Self::IronWire => 12.5,
-            Self::SteelCanister => 40.,
-            Self::SteelRod => 12.,
-            Self::SteelScrew => 5.,
-        }
+        self.stats().input_speeds()[0]
     }
 
     pub fn max_output_speed(&self) -> f32 {
@@ -269,25 +231,21 @@ impl ConstructorRecipe {
         self.output_speed_inner(Some(input_size))
     }
 
-    fn output_speed_inner(&self, input_size: Option<f32>) -> f32 {
-        if input_size == Some(0.) {
-            return 0.;
-        }
-
+    pub(super) fn stats(&self) -> RecipeStats<1, 1> {
         let (input_base, duration, output_size) = match self {
             Self::AlienDnaCapsule => (1., 6., 1.),
             Self::AluminumCasing => (3., 2., 2.),
             Self::BiomassAlienProtein => (1., 4., 100.),
             Self::BiomassLeaves => (10., 5., 5.),
             Self::BiomassMycelia => (1., 4., 10.),
-            Self::BiomassWood => (1., 4., 20.),
+            Self::BiomassWood => (4., 4., 20.),
             Self::Cable => (2., 2., 1.),
             Self::Concrete => (3., 4., 1.),
             Self::CopperPowder => (30., 6., 5.),
             Self::CopperSheet => (2., 6., 1.),
             Self::EmptyCanister => (2., 4., 4.),
-            Self::EmptyFluidTank => (1., 6., 3.),
-            Self::FicsiteTrigon => (1., 3., 1.),
+            Self::EmptyFluidTank => (1., 1., 1.),
+            Self::FicsiteTrigon => (1., 6., 3.),
             Self::HatcherProtein => (1., 3., 1.),
             Self::HogProtein => (1., 3., 1.),
             Self::IronPlate => (3., 6., 2.),
@@ -319,8 +277,23 @@ impl ConstructorRecipe {
             Self::SteelRod => (1., 5., 4.),
             Self::SteelScrew => (1., 12., 52.),
         };
+        RecipeStats::new(duration, [input_base], [output_size]).or_installed(&self.items())
+    }
 
-        calc_output(input_size, duration, output_size, input_base)
+    pub(super) fn items(&self) -> RecipeItems<1, 1> {
+        RecipeItems {
+            name: self.to_string(),
+            ingredients: [Some(Resource::Material(self.input_material()))],
+            products: [Some(Resource::Material(self.output_material()))],
+        }
+    }
+
+    fn output_speed_inner(&self, input_size: Option<f32>) -> f32 {
+        if input_size == Some(0.) {
+            return 0.;
+        }
+
+        self.stats().evaluate(input_size.map(|s| [s])).products[0]
     }
 }
 
@@ -350,80 +323,130 @@ impl Constructor {
         this
     }
 
-    pub fn header_image(&self) -> String {
+    pub fn input_speed(&self) -> f32 {
+        let base = self
+            .recipe
+            .as_ref()
+            .map(|r| r.input_speed())
+            .unwrap_or_default();
+        round(base * clock_factor(self.speed))
+    }
+
+    pub fn output_speed(&self) -> f32 {
+        let clock = clock_factor(self.speed);
+        let input_speed = self
+            .current_input
+            .as_ref()
+            .map(|i| i.speed)
+            .unwrap_or_default()
+            / clock;
+
+        let base = self
+            .recipe
+            .as_ref()
+            .map(|r| r.output_speed(input_speed))
+            .unwrap_or_default();
+        let amplification = self.amplified.factor();
+
+        round(base as f32 * clock * amplification)
+    }
+
+    pub fn input_material(&self) -> Option<Material> {
+        self.recipe.as_ref().map(|r| r.input_material())
+    }
+
+    pub fn output_material(&self) -> Option<Material> {
+        self.recipe.as_ref().map(|r| r.output_material())
+    }
+}
+
+impl Machine for Constructor {
+    fn header_image(&self) -> String {
         load_img("Constructor.png")
     }
 
-    pub fn name(&self) -> String {
+    fn name(&self) -> String {
         match &self.recipe {
             Some(r) => format!("Constructor ({})", r.name()),
             None => "Constructor".to_string(),
         }
     }
 
-    pub fn description(&self) -> String {
+    fn description(&self) -> String {
         "Constructs things".to_string()
     }
 
-    pub fn num_inputs(&self) -> usize {
+    fn num_inputs(&self) -> usize {
         1
     }
 
-    pub fn num_outputs(&self) -> usize {
+    fn num_outputs(&self) -> usize {
         1
     }
 
-    pub fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
+    fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
         assert_eq!(input_id, 0, "1 input");
         crate::node::ResourceType::Material
     }
 
-    pub fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
         assert_eq!(output_id, 0, "1 output");
         crate::node::ResourceType::Material
     }
 
-    pub fn input_speed(&self) -> f32 {
-        let base = self
-            .recipe
-            .as_ref()
-            .map(|r| r.input_speed())
-            .unwrap_or_default();
-        round(base * (self.speed / 100.))
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        assert_eq!(output_id, 0, "1 output");
+        self.recipe.map(|r| Output {
+            speed: self.output_speed(),
+            resource: Resource::Material(r.output_material()),
+        })
     }
 
-    pub fn output_speed(&self) -> f32 {
-        let input_speed = self
-            .current_input
-            .as_ref()
-            .map(|i| i.speed)
-            .unwrap_or_default();
-
-        let base = self
-            .recipe
-            .as_ref()
-            .map(|r| r.output_speed(input_speed))
-            .unwrap_or_default();
-        let amplification = self.amplified.factor();
+    fn input_spec(&self, input_id: usize) -> Option<PinSpec> {
+        assert_eq!(input_id, 0, "1 input");
+        Some(PinSpec {
+            resource: self.input_material().map(Resource::Material),
+            max_speed: self.input_speed(),
+        })
+    }
 
-        // TODO: take speed into account for input_speed
+    fn output_spec(&self, output_id: usize) -> Option<PinSpec> {
+        let factor = clock_factor(self.speed) * self.amplified.factor();
+        assert_eq!(output_id, 0, "1 output");
+        Some(PinSpec {
+            resource: self.output_material().map(Resource::Material),
+            max_speed: self
+                .recipe
+                .as_ref()
+                .map(|r| round(r.max_output_speed() * factor))
+                .unwrap_or_default(),
+        })
+    }
 
-        round(base as f32 * (self.speed / 100.) * amplification)
+    fn current_input(&self, input_id: usize) -> Option<Input> {
+        assert_eq!(input_id, 0, "1 input");
+        self.current_input.clone()
     }
 
-    pub fn input_material(&self) -> Option<Material> {
-        self.recipe.as_ref().map(|r| r.input_material())
+    fn current_input_mut(&mut self, input_id: usize) -> &mut Option<Input> {
+        assert_eq!(input_id, 0, "1 input");
+        &mut self.current_input
     }
 
-    pub fn output_material(&self) -> Option<Material> {
-        self.recipe.as_ref().map(|r| r.output_material())
+    fn clock_speed(&self) -> Option<f32> {
+        Some(self.speed)
     }
 
-    pub fn current_output(&self) -> Option<Output> {
-        self.recipe.map(|r| Output {
-            speed: self.output_speed(),
-            resource: Resource::Material(r.output_material()),
-        })
+    fn show_body(&mut self, ui: &mut Ui, scale: f32, cx: &mut BodyContext) -> bool {
+        let mut changed = recipe_selector(ui, scale, &mut self.recipe, cx.unlocks).changed;
+        ui.add_space(10.0 * scale);
+
+        let matching = self.input_limited_clock();
+        changed |= add_speed_ui(ui, &mut self.speed, matching).changed;
+        ui.add_space(10.0 * scale);
+
+        changed |= add_somersloop1_ui(ui, &mut self.amplified).changed;
+        changed
     }
 }
 
@@ -440,4 +463,24 @@ mod tests {
 
         assert_eq!(ConstructorRecipe::SteelPipe.max_output_speed(), 20.);
     }
+
+    #[test]
+    fn test_overclocked_specs() {
+        let mut constructor = Constructor {
+            recipe: Some(ConstructorRecipe::SteelPipe),
+            speed: 250.,
+            current_input: Some(Input {
+                speed: 75.,
+                resource: Resource::Material(Material::SteelIngot),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(constructor.input_spec(0).unwrap().max_speed, 75.);
+        assert_eq!(constructor.output_spec(0).unwrap().max_speed, 50.);
+        assert_eq!(constructor.current_output(0).unwrap().speed, 50.);
+
+        constructor.amplified = SomersloopSlot1::One;
+        assert_eq!(constructor.output_spec(0).unwrap().max_speed, 100.);
+        assert_eq!(constructor.current_output(0).unwrap().speed, 100.);
+    }
 }
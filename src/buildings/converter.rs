@@ -1,9 +1,16 @@
+use egui::Ui;
+
 use crate::{
     node::{Input, Output, Resource},
+    unlocks::Unlock,
     util::load_img,
+    widgets::{add_somersloop2_ui, add_speed_ui, recipe_selector},
 };
 
-use super::{calc_output2_2, round, Fluid, Material, Selectable, SomersloopSlot2};
+use super::{
+    clock_factor, round, BodyContext, Fluid, Machine, Material, PinSpec, RecipeItems, RecipeStats,
+    Selectable, SomersloopSlot2,
+};
 
 macro_rules! r {
     ($($literal_name:expr => $name:ident, $input_speed_material_0:expr => $input_material_0:expr, $input_speed_material_1:expr => $input_material_1:expr, $duration:expr, $output_speed_fluid:expr => $output_fluid:expr, $output_speed_material:expr => $output_material:expr),* $(,)*) => {
@@ -72,14 +79,8 @@ macro_rules! r {
             }
 
             pub fn input_speed(&self) -> (f32, f32) {
-                match self {
-                    $(
-                        Self::$name => (
-                            (60. / $duration) * $input_speed_material_0,
-                            (60. / $duration) * $input_speed_material_1,
-                        ),
-                    )*
-                }
+                let [a, b] = self.stats().input_speeds();
+                (a, b)
             }
             pub fn max_output_speed(&self) -> (f32, f32) {
                 self.output_speed_inner(None)
@@ -96,19 +97,32 @@ macro_rules! r {
                 )))
             }
 
-            // returns
-            // (duration, material_input, material_input, fluid_output, material_output)
-            pub fn stats(&self) -> (f32, f32, f32, f32, f32) {
-                match self {
+            /// Ingredients are (material 0, material 1), products (fluid, material).
+            pub fn stats(&self) -> RecipeStats<2, 2> {
+                let stats = match self {
                     $(
-                        Self::$name => (
+                        Self::$name => RecipeStats::new(
                             $duration,
-                            $input_speed_material_0,
-                            $input_speed_material_1,
-                            $output_speed_fluid,
-                            $output_speed_material,
+                            [$input_speed_material_0, $input_speed_material_1],
+                            [$output_speed_fluid, $output_speed_material],
                         ),
                     )*
+                };
+                stats.or_installed(&self.items())
+            }
+
+            pub(super) fn items(&self) -> RecipeItems<2, 2> {
+                let (material_0, material_1) = self.input_material();
+                RecipeItems {
+                    name: self.to_string(),
+                    ingredients: [
+                        material_0.map(Resource::Material),
+                        material_1.map(Resource::Material),
+                    ],
+                    products: [
+                        self.output_fluid().map(Resource::Fluid),
+                        self.output_material().map(Resource::Material),
+                    ],
                 }
             }
         }
@@ -246,13 +260,6 @@ impl ConverterRecipe {
     fn output_speed_inner(&self, input_speed: Option<(f32, f32)>) -> (f32, f32) {
         let input_material_0_speed = input_speed.map(|(a, _)| a);
         let input_material_1_speed = input_speed.map(|(_, b)| b);
-        let (
-            duration,
-            material_0_input_speed,
-            material_1_input_speed,
-            fluid_output_speed,
-            material_output_speed,
-        ) = self.stats();
 
         if self.input_material().0.is_some() && input_material_0_speed == Some(0.0) {
             return (0., 0.);
@@ -262,14 +269,8 @@ impl ConverterRecipe {
             return (0., 0.);
         }
 
-        calc_output2_2(
-            input_speed,
-            duration,
-            fluid_output_speed,
-            material_output_speed,
-            material_0_input_speed,
-            material_1_input_speed,
-        )
+        let output = self.stats().evaluate(input_speed.map(|(a, b)| [a, b]));
+        (output.products[0], output.products[1])
     }
 }
 
@@ -304,52 +305,14 @@ impl Converter {
         }
     }
 
-    pub fn header_image(&self) -> String {
-        load_img("Converter.png")
-    }
-
-    pub fn name(&self) -> String {
-        match &self.recipe {
-            Some(r) => format!("Converter ({})", r.name()),
-            None => "Converter".to_string(),
-        }
-    }
-
-    pub fn description(&self) -> String {
-        "Converts things".to_string()
-    }
-
-    pub fn num_inputs(&self) -> usize {
-        2
-    }
-
-    pub fn num_outputs(&self) -> usize {
-        2
-    }
-    pub fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
-        match input_id {
-            0 => crate::node::ResourceType::Material,
-            1 => crate::node::ResourceType::Material,
-            _ => unreachable!("2 inputs"),
-        }
-    }
-
-    pub fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
-        match output_id {
-            0 => crate::node::ResourceType::Fluid,
-            1 => crate::node::ResourceType::Material,
-            _ => unreachable!("2 outputs"),
-        }
-    }
-
     pub fn input_speed(&self) -> (f32, f32) {
         let (base_0, base_1) = self
             .recipe
             .as_ref()
             .map(|r| r.input_speed())
             .unwrap_or_default();
-        let a = round(base_0 as f32 * (self.speed / 100.));
-        let b = round(base_1 as f32 * (self.speed / 100.));
+        let a = round(base_0 as f32 * clock_factor(self.speed));
+        let b = round(base_1 as f32 * clock_factor(self.speed));
 
         (a, b)
     }
@@ -363,16 +326,19 @@ impl Converter {
     }
 
     pub fn output_speed(&self) -> (f32, f32) {
+        let clock = clock_factor(self.speed);
         let input_material_0_speed = self
             .current_input_material_0
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let input_material_1_speed = self
             .current_input_material_1
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
 
         let (base_material_0, base_material_1) = self
             .recipe
@@ -381,10 +347,8 @@ impl Converter {
             .unwrap_or_default();
         let amplification = self.amplified.factor();
 
-        // TODO: take speed into account for input_speed
-
-        let material_0 = round(base_material_0 as f32 * (self.speed / 100.) * amplification);
-        let material_1 = round(base_material_1 as f32 * (self.speed / 100.) * amplification);
+        let material_0 = round(base_material_0 as f32 * clock * amplification);
+        let material_1 = round(base_material_1 as f32 * clock * amplification);
         (material_0, material_1)
     }
 
@@ -411,6 +375,133 @@ impl Converter {
     }
 }
 
+impl Machine for Converter {
+    fn header_image(&self) -> String {
+        load_img("Converter.png")
+    }
+
+    fn name(&self) -> String {
+        match &self.recipe {
+            Some(r) => format!("Converter ({})", r.name()),
+            None => "Converter".to_string(),
+        }
+    }
+
+    fn description(&self) -> String {
+        "Converts things".to_string()
+    }
+
+    fn unlocked_by(&self) -> Unlock {
+        Unlock::milestone(9, "Matter Conversion")
+    }
+
+    fn num_inputs(&self) -> usize {
+        2
+    }
+
+    fn num_outputs(&self) -> usize {
+        2
+    }
+
+    fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
+        match input_id {
+            0 => crate::node::ResourceType::Material,
+            1 => crate::node::ResourceType::Material,
+            _ => unreachable!("2 inputs"),
+        }
+    }
+
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+        match output_id {
+            0 => crate::node::ResourceType::Fluid,
+            1 => crate::node::ResourceType::Material,
+            _ => unreachable!("2 outputs"),
+        }
+    }
+
+    fn input_spec(&self, input_id: usize) -> Option<PinSpec> {
+        let spec = match input_id {
+            0 => PinSpec {
+                resource: self
+                    .input_material()
+                    .and_then(|b| b.0)
+                    .map(Resource::Material),
+                max_speed: self.input_speed().0,
+            },
+            1 => PinSpec {
+                resource: self
+                    .input_material()
+                    .and_then(|b| b.1)
+                    .map(Resource::Material),
+                max_speed: self.input_speed().1,
+            },
+            _ => unreachable!("2 inputs"),
+        };
+        Some(spec)
+    }
+
+    fn output_spec(&self, output_id: usize) -> Option<PinSpec> {
+        let factor = clock_factor(self.speed) * self.amplified.factor();
+        let (max_fluid, max_material) = self
+            .recipe
+            .as_ref()
+            .map(|r| r.max_output_speed())
+            .unwrap_or_default();
+        let spec = match output_id {
+            0 => PinSpec {
+                resource: self.output_fluid().map(Resource::Fluid),
+                max_speed: round(max_fluid * factor),
+            },
+            1 => PinSpec {
+                resource: self.output_material().map(Resource::Material),
+                max_speed: round(max_material * factor),
+            },
+            _ => unreachable!("2 outputs"),
+        };
+        Some(spec)
+    }
+
+    fn current_input(&self, input_id: usize) -> Option<Input> {
+        match input_id {
+            0 => self.current_input_material_0.clone(),
+            1 => self.current_input_material_1.clone(),
+            _ => unreachable!("2 inputs"),
+        }
+    }
+
+    fn current_input_mut(&mut self, input_id: usize) -> &mut Option<Input> {
+        match input_id {
+            0 => &mut self.current_input_material_0,
+            1 => &mut self.current_input_material_1,
+            _ => unreachable!("2 inputs"),
+        }
+    }
+
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        match output_id {
+            0 => self.current_output_fluid(),
+            1 => self.current_output_material(),
+            _ => unreachable!("2 outputs"),
+        }
+    }
+
+    fn clock_speed(&self) -> Option<f32> {
+        Some(self.speed)
+    }
+
+    fn show_body(&mut self, ui: &mut Ui, scale: f32, cx: &mut BodyContext) -> bool {
+        let mut changed = recipe_selector(ui, scale, &mut self.recipe, cx.unlocks).changed;
+        ui.add_space(10.0 * scale);
+
+        let matching = self.input_limited_clock();
+        changed |= add_speed_ui(ui, &mut self.speed, matching).changed;
+        ui.add_space(10.0 * scale);
+
+        changed |= add_somersloop2_ui(ui, &mut self.amplified).changed;
+        changed
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
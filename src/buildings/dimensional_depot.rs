@@ -0,0 +1,283 @@
+use egui::Ui;
+
+use crate::{
+    app::Logistics,
+    node::{Input, Output, Resource},
+    unlocks::Unlock,
+    util::load_img,
+    widgets::{general_selector, INVALID_COLOR},
+};
+
+use super::{round, BodyContext, Machine, Material, PinSpec, Selectable};
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DimensionalDepot {
+    pub current_input: Option<Input>,
+    /// Maximum upload rate in items/min, depends on the researched upgrades.
+    pub upload_rate: f32,
+}
+
+impl Default for DimensionalDepot {
+    fn default() -> Self {
+        Self {
+            current_input: None,
+            upload_rate: 60.,
+        }
+    }
+}
+
+impl DimensionalDepot {
+    pub fn clear_clone(&self) -> Self {
+        let mut this = self.clone();
+        this.current_input = None;
+        this
+    }
+
+    /// The material and rate actually uploaded, capped by the upload rate.
+    pub fn upload(&self) -> Option<(Material, f32)> {
+        let input = self.current_input.as_ref()?;
+        match input.resource {
+            Resource::Material(m) => Some((m, round(input.speed.min(self.upload_rate)))),
+            Resource::Fluid(_) => None,
+        }
+    }
+}
+
+impl Machine for DimensionalDepot {
+    fn header_image(&self) -> String {
+        // no depot image yet
+        load_img("Placeholder.png")
+    }
+
+    fn name(&self) -> String {
+        "Dimensional Depot Uploader".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Uploads things to the central storage".to_string()
+    }
+
+    fn unlocked_by(&self) -> Unlock {
+        Unlock::Research {
+            tier: 1,
+            name: "Dimensional Depot",
+        }
+    }
+
+    fn num_inputs(&self) -> usize {
+        1
+    }
+
+    fn num_outputs(&self) -> usize {
+        0
+    }
+
+    fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
+        assert_eq!(input_id, 0, "1 input");
+        crate::node::ResourceType::Material
+    }
+
+    fn output_resource(&self, _output_id: usize) -> crate::node::ResourceType {
+        unreachable!("no outputs");
+    }
+
+    fn input_spec(&self, input_id: usize) -> Option<PinSpec> {
+        assert_eq!(input_id, 0, "1 input");
+        self.current_input.as_ref().map(|i| PinSpec {
+            resource: Some(i.resource),
+            max_speed: self.upload_rate,
+        })
+    }
+
+    fn current_input(&self, input_id: usize) -> Option<Input> {
+        assert_eq!(input_id, 0, "1 input");
+        self.current_input.clone()
+    }
+
+    fn current_input_mut(&mut self, input_id: usize) -> &mut Option<Input> {
+        assert_eq!(input_id, 0, "1 input");
+        &mut self.current_input
+    }
+
+    fn show_body(&mut self, ui: &mut Ui, _scale: f32, _cx: &mut BodyContext) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            let rate = egui::DragValue::new(&mut self.upload_rate)
+                .range(0.0..=f32::MAX)
+                .suffix("/min");
+            changed |= ui.add(rate).changed();
+            ui.label("Upload rate");
+        });
+        changed
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct CentralStorage {
+    pub material: Option<Material>,
+    /// Requested rate in items/min.
+    pub speed: f32,
+    /// Its share of the rate uploaded across all tabs, updated every frame.
+    #[serde(skip)]
+    pub available: f32,
+}
+
+impl Default for CentralStorage {
+    fn default() -> Self {
+        Self {
+            material: None,
+            speed: 60.,
+            available: 0.,
+        }
+    }
+}
+
+impl CentralStorage {
+    pub fn clear_clone(&self) -> Self {
+        self.clone()
+    }
+
+    pub fn output_speed(&self) -> f32 {
+        round(self.speed.min(self.available))
+    }
+
+    pub fn output_material(&self) -> Option<Material> {
+        self.material
+    }
+
+    /// Takes its share of what is uploaded, returns whether it changed.
+    pub fn update_available(&mut self, logistics: &Logistics) -> bool {
+        let available = self
+            .material
+            .map(|m| share(self.speed, logistics.uploaded(m), logistics.drawn(m)))
+            .unwrap_or_default();
+        let changed = self.available != available;
+        self.available = available;
+        changed
+    }
+}
+
+/// When storages draw more than is uploaded, each gets a part in proportion
+/// to what it draws.
+fn share(speed: f32, uploaded: f32, drawn: f32) -> f32 {
+    if drawn <= uploaded {
+        speed
+    } else {
+        round(uploaded * speed / drawn)
+    }
+}
+
+impl Machine for CentralStorage {
+    fn header_image(&self) -> String {
+        // no depot image yet
+        load_img("Placeholder.png")
+    }
+
+    fn name(&self) -> String {
+        match &self.material {
+            Some(m) => format!("Central Storage ({})", m.name()),
+            None => "Central Storage".to_string(),
+        }
+    }
+
+    fn description(&self) -> String {
+        "Takes things out of the central storage".to_string()
+    }
+
+    fn num_inputs(&self) -> usize {
+        0
+    }
+
+    fn num_outputs(&self) -> usize {
+        1
+    }
+
+    fn input_resource(&self, _input_id: usize) -> crate::node::ResourceType {
+        unreachable!("no inputs");
+    }
+
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+        assert_eq!(output_id, 0, "1 output");
+        crate::node::ResourceType::Material
+    }
+
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        assert_eq!(output_id, 0, "1 output");
+        self.material.map(|m| Output {
+            speed: self.output_speed(),
+            resource: Resource::Material(m),
+        })
+    }
+
+    fn output_spec(&self, output_id: usize) -> Option<PinSpec> {
+        assert_eq!(output_id, 0, "1 output");
+        Some(PinSpec {
+            resource: self.output_material().map(Resource::Material),
+            max_speed: self.speed,
+        })
+    }
+
+    fn show_body(&mut self, ui: &mut Ui, scale: f32, cx: &mut BodyContext) -> bool {
+        let mut changed = general_selector(ui, scale, &mut self.material).changed;
+        ui.add_space(10.0 * scale);
+
+        ui.horizontal(|ui| {
+            let speed = egui::DragValue::new(&mut self.speed)
+                .range(0.0..=f32::MAX)
+                .suffix("/min");
+            changed |= ui.add(speed).changed();
+            ui.label("Speed");
+        });
+
+        let uploaded = self
+            .material
+            .map(|m| cx.logistics.uploaded(m))
+            .unwrap_or_default();
+        let drawn = self
+            .material
+            .map(|m| cx.logistics.drawn(m))
+            .unwrap_or_default();
+        ui.label(format!("Uploaded: {}/min", uploaded));
+        ui.label(format!("Available here: {}/min", self.available));
+        if drawn > uploaded {
+            ui.colored_label(INVALID_COLOR, format!("Drawn: {}/min", drawn));
+        } else {
+            ui.label(format!("Drawn: {}/min", drawn));
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upload_capped() {
+        let mut depot = DimensionalDepot::default();
+        assert_eq!(depot.upload(), None);
+
+        depot.current_input = Some(Input {
+            speed: 120.,
+            resource: Resource::Material(Material::Screw),
+        });
+        assert_eq!(depot.upload(), Some((Material::Screw, 60.)));
+
+        let storage = CentralStorage {
+            material: Some(Material::Screw),
+            speed: 90.,
+            available: 60.,
+        };
+        assert_eq!(storage.output_speed(), 60.);
+    }
+
+    #[test]
+    fn test_storages_share_upload() {
+        // enough for everyone
+        assert_eq!(share(30., 60., 45.), 30.);
+        // two storages asking for 60/min and 30/min of 60/min uploaded
+        assert_eq!(share(60., 60., 90.), 40.);
+        assert_eq!(share(30., 60., 90.), 20.);
+        assert_eq!(share(0., 0., 0.), 0.);
+    }
+}
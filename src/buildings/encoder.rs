@@ -1,9 +1,16 @@
+use egui::Ui;
+
 use crate::{
     node::{Input, Output, Resource},
+    unlocks::Unlock,
     util::load_img,
+    widgets::{add_somersloop4_ui, add_speed_ui, recipe_selector},
 };
 
-use super::{calc_output4_2, round, Fluid, Material, Selectable, SomersloopSlot4};
+use super::{
+    clock_factor, round, BodyContext, Fluid, Machine, Material, PinSpec, RecipeItems, RecipeStats,
+    Selectable, SomersloopSlot4,
+};
 
 macro_rules! r {
     ($($literal_name:expr => $name:ident, $input_speed_fluid_0:expr => $input_fluid_0:expr, $input_speed_material_0:expr => $input_material_0:expr, $input_speed_material_1:expr => $input_material_1:expr, $input_speed_material_2:expr => $input_material_2:expr, $duration:expr, $output_speed_fluid:expr => $output_fluid:expr, $output_speed_material:expr => $output_material:expr),* $(,)*) => {
@@ -62,16 +69,8 @@ macro_rules! r {
             }
 
             pub fn input_speed(&self) -> (f32, f32, f32, f32) {
-                match self {
-                    $(
-                        Self::$name => (
-                            (60. / $duration) * $input_speed_fluid_0,
-                            (60. / $duration) * $input_speed_material_0,
-                            (60. / $duration) * $input_speed_material_1,
-                            (60. / $duration) * $input_speed_material_2,
-                        ),
-                    )*
-                }
+                let [a, b, c, d] = self.stats().input_speeds();
+                (a, b, c, d)
             }
             pub fn max_output_speed(&self) -> (f32, f32) {
                 self.output_speed_inner(None)
@@ -92,21 +91,40 @@ macro_rules! r {
                 )))
             }
 
-            // returns
-            // (duration, fluid_input_0, material_input_0, material_input_1, material_input_2, fluid_output, material_output)
-            pub fn stats(&self) -> (f32, f32, f32, f32, f32, f32, f32) {
-                match self {
+            /// Ingredients are (fluid, material 0, material 1, material 2),
+            /// products (fluid, material).
+            pub fn stats(&self) -> RecipeStats<4, 2> {
+                let stats = match self {
                     $(
-                        Self::$name => (
+                        Self::$name => RecipeStats::new(
                             $duration,
-                            $input_speed_fluid_0,
-                            $input_speed_material_0,
-                            $input_speed_material_1,
-                            $input_speed_material_2,
-                            $output_speed_fluid,
-                            $output_speed_material,
+                            [
+                                $input_speed_fluid_0,
+                                $input_speed_material_0,
+                                $input_speed_material_1,
+                                $input_speed_material_2,
+                            ],
+                            [$output_speed_fluid, $output_speed_material],
                         ),
                     )*
+                };
+                stats.or_installed(&self.items())
+            }
+
+            pub(super) fn items(&self) -> RecipeItems<4, 2> {
+                let (fluid, material_0, material_1, material_2) = self.input_material();
+                RecipeItems {
+                    name: self.to_string(),
+                    ingredients: [
+                        Some(Resource::Fluid(fluid)),
+                        Some(Resource::Material(material_0)),
+                        Some(Resource::Material(material_1)),
+                        Some(Resource::Material(material_2)),
+                    ],
+                    products: [
+                        Some(Resource::Fluid(self.output_fluid())),
+                        Some(Resource::Material(self.output_material())),
+                    ],
                 }
             }
         }
@@ -169,15 +187,6 @@ impl QuantumEncoderRecipe {
         let input_material_0_speed = input_speed.map(|(_, b, _, _)| b);
         let input_material_1_speed = input_speed.map(|(_, _, c, _)| c);
         let input_material_2_speed = input_speed.map(|(_, _, _, d)| d);
-        let (
-            duration,
-            fluid_input_0_speed,
-            material_input_0_speed,
-            material_input_1_speed,
-            material_input_2_speed,
-            fluid_output_speed,
-            material_output_speed,
-        ) = self.stats();
 
         if input_fluid_0_speed == Some(0.0)
             || input_material_0_speed == Some(0.0)
@@ -187,16 +196,10 @@ impl QuantumEncoderRecipe {
             return (0., 0.);
         }
 
-        calc_output4_2(
-            input_speed,
-            duration,
-            fluid_output_speed,
-            material_output_speed,
-            fluid_input_0_speed,
-            material_input_0_speed,
-            material_input_1_speed,
-            material_input_2_speed,
-        )
+        let output = self
+            .stats()
+            .evaluate(input_speed.map(|(a, b, c, d)| [a, b, c, d]));
+        (output.products[0], output.products[1])
     }
 }
 
@@ -235,56 +238,16 @@ impl QuantumEncoder {
         }
     }
 
-    pub fn header_image(&self) -> String {
-        load_img("Quantum_Encoder.png")
-    }
-
-    pub fn name(&self) -> String {
-        match &self.recipe {
-            Some(r) => format!("Quantum Encoder ({})", r.name()),
-            None => "Quantum Encoder".to_string(),
-        }
-    }
-
-    pub fn description(&self) -> String {
-        "Quantum encodes things".to_string()
-    }
-
-    pub fn num_inputs(&self) -> usize {
-        4
-    }
-
-    pub fn num_outputs(&self) -> usize {
-        2
-    }
-    pub fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
-        match input_id {
-            0 => crate::node::ResourceType::Fluid,
-            1 => crate::node::ResourceType::Material,
-            2 => crate::node::ResourceType::Material,
-            3 => crate::node::ResourceType::Material,
-            _ => unreachable!("4 outputs"),
-        }
-    }
-
-    pub fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
-        match output_id {
-            0 => crate::node::ResourceType::Fluid,
-            1 => crate::node::ResourceType::Material,
-            _ => unreachable!("2 outputs"),
-        }
-    }
-
     pub fn input_speed(&self) -> (f32, f32, f32, f32) {
         let (base_0, base_1, base_2, base_3) = self
             .recipe
             .as_ref()
             .map(|r| r.input_speed())
             .unwrap_or_default();
-        let a = round(base_0 as f32 * (self.speed / 100.));
-        let b = round(base_1 as f32 * (self.speed / 100.));
-        let c = round(base_2 as f32 * (self.speed / 100.));
-        let d = round(base_3 as f32 * (self.speed / 100.));
+        let a = round(base_0 as f32 * clock_factor(self.speed));
+        let b = round(base_1 as f32 * clock_factor(self.speed));
+        let c = round(base_2 as f32 * clock_factor(self.speed));
+        let d = round(base_3 as f32 * clock_factor(self.speed));
 
         (a, b, c, d)
     }
@@ -298,26 +261,31 @@ impl QuantumEncoder {
     }
 
     pub fn output_speed(&self) -> (f32, f32) {
+        let clock = clock_factor(self.speed);
         let input_fluid_0_speed = self
             .current_input_fluid_0
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let input_material_0_speed = self
             .current_input_material_0
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let input_material_1_speed = self
             .current_input_material_1
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let input_material_2_speed = self
             .current_input_material_2
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let (base_fluid, base_material) = self
             .recipe
             .as_ref()
@@ -332,10 +300,8 @@ impl QuantumEncoder {
             .unwrap_or_default();
         let amplification = self.amplified.factor();
 
-        // TODO: take speed into account for input_speed
-
-        let fluid = round(base_fluid as f32 * (self.speed / 100.) * amplification);
-        let material = round(base_material as f32 * (self.speed / 100.) * amplification);
+        let fluid = round(base_fluid as f32 * clock * amplification);
+        let material = round(base_material as f32 * clock * amplification);
         (fluid, material)
     }
 
@@ -358,6 +324,141 @@ impl QuantumEncoder {
     }
 }
 
+impl Machine for QuantumEncoder {
+    fn header_image(&self) -> String {
+        load_img("Quantum_Encoder.png")
+    }
+
+    fn name(&self) -> String {
+        match &self.recipe {
+            Some(r) => format!("Quantum Encoder ({})", r.name()),
+            None => "Quantum Encoder".to_string(),
+        }
+    }
+
+    fn description(&self) -> String {
+        "Quantum encodes things".to_string()
+    }
+
+    fn unlocked_by(&self) -> Unlock {
+        Unlock::milestone(9, "Quantum Encoding")
+    }
+
+    fn num_inputs(&self) -> usize {
+        4
+    }
+
+    fn num_outputs(&self) -> usize {
+        2
+    }
+
+    fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
+        match input_id {
+            0 => crate::node::ResourceType::Fluid,
+            1 => crate::node::ResourceType::Material,
+            2 => crate::node::ResourceType::Material,
+            3 => crate::node::ResourceType::Material,
+            _ => unreachable!("4 outputs"),
+        }
+    }
+
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+        match output_id {
+            0 => crate::node::ResourceType::Fluid,
+            1 => crate::node::ResourceType::Material,
+            _ => unreachable!("2 outputs"),
+        }
+    }
+
+    fn input_spec(&self, input_id: usize) -> Option<PinSpec> {
+        let spec = match input_id {
+            0 => PinSpec {
+                resource: self.input_material().map(|b| Resource::Fluid(b.0)),
+                max_speed: self.input_speed().0,
+            },
+            1 => PinSpec {
+                resource: self.input_material().map(|b| Resource::Material(b.1)),
+                max_speed: self.input_speed().1,
+            },
+            2 => PinSpec {
+                resource: self.input_material().map(|b| Resource::Material(b.2)),
+                max_speed: self.input_speed().2,
+            },
+            3 => PinSpec {
+                resource: self.input_material().map(|b| Resource::Material(b.3)),
+                max_speed: self.input_speed().3,
+            },
+            _ => unreachable!("4 inputs"),
+        };
+        Some(spec)
+    }
+
+    fn output_spec(&self, output_id: usize) -> Option<PinSpec> {
+        let factor = clock_factor(self.speed) * self.amplified.factor();
+        let (max_fluid, max_material) = self
+            .recipe
+            .as_ref()
+            .map(|r| r.max_output_speed())
+            .unwrap_or_default();
+        let spec = match output_id {
+            0 => PinSpec {
+                resource: self.output_fluid().map(Resource::Fluid),
+                max_speed: round(max_fluid * factor),
+            },
+            1 => PinSpec {
+                resource: self.output_material().map(Resource::Material),
+                max_speed: round(max_material * factor),
+            },
+            _ => unreachable!("2 outputs"),
+        };
+        Some(spec)
+    }
+
+    fn current_input(&self, input_id: usize) -> Option<Input> {
+        match input_id {
+            0 => self.current_input_fluid_0.clone(),
+            1 => self.current_input_material_0.clone(),
+            2 => self.current_input_material_1.clone(),
+            3 => self.current_input_material_2.clone(),
+            _ => unreachable!("4 inputs"),
+        }
+    }
+
+    fn current_input_mut(&mut self, input_id: usize) -> &mut Option<Input> {
+        match input_id {
+            0 => &mut self.current_input_fluid_0,
+            1 => &mut self.current_input_material_0,
+            2 => &mut self.current_input_material_1,
+            3 => &mut self.current_input_material_2,
+            _ => unreachable!("4 inputs"),
+        }
+    }
+
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        match output_id {
+            0 => self.current_output_fluid(),
+            1 => self.current_output_material(),
+            _ => unreachable!("2 outputs"),
+        }
+    }
+
+    fn clock_speed(&self) -> Option<f32> {
+        Some(self.speed)
+    }
+
+    fn show_body(&mut self, ui: &mut Ui, scale: f32, cx: &mut BodyContext) -> bool {
+        let mut changed = recipe_selector(ui, scale, &mut self.recipe, cx.unlocks).changed;
+        ui.add_space(10.0 * scale);
+
+        let matching = self.input_limited_clock();
+        changed |= add_speed_ui(ui, &mut self.speed, matching).changed;
+        ui.add_space(10.0 * scale);
+
+        changed |= add_somersloop4_ui(ui, &mut self.amplified).changed;
+        changed
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
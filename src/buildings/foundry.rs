@@ -1,9 +1,16 @@
+use egui::Ui;
+
 use crate::{
     node::{Input, Output, Resource},
+    unlocks::Unlock,
     util::load_img,
+    widgets::{add_somersloop2_ui, add_speed_ui, recipe_selector},
 };
 
-use super::{calc_output2, round, Material, Selectable, SomersloopSlot2};
+use super::{
+    clock_factor, round, BodyContext, Machine, Material, PinSpec, RecipeItems, RecipeStats,
+    Selectable, SomersloopSlot2,
+};
 
 #[derive(
     Debug,
@@ -97,22 +104,8 @@ impl FoundryRecipe {
     }
 
     pub fn input_material_speed(&self) -> (f32, f32) {
-        match self {
-            Self::AluminumIngot => (90., 75.),
-            Self::SteelIngot => (45., 45.),
-            Self::BasicIronIngot => (25., 40.),
-            Self::CokeSteelIngot => (75., 75.),
-            Self::CompactedSteelIngot => (5., 2.5),
-            Self::CopperAlloyIngot => (50., 50.),
-            Self::FusedQuartzCrystal => (75., 36.),
-            Self::IronAlloyIngot => (40., 10.),
-            Self::MoldedBeam => (120., 80.),
-            Self::MoldedSteelPipe => (50., 30.),
-            Self::SolidSteelIngot => (40., 40.),
-            Self::SteelCastPlate => (15., 15.),
-            Self::TemperedCateriumIngot => (45., 15.),
-            Self::TemperedCopperIngot => (25., 40.),
-        }
+        let [a, b] = self.stats().input_speeds();
+        (a, b)
     }
 
     pub fn max_output_speed_material(&self) -> f32 {
@@ -127,10 +120,8 @@ impl FoundryRecipe {
         self.output_speed_inner(Some((input_material_size_0, input_material_size_1)))
     }
 
-    // returns
-    // (duration, material_input_0, material_input_1, material_output)
-    fn stats(&self) -> (f32, f32, f32, f32) {
-        match self {
+    pub(super) fn stats(&self) -> RecipeStats<2, 1> {
+        let (duration, input_0, input_1, output) = match self {
             Self::AluminumIngot => (4., 6., 5., 4.),
             Self::SteelIngot => (4., 3., 3., 3.),
             Self::BasicIronIngot => (12., 5., 8., 10.),
@@ -145,26 +136,33 @@ impl FoundryRecipe {
             Self::SteelCastPlate => (4., 1., 1., 3.),
             Self::TemperedCateriumIngot => (8., 6., 2., 3.),
             Self::TemperedCopperIngot => (12., 5., 8., 12.),
+        };
+        RecipeStats::new(duration, [input_0, input_1], [output]).or_installed(&self.items())
+    }
+
+    pub(super) fn items(&self) -> RecipeItems<2, 1> {
+        let (material_0, material_1) = self.input_material();
+        RecipeItems {
+            name: self.to_string(),
+            ingredients: [
+                Some(Resource::Material(material_0)),
+                Some(Resource::Material(material_1)),
+            ],
+            products: [Some(Resource::Material(self.output_material()))],
         }
     }
 
     fn output_speed_inner(&self, input_size: Option<(f32, f32)>) -> f32 {
         let input_material_0_size = input_size.map(|(a, _)| a);
         let input_material_1_size = input_size.map(|(_, b)| b);
-        let (duration, material_input_0_size, material_input_1_size, material_output_size) =
-            self.stats();
 
         if input_material_0_size == Some(0.0) || input_material_1_size == Some(0.0) {
             return 0.;
         }
 
-        calc_output2(
-            input_size,
-            duration,
-            material_output_size,
-            material_input_0_size,
-            material_input_1_size,
-        )
+        self.stats()
+            .evaluate(input_size.map(|(a, b)| [a, b]))
+            .products[0]
     }
 }
 
@@ -196,30 +194,80 @@ impl Foundry {
         this.current_input_material_1 = None;
         this
     }
-    pub fn header_image(&self) -> String {
+    pub fn input_material_speed(&self) -> (f32, f32) {
+        let (base_0, base_1) = self
+            .recipe
+            .as_ref()
+            .map(|r| r.input_material_speed())
+            .unwrap_or_default();
+        let a = round(base_0 as f32 * clock_factor(self.speed));
+        let b = round(base_1 as f32 * clock_factor(self.speed));
+
+        (a, b)
+    }
+
+    pub fn output_material(&self) -> Option<Material> {
+        self.recipe.as_ref().map(|r| r.output_material())
+    }
+
+    pub fn output_speed(&self) -> f32 {
+        let clock = clock_factor(self.speed);
+        let input_material_0_speed = self
+            .current_input_material_0
+            .as_ref()
+            .map(|i| i.speed)
+            .unwrap_or_default()
+            / clock;
+        let input_material_1_speed = self
+            .current_input_material_1
+            .as_ref()
+            .map(|i| i.speed)
+            .unwrap_or_default()
+            / clock;
+        let base = self
+            .recipe
+            .as_ref()
+            .map(|r| r.output_speed_material(input_material_0_speed, input_material_1_speed))
+            .unwrap_or_default();
+        let amplification = self.amplified.factor();
+
+        round(base as f32 * clock * amplification)
+    }
+
+    pub fn input_material(&self) -> Option<(Material, Material)> {
+        self.recipe.map(|r| r.input_material())
+    }
+}
+
+impl Machine for Foundry {
+    fn header_image(&self) -> String {
         load_img("Foundry.png")
     }
 
-    pub fn name(&self) -> String {
+    fn name(&self) -> String {
         match &self.recipe {
             Some(r) => format!("Foundry ({})", r.name()),
             None => "Foundry".to_string(),
         }
     }
 
-    pub fn description(&self) -> String {
+    fn description(&self) -> String {
         "Smelts more things".to_string()
     }
 
-    pub fn num_inputs(&self) -> usize {
+    fn unlocked_by(&self) -> Unlock {
+        Unlock::milestone(3, "Basic Steel Production")
+    }
+
+    fn num_inputs(&self) -> usize {
         2
     }
 
-    pub fn num_outputs(&self) -> usize {
+    fn num_outputs(&self) -> usize {
         1
     }
 
-    pub fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
+    fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
         match input_id {
             0 => crate::node::ResourceType::Material,
             1 => crate::node::ResourceType::Material,
@@ -227,61 +275,79 @@ impl Foundry {
         }
     }
 
-    pub fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
         match output_id {
             0 => crate::node::ResourceType::Material,
             _ => unreachable!("1 output"),
         }
     }
 
-    pub fn input_material_speed(&self) -> (f32, f32) {
-        let (base_0, base_1) = self
-            .recipe
-            .as_ref()
-            .map(|r| r.input_material_speed())
-            .unwrap_or_default();
-        let a = round(base_0 as f32 * (self.speed / 100.));
-        let b = round(base_1 as f32 * (self.speed / 100.));
-
-        (a, b)
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        assert_eq!(output_id, 0, "1 output");
+        self.recipe.map(|r| Output {
+            speed: self.output_speed(),
+            resource: Resource::Material(r.output_material()),
+        })
     }
 
-    pub fn output_material(&self) -> Option<Material> {
-        self.recipe.as_ref().map(|r| r.output_material())
+    fn input_spec(&self, input_id: usize) -> Option<PinSpec> {
+        let spec = match input_id {
+            0 => PinSpec {
+                resource: self.input_material().map(|(a, _)| Resource::Material(a)),
+                max_speed: self.input_material_speed().0,
+            },
+            1 => PinSpec {
+                resource: self.input_material().map(|(_, b)| Resource::Material(b)),
+                max_speed: self.input_material_speed().1,
+            },
+            _ => unreachable!("2 inputs"),
+        };
+        Some(spec)
     }
 
-    pub fn output_speed(&self) -> f32 {
-        let input_material_0_speed = self
-            .current_input_material_0
-            .as_ref()
-            .map(|i| i.speed)
-            .unwrap_or_default();
-        let input_material_1_speed = self
-            .current_input_material_1
-            .as_ref()
-            .map(|i| i.speed)
-            .unwrap_or_default();
-        let base = self
-            .recipe
-            .as_ref()
-            .map(|r| r.output_speed_material(input_material_0_speed, input_material_1_speed))
-            .unwrap_or_default();
-        let amplification = self.amplified.factor();
+    fn output_spec(&self, output_id: usize) -> Option<PinSpec> {
+        let factor = clock_factor(self.speed) * self.amplified.factor();
+        assert_eq!(output_id, 0, "1 output");
+        Some(PinSpec {
+            resource: self.output_material().map(Resource::Material),
+            max_speed: self
+                .recipe
+                .as_ref()
+                .map(|r| round(r.max_output_speed_material() * factor))
+                .unwrap_or_default(),
+        })
+    }
 
-        // TODO: take speed into account for input_speed
+    fn current_input(&self, input_id: usize) -> Option<Input> {
+        match input_id {
+            0 => self.current_input_material_0.clone(),
+            1 => self.current_input_material_1.clone(),
+            _ => unreachable!("2 inputs"),
+        }
+    }
 
-        round(base as f32 * (self.speed / 100.) * amplification)
+    fn current_input_mut(&mut self, input_id: usize) -> &mut Option<Input> {
+        match input_id {
+            0 => &mut self.current_input_material_0,
+            1 => &mut self.current_input_material_1,
+            _ => unreachable!("2 inputs"),
+        }
     }
 
-    pub fn input_material(&self) -> Option<(Material, Material)> {
-        self.recipe.map(|r| r.input_material())
+    fn clock_speed(&self) -> Option<f32> {
+        Some(self.speed)
     }
 
-    pub fn current_output(&self) -> Option<Output> {
-        self.recipe.map(|r| Output {
-            speed: self.output_speed(),
-            resource: Resource::Material(r.output_material()),
-        })
+    fn show_body(&mut self, ui: &mut Ui, scale: f32, cx: &mut BodyContext) -> bool {
+        let mut changed = recipe_selector(ui, scale, &mut self.recipe, cx.unlocks).changed;
+        ui.add_space(10.0 * scale);
+
+        let matching = self.input_limited_clock();
+        changed |= add_speed_ui(ui, &mut self.speed, matching).changed;
+        ui.add_space(10.0 * scale);
+
+        changed |= add_somersloop2_ui(ui, &mut self.amplified).changed;
+        changed
     }
 }
 
@@ -1,9 +1,16 @@
+use egui::Ui;
+
 use crate::{
     node::{Input, Output, Resource},
+    unlocks::Unlock,
     util::load_img,
+    widgets::{add_somersloop4_ui, add_speed_ui, recipe_selector},
 };
 
-use super::{calc_output4, round, Material, Selectable, SomersloopSlot4};
+use super::{
+    clock_factor, round, BodyContext, Machine, Material, PinSpec, RecipeItems, RecipeStats,
+    Selectable, SomersloopSlot4,
+};
 
 macro_rules! r {
     ($($literal_name:expr => $name:ident, $input_speed_0:expr => $input_material_0:expr, $input_speed_1:expr => $input_material_1:expr, $input_speed_2:expr => $input_material_2:expr, $input_speed_3:expr => $input_material_3:expr, $duration:expr, $output_speed:expr, $output_material:expr),* $(,)*) => {
@@ -54,16 +61,8 @@ macro_rules! r {
             }
 
             pub fn input_material_speed(&self) -> (f32, f32, f32, f32) {
-                match self {
-                    $(
-                        Self::$name => (
-                            (60. / $duration) * $input_speed_0,
-                            (60. / $duration) * $input_speed_1,
-                            (60. / $duration) * $input_speed_2,
-                            (60. / $duration) * $input_speed_3,
-                        ),
-                    )*
-                }
+                let [a, b, c, d] = self.stats().input_speeds();
+                (a, b, c, d)
             }
             pub fn max_output_speed_material(&self) -> f32 {
                 self.output_speed_inner(None)
@@ -84,20 +83,30 @@ macro_rules! r {
                 )))
             }
 
-            // returns
-            // (duration, material_input_0, material_input_1, material_input_2, material_input_3, material_output)
-            pub fn stats(&self) -> (f32, f32, f32, f32, f32, f32) {
-                match self {
+            pub fn stats(&self) -> RecipeStats<4, 1> {
+                let stats = match self {
                     $(
-                        Self::$name => (
+                        Self::$name => RecipeStats::new(
                             $duration,
-                            $input_speed_0,
-                            $input_speed_1,
-                            $input_speed_2,
-                            $input_speed_3,
-                            $output_speed,
+                            [$input_speed_0, $input_speed_1, $input_speed_2, $input_speed_3],
+                            [$output_speed],
                         ),
                     )*
+                };
+                stats.or_installed(&self.items())
+            }
+
+            pub(super) fn items(&self) -> RecipeItems<4, 1> {
+                let (material_0, material_1, material_2, material_3) = self.input_material();
+                RecipeItems {
+                    name: self.to_string(),
+                    ingredients: [
+                        Some(Resource::Material(material_0)),
+                        Some(Resource::Material(material_1)),
+                        Some(Resource::Material(material_2)),
+                        material_3.map(Resource::Material),
+                    ],
+                    products: [Some(Resource::Material(self.output_material()))],
                 }
             }
         }
@@ -365,14 +374,6 @@ impl ManufacturerRecipe {
         let input_material_1_size = input_size.map(|(_, b, _, _)| b);
         let input_material_2_size = input_size.map(|(_, _, c, _)| c);
         let input_material_3_size = input_size.map(|(_, _, _, d)| d);
-        let (
-            duration,
-            material_input_0_size,
-            material_input_1_size,
-            material_input_2_size,
-            material_input_3_size,
-            material_output_size,
-        ) = self.stats();
 
         if input_material_0_size == Some(0.0)
             || input_material_1_size == Some(0.0)
@@ -385,15 +386,9 @@ impl ManufacturerRecipe {
             return 0.;
         }
 
-        calc_output4(
-            input_size,
-            duration,
-            material_output_size,
-            material_input_0_size,
-            material_input_1_size,
-            material_input_2_size,
-            material_input_3_size,
-        )
+        self.stats()
+            .evaluate(input_size.map(|(a, b, c, d)| [a, b, c, d]))
+            .products[0]
     }
 }
 
@@ -432,55 +427,16 @@ impl Manufacturer {
         this
     }
 
-    pub fn header_image(&self) -> String {
-        load_img("Manufacturer.png")
-    }
-
-    pub fn name(&self) -> String {
-        match &self.recipe {
-            Some(r) => format!("Manufacturer ({})", r.name()),
-            None => "Manufacturer".to_string(),
-        }
-    }
-
-    pub fn description(&self) -> String {
-        "Smelts more things".to_string()
-    }
-
-    pub fn num_inputs(&self) -> usize {
-        4
-    }
-
-    pub fn num_outputs(&self) -> usize {
-        1
-    }
-    pub fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
-        match input_id {
-            0 => crate::node::ResourceType::Material,
-            1 => crate::node::ResourceType::Material,
-            2 => crate::node::ResourceType::Material,
-            3 => crate::node::ResourceType::Material,
-            _ => unreachable!("4 outputs"),
-        }
-    }
-
-    pub fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
-        match output_id {
-            0 => crate::node::ResourceType::Material,
-            _ => unreachable!("1 output"),
-        }
-    }
-
     pub fn input_material_speed(&self) -> (f32, f32, f32, f32) {
         let (base_0, base_1, base_2, base_3) = self
             .recipe
             .as_ref()
             .map(|r| r.input_material_speed())
             .unwrap_or_default();
-        let a = round(base_0 as f32 * (self.speed / 100.));
-        let b = round(base_1 as f32 * (self.speed / 100.));
-        let c = round(base_2 as f32 * (self.speed / 100.));
-        let d = round(base_3 as f32 * (self.speed / 100.));
+        let a = round(base_0 as f32 * clock_factor(self.speed));
+        let b = round(base_1 as f32 * clock_factor(self.speed));
+        let c = round(base_2 as f32 * clock_factor(self.speed));
+        let d = round(base_3 as f32 * clock_factor(self.speed));
 
         (a, b, c, d)
     }
@@ -490,26 +446,31 @@ impl Manufacturer {
     }
 
     pub fn output_speed(&self) -> f32 {
+        let clock = clock_factor(self.speed);
         let input_material_0_speed = self
             .current_input_material_0
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let input_material_1_speed = self
             .current_input_material_1
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let input_material_2_speed = self
             .current_input_material_2
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let input_material_3_speed = self
             .current_input_material_3
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let base = self
             .recipe
             .as_ref()
@@ -524,21 +485,146 @@ impl Manufacturer {
             .unwrap_or_default();
         let amplification = self.amplified.factor();
 
-        // TODO: take speed into account for input_speed
-
-        round(base as f32 * (self.speed / 100.) * amplification)
+        round(base as f32 * clock * amplification)
     }
 
     pub fn input_material(&self) -> Option<(Material, Material, Material, Option<Material>)> {
         self.recipe.map(|r| r.input_material())
     }
+}
+
+impl Machine for Manufacturer {
+    fn header_image(&self) -> String {
+        load_img("Manufacturer.png")
+    }
 
-    pub fn current_output(&self) -> Option<Output> {
+    fn name(&self) -> String {
+        match &self.recipe {
+            Some(r) => format!("Manufacturer ({})", r.name()),
+            None => "Manufacturer".to_string(),
+        }
+    }
+
+    fn description(&self) -> String {
+        "Smelts more things".to_string()
+    }
+
+    fn unlocked_by(&self) -> Unlock {
+        Unlock::milestone(5, "Industrial Manufacturing")
+    }
+
+    fn num_inputs(&self) -> usize {
+        4
+    }
+
+    fn num_outputs(&self) -> usize {
+        1
+    }
+
+    fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
+        match input_id {
+            0 => crate::node::ResourceType::Material,
+            1 => crate::node::ResourceType::Material,
+            2 => crate::node::ResourceType::Material,
+            3 => crate::node::ResourceType::Material,
+            _ => unreachable!("4 outputs"),
+        }
+    }
+
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+        match output_id {
+            0 => crate::node::ResourceType::Material,
+            _ => unreachable!("1 output"),
+        }
+    }
+
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        assert_eq!(output_id, 0, "1 output");
         self.recipe.map(|r| Output {
             speed: self.output_speed(),
             resource: Resource::Material(r.output_material()),
         })
     }
+
+    fn input_spec(&self, input_id: usize) -> Option<PinSpec> {
+        let spec = match input_id {
+            0 => PinSpec {
+                resource: self
+                    .input_material()
+                    .map(|(a, _, _, _)| Resource::Material(a)),
+                max_speed: self.input_material_speed().0,
+            },
+            1 => PinSpec {
+                resource: self
+                    .input_material()
+                    .map(|(_, b, _, _)| Resource::Material(b)),
+                max_speed: self.input_material_speed().1,
+            },
+            2 => PinSpec {
+                resource: self
+                    .input_material()
+                    .map(|(_, _, c, _)| Resource::Material(c)),
+                max_speed: self.input_material_speed().2,
+            },
+            3 => PinSpec {
+                resource: self
+                    .input_material()
+                    .and_then(|(_, _, _, d)| d.map(Resource::Material)),
+                max_speed: self.input_material_speed().3,
+            },
+            _ => unreachable!("4 inputs"),
+        };
+        Some(spec)
+    }
+
+    fn output_spec(&self, output_id: usize) -> Option<PinSpec> {
+        let factor = clock_factor(self.speed) * self.amplified.factor();
+        assert_eq!(output_id, 0, "1 output");
+        Some(PinSpec {
+            resource: self.output_material().map(Resource::Material),
+            max_speed: self
+                .recipe
+                .as_ref()
+                .map(|r| round(r.max_output_speed_material() * factor))
+                .unwrap_or_default(),
+        })
+    }
+
+    fn current_input(&self, input_id: usize) -> Option<Input> {
+        match input_id {
+            0 => self.current_input_material_0.clone(),
+            1 => self.current_input_material_1.clone(),
+            2 => self.current_input_material_2.clone(),
+            3 => self.current_input_material_3.clone(),
+            _ => unreachable!("4 inputs"),
+        }
+    }
+
+    fn current_input_mut(&mut self, input_id: usize) -> &mut Option<Input> {
+        match input_id {
+            0 => &mut self.current_input_material_0,
+            1 => &mut self.current_input_material_1,
+            2 => &mut self.current_input_material_2,
+            3 => &mut self.current_input_material_3,
+            _ => unreachable!("4 inputs"),
+        }
+    }
+
+    fn clock_speed(&self) -> Option<f32> {
+        Some(self.speed)
+    }
+
+    fn show_body(&mut self, ui: &mut Ui, scale: f32, cx: &mut BodyContext) -> bool {
+        let mut changed = recipe_selector(ui, scale, &mut self.recipe, cx.unlocks).changed;
+        ui.add_space(10.0 * scale);
+
+        let matching = self.input_limited_clock();
+        changed |= add_speed_ui(ui, &mut self.speed, matching).changed;
+        ui.add_space(10.0 * scale);
+
+        changed |= add_somersloop4_ui(ui, &mut self.amplified).changed;
+        changed
+    }
 }
 
 #[cfg(test)]
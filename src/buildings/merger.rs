@@ -1,9 +1,10 @@
 use crate::{
     node::{Input, Output},
+    unlocks::Unlock,
     util::load_img,
 };
 
-use super::Material;
+use super::{Machine, Material};
 
 #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Merger {
@@ -22,41 +23,48 @@ impl Merger {
         this
     }
 
-    pub fn header_image(&self) -> String {
+    pub fn input_material(&self) -> Option<Material> {
+        None
+    }
+}
+
+impl Machine for Merger {
+    fn header_image(&self) -> String {
         load_img("Conveyor_Merger.png")
     }
 
-    pub fn name(&self) -> String {
+    fn name(&self) -> String {
         "Merger".to_string()
     }
 
-    pub fn description(&self) -> String {
+    fn description(&self) -> String {
         "Merges things".to_string()
     }
 
-    pub fn num_inputs(&self) -> usize {
+    fn unlocked_by(&self) -> Unlock {
+        Unlock::milestone(1, "Logistics")
+    }
+
+    fn num_inputs(&self) -> usize {
         3
     }
 
-    pub fn num_outputs(&self) -> usize {
+    fn num_outputs(&self) -> usize {
         1
     }
 
-    pub fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
+    fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
         assert!(input_id < 3, "3 inputs");
         crate::node::ResourceType::Material
     }
 
-    pub fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
         assert_eq!(output_id, 0, "1 output");
         crate::node::ResourceType::Material
     }
 
-    pub fn input_material(&self) -> Option<Material> {
-        None
-    }
-
-    pub fn current_output(&self) -> Option<Output> {
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        assert_eq!(output_id, 0, "1 output");
         let mut speed = 0.;
         let mut resource = None;
 
@@ -85,4 +93,22 @@ impl Merger {
 
         resource.map(|r| Output { speed, resource: r })
     }
+
+    fn current_input(&self, input_id: usize) -> Option<Input> {
+        match input_id {
+            0 => self.current_input_0.clone(),
+            1 => self.current_input_1.clone(),
+            2 => self.current_input_2.clone(),
+            _ => unreachable!("3 inputs"),
+        }
+    }
+
+    fn current_input_mut(&mut self, input_id: usize) -> &mut Option<Input> {
+        match input_id {
+            0 => &mut self.current_input_0,
+            1 => &mut self.current_input_1,
+            2 => &mut self.current_input_2,
+            _ => unreachable!("3 inputs"),
+        }
+    }
 }
@@ -1,9 +1,16 @@
+use egui::Ui;
+
 use crate::{
     node::{Output, Resource},
+    unlocks::{Gated, Unlock},
     util::load_img,
+    widgets::{
+        add_speed_ui, extraction_ui, gated_selector, general_selector, level_selector,
+        purity_selector,
+    },
 };
 
-use super::{round, Material, ResourceType, Selectable};
+use super::{round, Belt, BodyContext, Machine, Material, ResourceType, Selectable};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Miner {
@@ -11,6 +18,9 @@ pub struct Miner {
     pub resource_purity: ResourcePurity,
     pub level: MinerLevel,
     pub speed: f32,
+    /// Caps the output, `None` until a belt is picked.
+    #[serde(default)]
+    pub output_belt: Option<Belt>,
 }
 
 impl Default for Miner {
@@ -20,6 +30,7 @@ impl Default for Miner {
             resource_purity: ResourcePurity::Normal,
             level: MinerLevel::Mk1,
             speed: 100.,
+            output_belt: None,
         }
     }
 }
@@ -51,6 +62,16 @@ impl MinerLevel {
     }
 }
 
+impl Gated for MinerLevel {
+    fn unlocked_by(&self) -> Unlock {
+        match self {
+            Self::Mk1 => Unlock::ONBOARDING,
+            Self::Mk2 => Unlock::milestone(4, "Advanced Steel Production"),
+            Self::Mk3 => Unlock::milestone(8, "Leading-edge Production"),
+        }
+    }
+}
+
 impl Miner {
     pub fn clear_clone(&self) -> Self {
         Self {
@@ -58,10 +79,39 @@ impl Miner {
             resource_purity: self.resource_purity.clone(),
             level: self.level.clone(),
             speed: self.speed.clone(),
+            output_belt: self.output_belt,
         }
     }
 
-    pub fn header_image(&self) -> String {
+    /// What the miner extracts, before the output belt limits it.
+    pub fn theoretical_speed(&self) -> f32 {
+        match self.resource {
+            Some(_) => {
+                // (Mining Speed) in items/min = (Purity Modifier) * (Overclock percentage) / 100 * (Default Mining Speed) items/min
+                let val = self.resource_purity.modifier()
+                    * (self.speed / 100.)
+                    * self.level.mining_speed() as f32;
+                round(val)
+            }
+            None => 0.,
+        }
+    }
+
+    pub fn output_speed(&self) -> f32 {
+        let speed = self.theoretical_speed();
+        match self.output_belt {
+            Some(belt) => speed.min(belt.speed()),
+            None => speed,
+        }
+    }
+
+    pub fn output_material(&self) -> Option<Material> {
+        self.resource.map(|r| r.output_material())
+    }
+}
+
+impl Machine for Miner {
+    fn header_image(&self) -> String {
         let name = match self.level {
             MinerLevel::Mk1 => "Miner_Mk.1.png",
             MinerLevel::Mk2 => "Miner_Mk.2.png",
@@ -70,7 +120,7 @@ impl Miner {
         load_img(name)
     }
 
-    pub fn name(&self) -> String {
+    fn name(&self) -> String {
         match &self.resource {
             Some(r) => format!(
                 "Miner {:?} ({} {})",
@@ -82,57 +132,60 @@ impl Miner {
         }
     }
 
-    pub fn description(&self) -> String {
+    fn description(&self) -> String {
         "Mines things".to_string()
     }
 
-    pub fn num_inputs(&self) -> usize {
-        0
+    fn unlocked_by(&self) -> Unlock {
+        self.level.unlocked_by()
     }
 
-    pub fn num_outputs(&self) -> usize {
-        1
+    fn num_inputs(&self) -> usize {
+        0
     }
 
-    pub fn input_speed(&self) -> usize {
-        0
+    fn num_outputs(&self) -> usize {
+        1
     }
 
-    pub fn input_resource(&self, _input_id: usize) -> crate::node::ResourceType {
+    fn input_resource(&self, _input_id: usize) -> crate::node::ResourceType {
         unreachable!("no inputs");
     }
 
-    pub fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
         assert_eq!(output_id, 0, "1 output");
         crate::node::ResourceType::Material
     }
 
-    pub fn output_speed(&self) -> f32 {
-        match self.resource {
-            Some(_) => {
-                // (Mining Speed) in items/min = (Purity Modifier) * (Overclock percentage) / 100 * (Default Mining Speed) items/min
-                let val = self.resource_purity.modifier()
-                    * (self.speed / 100.)
-                    * self.level.mining_speed() as f32;
-                round(val)
-            }
-            None => 0.,
-        }
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        assert_eq!(output_id, 0, "1 output");
+        self.output_material().map(|m| Output {
+            speed: self.output_speed(),
+            resource: Resource::Material(m),
+        })
     }
 
-    pub fn input_material(&self) -> Option<Material> {
-        None
+    fn is_output_limited(&self, output_id: usize) -> bool {
+        assert_eq!(output_id, 0, "1 output");
+        self.output_speed() < self.theoretical_speed()
     }
 
-    pub fn output_material(&self) -> Option<Material> {
-        self.resource.map(|r| r.output_material())
-    }
+    fn show_body(&mut self, ui: &mut Ui, scale: f32, cx: &mut BodyContext) -> bool {
+        let mut changed = general_selector(ui, scale, &mut self.resource).changed;
+        ui.add_space(10.0 * scale);
 
-    pub fn current_output(&self) -> Option<Output> {
-        self.resource.map(|r| Output {
-            speed: self.output_speed(),
-            resource: Resource::Material(r.output_material()),
-        })
+        changed |= level_selector(ui, scale, &mut self.level, cx.unlocks).changed;
+        ui.add_space(10.0 * scale);
+
+        changed |= purity_selector(ui, scale, &mut self.resource_purity).changed;
+        ui.add_space(10.0 * scale);
+
+        changed |= gated_selector(ui, scale, &mut self.output_belt, cx.unlocks).changed;
+        ui.add_space(10.0 * scale);
+
+        changed |= add_speed_ui(ui, &mut self.speed, None).changed;
+        extraction_ui(ui, self.theoretical_speed(), self.output_speed(), "min");
+        changed
     }
 }
 
@@ -162,3 +215,29 @@ impl ResourcePurity {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_capped_by_belt() {
+        let mut miner = Miner {
+            resource: Some(ResourceType::IronOre),
+            resource_purity: ResourcePurity::Pure,
+            level: MinerLevel::Mk3,
+            speed: 250.,
+            output_belt: None,
+        };
+        assert_eq!(miner.theoretical_speed(), 1200.);
+        assert_eq!(miner.output_speed(), 1200.);
+        assert!(!miner.is_output_limited(0));
+
+        miner.output_belt = Some(Belt::Mk5);
+        assert_eq!(miner.output_speed(), 780.);
+        assert!(miner.is_output_limited(0));
+
+        miner.output_belt = Some(Belt::Mk6);
+        assert!(!miner.is_output_limited(0));
+    }
+}
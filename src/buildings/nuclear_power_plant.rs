@@ -0,0 +1,306 @@
+use egui::Ui;
+
+use crate::{
+    node::{Input, Output, Resource},
+    unlocks::Unlock,
+    util::load_img,
+    widgets::{add_speed_ui, general_selector},
+};
+
+use super::{clock_factor, round, BodyContext, Fluid, Machine, Material, PinSpec, Selectable};
+
+/// Power generated at 100% clock speed, in MW.
+const POWER: f32 = 2500.;
+
+/// Water used at 100% clock speed, in m^3/min.
+const WATER: f32 = 240.;
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    serde::Serialize,
+    serde::Deserialize,
+    PartialEq,
+    strum::Display,
+    strum::VariantArray,
+)]
+pub enum NuclearFuel {
+    #[strum(to_string = "Uranium Fuel Rod")]
+    Uranium,
+    #[strum(to_string = "Plutonium Fuel Rod")]
+    Plutonium,
+    #[strum(to_string = "Ficsonium Fuel Rod")]
+    Ficsonium,
+}
+
+impl Selectable for NuclearFuel {
+    const NAME: &'static str = "Fuel";
+
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn image(&self) -> String {
+        self.fuel_rod().image()
+    }
+}
+
+impl NuclearFuel {
+    pub fn fuel_rod(&self) -> Material {
+        match self {
+            Self::Uranium => Material::UraniumFuelRod,
+            Self::Plutonium => Material::PlutoniumFuelRod,
+            Self::Ficsonium => Material::FicsoniumFuelRod,
+        }
+    }
+
+    /// Ficsonium burns up without leaving waste behind.
+    pub fn waste(&self) -> Option<Material> {
+        match self {
+            Self::Uranium => Some(Material::UraniumWaste),
+            Self::Plutonium => Some(Material::PlutoniumWaste),
+            Self::Ficsonium => None,
+        }
+    }
+
+    /// Waste left behind by each burned rod.
+    pub fn waste_per_rod(&self) -> f32 {
+        match self {
+            Self::Uranium => 50.,
+            Self::Plutonium => 10.,
+            Self::Ficsonium => 0.,
+        }
+    }
+
+    /// Rods/min burned at 100% clock speed.
+    pub fn rods_per_minute(&self) -> f32 {
+        let energy = self.fuel_rod().energy().unwrap_or(f32::INFINITY);
+        60. * POWER / energy
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct NuclearPowerPlant {
+    pub fuel: Option<NuclearFuel>,
+    pub speed: f32,
+    pub current_input_fuel: Option<Input>,
+    pub current_input_water: Option<Input>,
+}
+
+impl Default for NuclearPowerPlant {
+    fn default() -> Self {
+        Self {
+            fuel: None,
+            speed: 100.,
+            current_input_fuel: None,
+            current_input_water: None,
+        }
+    }
+}
+
+impl NuclearPowerPlant {
+    pub fn clear_clone(&self) -> Self {
+        let mut this = self.clone();
+        this.current_input_fuel = None;
+        this.current_input_water = None;
+        this
+    }
+
+    pub fn fuel_speed(&self) -> f32 {
+        let base = self.fuel.map(|f| f.rods_per_minute()).unwrap_or_default();
+        base * clock_factor(self.speed)
+    }
+
+    pub fn water_speed(&self) -> f32 {
+        round(WATER * clock_factor(self.speed))
+    }
+
+    /// How much of its clock speed the plant can run at with what it is fed.
+    fn supplied(&self) -> f32 {
+        let Some(fuel) = self.fuel else {
+            return 0.;
+        };
+        let rods = match &self.current_input_fuel {
+            Some(input) if input.resource == Resource::Material(fuel.fuel_rod()) => input.speed,
+            _ => 0.,
+        };
+        let water = match &self.current_input_water {
+            Some(input) if input.resource == Resource::Fluid(Fluid::Water) => input.speed,
+            _ => 0.,
+        };
+        (rods / self.fuel_speed())
+            .min(water / self.water_speed())
+            .min(1.)
+    }
+
+    /// MW generated with the current inputs.
+    pub fn power(&self) -> f32 {
+        round(POWER * clock_factor(self.speed) * self.supplied())
+    }
+
+    /// Waste/min produced when running flat out.
+    pub fn max_waste_speed(&self) -> f32 {
+        self.fuel
+            .map(|f| round(self.fuel_speed() * f.waste_per_rod()))
+            .unwrap_or_default()
+    }
+
+    /// Waste/min produced with the current inputs.
+    pub fn waste_speed(&self) -> f32 {
+        round(self.max_waste_speed() * self.supplied())
+    }
+
+    pub fn waste_material(&self) -> Option<Material> {
+        self.fuel.and_then(|f| f.waste())
+    }
+}
+
+impl Machine for NuclearPowerPlant {
+    fn header_image(&self) -> String {
+        // no nuclear power plant image yet
+        load_img("Placeholder.png")
+    }
+
+    fn name(&self) -> String {
+        match &self.fuel {
+            Some(f) => format!("Nuclear Power Plant ({})", f.name()),
+            None => "Nuclear Power Plant".to_string(),
+        }
+    }
+
+    fn description(&self) -> String {
+        "Burns fuel rods to generate power, leaving nuclear waste".to_string()
+    }
+
+    fn unlocked_by(&self) -> Unlock {
+        Unlock::milestone(8, "Nuclear Power")
+    }
+
+    fn num_inputs(&self) -> usize {
+        2
+    }
+
+    fn num_outputs(&self) -> usize {
+        1
+    }
+
+    fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
+        match input_id {
+            0 => crate::node::ResourceType::Material,
+            1 => crate::node::ResourceType::Fluid,
+            _ => unreachable!("2 inputs"),
+        }
+    }
+
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+        assert_eq!(output_id, 0, "1 output");
+        crate::node::ResourceType::Material
+    }
+
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        assert_eq!(output_id, 0, "1 output");
+        self.waste_material().map(|m| Output {
+            speed: self.waste_speed(),
+            resource: Resource::Material(m),
+        })
+    }
+
+    fn input_spec(&self, input_id: usize) -> Option<PinSpec> {
+        match input_id {
+            0 => Some(PinSpec {
+                resource: self.fuel.map(|f| Resource::Material(f.fuel_rod())),
+                max_speed: round(self.fuel_speed()),
+            }),
+            1 => Some(PinSpec {
+                resource: Some(Resource::Fluid(Fluid::Water)),
+                max_speed: self.water_speed(),
+            }),
+            _ => unreachable!("2 inputs"),
+        }
+    }
+
+    fn output_spec(&self, output_id: usize) -> Option<PinSpec> {
+        assert_eq!(output_id, 0, "1 output");
+        Some(PinSpec {
+            resource: self.waste_material().map(Resource::Material),
+            max_speed: self.max_waste_speed(),
+        })
+    }
+
+    fn current_input(&self, input_id: usize) -> Option<Input> {
+        match input_id {
+            0 => self.current_input_fuel.clone(),
+            1 => self.current_input_water.clone(),
+            _ => unreachable!("2 inputs"),
+        }
+    }
+
+    fn current_input_mut(&mut self, input_id: usize) -> &mut Option<Input> {
+        match input_id {
+            0 => &mut self.current_input_fuel,
+            1 => &mut self.current_input_water,
+            _ => unreachable!("2 inputs"),
+        }
+    }
+
+    fn clock_speed(&self) -> Option<f32> {
+        Some(self.speed)
+    }
+
+    fn show_body(&mut self, ui: &mut Ui, scale: f32, _cx: &mut BodyContext) -> bool {
+        let mut changed = general_selector(ui, scale, &mut self.fuel).changed;
+        ui.add_space(10.0 * scale);
+
+        let matching = self.input_limited_clock();
+        changed |= add_speed_ui(ui, &mut self.speed, matching).changed;
+        ui.add_space(10.0 * scale);
+
+        ui.label(format!("Power: {} MW", self.power()));
+        if let Some(waste) = self.waste_material() {
+            ui.label(format!("{}: {}/h", waste, round(self.waste_speed() * 60.)));
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_waste() {
+        let mut plant = NuclearPowerPlant {
+            fuel: Some(NuclearFuel::Uranium),
+            ..Default::default()
+        };
+        assert_eq!(plant.fuel_speed(), 0.2);
+        assert_eq!(plant.max_waste_speed(), 10.);
+        assert_eq!(plant.waste_speed(), 0.);
+
+        plant.current_input_fuel = Some(Input {
+            speed: 0.2,
+            resource: Resource::Material(Material::UraniumFuelRod),
+        });
+        assert_eq!(plant.power(), 0.);
+
+        plant.current_input_water = Some(Input {
+            speed: 120.,
+            resource: Resource::Fluid(Fluid::Water),
+        });
+        assert_eq!(plant.power(), 1250.);
+        assert_eq!(plant.waste_speed(), 5.);
+
+        plant.fuel = Some(NuclearFuel::Plutonium);
+        plant.current_input_fuel = Some(Input {
+            speed: 0.1,
+            resource: Resource::Material(Material::PlutoniumFuelRod),
+        });
+        plant.current_input_water.as_mut().unwrap().speed = 240.;
+        assert_eq!(plant.power(), 2500.);
+        assert_eq!(plant.waste_speed(), 1.);
+
+        plant.fuel = Some(NuclearFuel::Ficsonium);
+        assert!(plant.current_output(0).is_none());
+    }
+}
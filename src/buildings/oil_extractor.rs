@@ -1,9 +1,13 @@
+use egui::Ui;
+
 use crate::{
     node::{Output, Resource},
+    unlocks::Unlock,
     util::load_img,
+    widgets::{add_speed_ui, extraction_ui, pipe_selector, purity_selector},
 };
 
-use super::{miner::ResourcePurity, round, Fluid, Pipe};
+use super::{miner::ResourcePurity, round, BodyContext, Fluid, Machine, Pipe};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct OilExtractor {
@@ -28,54 +32,105 @@ impl OilExtractor {
         this
     }
 
-    pub fn header_image(&self) -> String {
+    /// What the extractor pumps, before the output pipe limits it.
+    pub fn theoretical_speed(&self) -> f32 {
+        round(120. * self.resource_purity.modifier() * (self.speed / 100.))
+    }
+
+    /// Nothing flows until a pipe is picked.
+    pub fn output_speed(&self) -> f32 {
+        let max = self.output_pipe.map(|p| p.speed()).unwrap_or_default();
+        self.theoretical_speed().min(max)
+    }
+
+    pub fn output_fluid(&self) -> Fluid {
+        Fluid::CrudeOil
+    }
+}
+
+impl Machine for OilExtractor {
+    fn header_image(&self) -> String {
         load_img("Oil_Extractor.png")
     }
 
-    pub fn name(&self) -> String {
+    fn name(&self) -> String {
         "Oil Extractor".to_string()
     }
 
-    pub fn description(&self) -> String {
+    fn description(&self) -> String {
         "Extracts oil".to_string()
     }
 
-    pub fn num_inputs(&self) -> usize {
+    fn unlocked_by(&self) -> Unlock {
+        Unlock::milestone(5, "Oil Processing")
+    }
+
+    fn num_inputs(&self) -> usize {
         0
     }
 
-    pub fn num_outputs(&self) -> usize {
+    fn num_outputs(&self) -> usize {
         1
     }
 
-    pub fn input_resource(&self, _input_id: usize) -> crate::node::ResourceType {
+    fn input_resource(&self, _input_id: usize) -> crate::node::ResourceType {
         unreachable!("no inputs");
     }
 
-    pub fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
         assert_eq!(output_id, 0, "1 output");
         crate::node::ResourceType::Fluid
     }
 
-    pub fn output_speed(&self) -> f32 {
-        let max = self.output_pipe.map(|p| p.speed()).unwrap_or_default();
-        let val = round(120. * self.resource_purity.modifier() * (self.speed / 100.));
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        assert_eq!(output_id, 0, "1 output");
+        self.output_pipe.map(|_| Output {
+            speed: self.output_speed(),
+            resource: Resource::Fluid(self.output_fluid()),
+        })
+    }
 
-        if val > max {
-            max
-        } else {
-            val
-        }
+    fn is_output_limited(&self, output_id: usize) -> bool {
+        assert_eq!(output_id, 0, "1 output");
+        self.output_pipe.is_some() && self.output_speed() < self.theoretical_speed()
     }
 
-    pub fn output_fluid(&self) -> Fluid {
-        Fluid::CrudeOil
+    fn show_body(&mut self, ui: &mut Ui, scale: f32, cx: &mut BodyContext) -> bool {
+        let mut changed = pipe_selector(ui, scale, &mut self.output_pipe, cx.unlocks).changed;
+        ui.add_space(10.0 * scale);
+
+        changed |= purity_selector(ui, scale, &mut self.resource_purity).changed;
+        ui.add_space(10.0 * scale);
+
+        changed |= add_speed_ui(ui, &mut self.speed, None).changed;
+        if self.output_pipe.is_some() {
+            extraction_ui(ui, self.theoretical_speed(), self.output_speed(), "m^3/min");
+        }
+        changed
     }
+}
 
-    pub fn current_output(&self) -> Option<Output> {
-        self.output_pipe.map(|_| Output {
-            speed: self.output_speed(),
-            resource: Resource::Fluid(Fluid::CrudeOil),
-        })
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_capped_by_pipe() {
+        let mut extractor = OilExtractor {
+            output_pipe: None,
+            resource_purity: ResourcePurity::Pure,
+            speed: 250.,
+        };
+        assert_eq!(extractor.theoretical_speed(), 600.);
+        assert_eq!(extractor.output_speed(), 0.);
+        assert!(!extractor.is_output_limited(0));
+
+        extractor.output_pipe = Some(Pipe::Mk1);
+        assert_eq!(extractor.output_speed(), 300.);
+        assert!(extractor.is_output_limited(0));
+
+        extractor.output_pipe = Some(Pipe::Mk2);
+        assert_eq!(extractor.output_speed(), 600.);
+        assert!(!extractor.is_output_limited(0));
     }
 }
@@ -1,11 +1,17 @@
+use egui::Ui;
 use strum::VariantArray;
 
 use crate::{
     node::{Input, Output, Resource},
+    unlocks::Unlock,
     util::load_img,
+    widgets::{add_speed_ui, recipe_selector},
 };
 
-use super::{calc_output, calc_output2, round, Fluid, Material, Selectable};
+use super::{
+    clock_factor, round, BodyContext, Fluid, Machine, Material, PinSpec, RecipeItems, RecipeStats,
+    Selectable,
+};
 
 #[derive(
     Debug,
@@ -207,61 +213,11 @@ impl PackagerRecipe {
     }
 
     pub fn input_material_speed(&self) -> f32 {
-        match self {
-            Self::PackagedAluminaSolution => 120.,
-            Self::PackagedFuel => 40.,
-            Self::PackagedHeavyOilResidue => 30.,
-            Self::PackagedIonizedFuel => 40.,
-            Self::PackagedLiquidBiofuel => 40.,
-            Self::PackagedNitricAcid => 30.,
-            Self::PackagedNitrogenGas => 60.,
-            Self::PackagedOil => 30.,
-            Self::PackagedRocketFuel => 60.,
-            Self::PackagedSulfuricAcid => 40.,
-            Self::PackagedTurbofuel => 20.,
-            Self::PackagedWater => 60.,
-            Self::UnpackageAluminaSolution => 120.,
-            Self::UnpackageFuel => 60.,
-            Self::UnpackageHeavyOilResidue => 20.,
-            Self::UnpackageIonizedFuel => 40.,
-            Self::UnpackageLiquidBiofuel => 60.,
-            Self::UnpackageNitricAcid => 20.,
-            Self::UnpackageNitrogenGas => 60.,
-            Self::UnpackageOil => 60.,
-            Self::UnpackageRocketFuel => 60.,
-            Self::UnpackageSulfuricAcid => 60.,
-            Self::UnpackageTurbofuel => 20.,
-            Self::UnpackageWater => 120.,
-        }
+        self.stats().input_speeds()[0]
     }
 
     pub fn input_fluid_speed(&self) -> f32 {
-        match self {
-            Self::PackagedAluminaSolution => 120.,
-            Self::PackagedFuel => 40.,
-            Self::PackagedHeavyOilResidue => 30.,
-            Self::PackagedIonizedFuel => 80.,
-            Self::PackagedLiquidBiofuel => 40.,
-            Self::PackagedNitricAcid => 30.,
-            Self::PackagedNitrogenGas => 240.,
-            Self::PackagedOil => 30.,
-            Self::PackagedRocketFuel => 120.,
-            Self::PackagedSulfuricAcid => 40.,
-            Self::PackagedTurbofuel => 20.,
-            Self::PackagedWater => 60.,
-            Self::UnpackageAluminaSolution => 0.,
-            Self::UnpackageFuel => 0.,
-            Self::UnpackageHeavyOilResidue => 0.,
-            Self::UnpackageIonizedFuel => 0.,
-            Self::UnpackageLiquidBiofuel => 0.,
-            Self::UnpackageNitricAcid => 0.,
-            Self::UnpackageNitrogenGas => 0.,
-            Self::UnpackageOil => 0.,
-            Self::UnpackageRocketFuel => 0.,
-            Self::UnpackageSulfuricAcid => 0.,
-            Self::UnpackageTurbofuel => 0.,
-            Self::UnpackageWater => 0.,
-        }
+        self.stats().input_speeds()[1]
     }
 
     pub fn max_output_speed_material(&self) -> f32 {
@@ -269,7 +225,7 @@ impl PackagerRecipe {
             return 0.;
         }
 
-        self.output_speed_inner(None)
+        self.output_speed_inner(None, 0)
     }
 
     pub fn max_output_speed_fluid(&self) -> f32 {
@@ -277,7 +233,7 @@ impl PackagerRecipe {
             return 0.;
         }
 
-        self.output_speed_inner(None)
+        self.output_speed_inner(None, 1)
     }
 
     pub fn output_speed_material(&self, input_material_size: f32, input_fluid_size: f32) -> f32 {
@@ -285,7 +241,7 @@ impl PackagerRecipe {
             return 0.;
         }
 
-        self.output_speed_inner(Some((input_material_size, input_fluid_size)))
+        self.output_speed_inner(Some((input_material_size, input_fluid_size)), 0)
     }
 
     pub fn output_speed_fluid(&self, input_material_size: f32, input_fluid_size: f32) -> f32 {
@@ -293,13 +249,13 @@ impl PackagerRecipe {
             return 0.;
         }
 
-        self.output_speed_inner(Some((input_material_size, input_fluid_size)))
+        self.output_speed_inner(Some((input_material_size, input_fluid_size)), 1)
     }
 
-    // returns
-    // (duration, fluid_input, material_input, fluid_output, material_output)
-    fn stats(&self) -> (f32, f32, f32, f32, f32) {
-        match self {
+    /// Ingredients are (material, fluid), products (material, fluid).
+    pub(super) fn stats(&self) -> RecipeStats<2, 2> {
+        // (duration, fluid_input, material_input, fluid_output, material_output)
+        let (duration, fluid_input, material_input, fluid_output, material_output) = match self {
             Self::PackagedAluminaSolution => (1., 2., 2., 0., 2.),
             Self::PackagedFuel => (3., 2., 2., 0., 2.),
             Self::PackagedHeavyOilResidue => (4., 2., 2., 0., 2.),
@@ -307,7 +263,7 @@ impl PackagerRecipe {
             Self::PackagedLiquidBiofuel => (3., 2., 2., 0., 2.),
             Self::PackagedNitricAcid => (2., 1., 1., 0., 1.),
             Self::PackagedNitrogenGas => (1., 4., 1., 0., 1.),
-            Self::PackagedOil => (4., 2., 2., 0., 1.),
+            Self::PackagedOil => (4., 2., 2., 0., 2.),
             Self::PackagedRocketFuel => (1., 2., 1., 0., 1.),
             Self::PackagedSulfuricAcid => (3., 2., 2., 0., 2.),
             Self::PackagedTurbofuel => (6., 2., 2., 0., 2.),
@@ -316,7 +272,7 @@ impl PackagerRecipe {
             Self::UnpackageFuel => (2., 0., 2., 2., 2.),
             Self::UnpackageHeavyOilResidue => (6., 0., 2., 2., 2.),
             Self::UnpackageIonizedFuel => (3., 0., 2., 4., 2.),
-            Self::UnpackageLiquidBiofuel => (2., 0., 1., 1., 1.),
+            Self::UnpackageLiquidBiofuel => (2., 0., 2., 2., 2.),
             Self::UnpackageNitricAcid => (3., 0., 1., 1., 1.),
             Self::UnpackageNitrogenGas => (1., 0., 1., 4., 1.),
             Self::UnpackageOil => (2., 0., 2., 2., 2.),
@@ -324,64 +280,44 @@ impl PackagerRecipe {
             Self::UnpackageSulfuricAcid => (1., 0., 1., 1., 1.),
             Self::UnpackageTurbofuel => (6., 0., 2., 2., 2.),
             Self::UnpackageWater => (1., 0., 2., 2., 2.),
+        };
+        RecipeStats::new(
+            duration,
+            [material_input, fluid_input],
+            [material_output, fluid_output],
+        )
+        .or_installed(&self.items())
+    }
+
+    pub(super) fn items(&self) -> RecipeItems<2, 2> {
+        RecipeItems {
+            name: self.to_string(),
+            ingredients: [
+                self.input_material().map(Resource::Material),
+                self.input_fluid().map(Resource::Fluid),
+            ],
+            products: [
+                self.output_material().map(Resource::Material),
+                self.output_fluid().map(Resource::Fluid),
+            ],
         }
     }
 
-    fn output_speed_inner(&self, input_size: Option<(f32, f32)>) -> f32 {
+    /// `output` is 0 for the material and 1 for the fluid product.
+    fn output_speed_inner(&self, input_size: Option<(f32, f32)>, output: usize) -> f32 {
         let input_material_size = input_size.map(|(a, _)| a);
         let input_fluid_size = input_size.map(|(_, b)| b);
 
-        let (
-            duration,
-            fluid_input_size,
-            material_input_size,
-            _fluid_output_size,
-            material_output_size,
-        ) = self.stats();
-
-        match (
-            self.input_material().is_some(),
-            self.input_fluid().is_some(),
-        ) {
-            (true, true) => {
-                if input_fluid_size == Some(0.0) || input_material_size == Some(0.0) {
-                    return 0.;
-                }
-
-                calc_output2(
-                    input_size,
-                    duration,
-                    material_output_size,
-                    material_input_size,
-                    fluid_input_size,
-                )
-            }
-            (false, true) => {
-                if input_fluid_size == Some(0.0) {
-                    return 0.;
-                }
-
-                calc_output(
-                    input_fluid_size,
-                    duration,
-                    material_output_size,
-                    fluid_input_size,
-                )
-            }
-            (true, false) => {
-                if input_material_size == Some(0.0) {
-                    return 0.;
-                }
-
-                calc_output(
-                    input_material_size,
-                    duration,
-                    material_output_size,
-                    material_input_size,
-                )
-            }
-            (false, false) => unreachable!("always one input"),
+        if (self.input_material().is_some() && input_material_size == Some(0.0))
+            || (self.input_fluid().is_some() && input_fluid_size == Some(0.0))
+        {
+            return 0.;
         }
+
+        // unused ingredients have an amount of 0 and never limit the recipe
+        self.stats()
+            .evaluate(input_size.map(|(a, b)| [a, b]))
+            .products[output]
     }
 }
 
@@ -412,56 +348,17 @@ impl Packager {
         this
     }
 
-    pub fn header_image(&self) -> String {
-        load_img("Packager.png")
-    }
-
     pub fn available_recipes(&self) -> &'static [PackagerRecipe] {
         PackagerRecipe::VARIANTS
     }
 
-    pub fn name(&self) -> String {
-        match &self.recipe {
-            Some(r) => format!("Packager ({})", r.name()),
-            None => "Packager".to_string(),
-        }
-    }
-
-    pub fn description(&self) -> String {
-        "Smelts things".to_string()
-    }
-
-    pub fn num_inputs(&self) -> usize {
-        2
-    }
-
-    pub fn num_outputs(&self) -> usize {
-        2
-    }
-
-    pub fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
-        match input_id {
-            0 => crate::node::ResourceType::Fluid,
-            1 => crate::node::ResourceType::Material,
-            _ => unreachable!("2 inputs"),
-        }
-    }
-
-    pub fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
-        match output_id {
-            0 => crate::node::ResourceType::Fluid,
-            1 => crate::node::ResourceType::Material,
-            _ => unreachable!("2 outputs"),
-        }
-    }
-
     pub fn input_material_speed(&self) -> f32 {
         let base = self
             .recipe
             .as_ref()
             .map(|r| r.input_material_speed())
             .unwrap_or_default();
-        round(base as f32 * (self.speed / 100.))
+        round(base as f32 * clock_factor(self.speed))
     }
 
     pub fn input_fluid_speed(&self) -> f32 {
@@ -470,7 +367,7 @@ impl Packager {
             .as_ref()
             .map(|r| r.input_fluid_speed())
             .unwrap_or_default();
-        round(base as f32 * (self.speed / 100.))
+        round(base as f32 * clock_factor(self.speed))
     }
 
     pub fn output_material(&self) -> Option<Material> {
@@ -482,47 +379,49 @@ impl Packager {
     }
 
     pub fn output_material_speed(&self) -> f32 {
+        let clock = clock_factor(self.speed);
         let input_material_speed = self
             .current_input_material
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let input_fluid_speed = self
             .current_input_fluid
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let base = self
             .recipe
             .as_ref()
             .map(|r| r.output_speed_material(input_material_speed, input_fluid_speed))
             .unwrap_or_default();
 
-        // TODO: take speed into account for input_speed
-
-        round(base as f32 * (self.speed / 100.))
+        round(base as f32 * clock)
     }
 
     pub fn output_fluid_speed(&self) -> f32 {
+        let clock = clock_factor(self.speed);
         let input_material_speed = self
             .current_input_material
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let input_fluid_speed = self
             .current_input_fluid
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let base = self
             .recipe
             .as_ref()
             .map(|r| r.output_speed_fluid(input_material_speed, input_fluid_speed))
             .unwrap_or_default();
 
-        // TODO: take speed into account for input_speed
-
-        round(base as f32 * (self.speed / 100.))
+        round(base as f32 * clock)
     }
 
     pub fn input_material(&self) -> Option<Material> {
@@ -557,6 +456,127 @@ impl Packager {
     }
 }
 
+impl Machine for Packager {
+    fn header_image(&self) -> String {
+        load_img("Packager.png")
+    }
+
+    fn name(&self) -> String {
+        match &self.recipe {
+            Some(r) => format!("Packager ({})", r.name()),
+            None => "Packager".to_string(),
+        }
+    }
+
+    fn description(&self) -> String {
+        "Smelts things".to_string()
+    }
+
+    fn unlocked_by(&self) -> Unlock {
+        Unlock::milestone(5, "Alternative Fluid Transport")
+    }
+
+    fn num_inputs(&self) -> usize {
+        2
+    }
+
+    fn num_outputs(&self) -> usize {
+        2
+    }
+
+    fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
+        match input_id {
+            0 => crate::node::ResourceType::Fluid,
+            1 => crate::node::ResourceType::Material,
+            _ => unreachable!("2 inputs"),
+        }
+    }
+
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+        match output_id {
+            0 => crate::node::ResourceType::Fluid,
+            1 => crate::node::ResourceType::Material,
+            _ => unreachable!("2 outputs"),
+        }
+    }
+
+    fn input_spec(&self, input_id: usize) -> Option<PinSpec> {
+        let spec = match input_id {
+            0 => PinSpec {
+                resource: self.input_fluid().map(Resource::Fluid),
+                max_speed: self.input_fluid_speed(),
+            },
+            1 => PinSpec {
+                resource: self.input_material().map(Resource::Material),
+                max_speed: self.input_material_speed(),
+            },
+            _ => unreachable!("2 inputs"),
+        };
+        Some(spec)
+    }
+
+    fn output_spec(&self, output_id: usize) -> Option<PinSpec> {
+        let factor = clock_factor(self.speed);
+        let spec = match output_id {
+            0 => PinSpec {
+                resource: self.output_fluid().map(Resource::Fluid),
+                max_speed: self
+                    .recipe
+                    .as_ref()
+                    .map(|r| round(r.max_output_speed_fluid() * factor))
+                    .unwrap_or_default(),
+            },
+            1 => PinSpec {
+                resource: self.output_material().map(Resource::Material),
+                max_speed: self
+                    .recipe
+                    .as_ref()
+                    .map(|r| round(r.max_output_speed_material() * factor))
+                    .unwrap_or_default(),
+            },
+            _ => unreachable!("2 outputs"),
+        };
+        Some(spec)
+    }
+
+    fn current_input(&self, input_id: usize) -> Option<Input> {
+        match input_id {
+            0 => self.current_input_fluid.clone(),
+            1 => self.current_input_material.clone(),
+            _ => unreachable!("2 inputs"),
+        }
+    }
+
+    fn current_input_mut(&mut self, input_id: usize) -> &mut Option<Input> {
+        match input_id {
+            0 => &mut self.current_input_fluid,
+            1 => &mut self.current_input_material,
+            _ => unreachable!("2 inputs"),
+        }
+    }
+
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        match output_id {
+            0 => self.current_output_fluid(),
+            1 => self.current_output_material(),
+            _ => unreachable!("2 outputs"),
+        }
+    }
+
+    fn clock_speed(&self) -> Option<f32> {
+        Some(self.speed)
+    }
+
+    fn show_body(&mut self, ui: &mut Ui, scale: f32, cx: &mut BodyContext) -> bool {
+        let mut changed = recipe_selector(ui, scale, &mut self.recipe, cx.unlocks).changed;
+        ui.add_space(10.0 * scale);
+
+        let matching = self.input_limited_clock();
+        changed |= add_speed_ui(ui, &mut self.speed, matching).changed;
+        changed
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -609,4 +629,13 @@ mod tests {
             "fluid"
         );
     }
+
+    #[test]
+    fn test_output_speed_unpackage_nitrogen_gas() {
+        // 1 packaged gas to 4 m^3 of gas and an empty tank every second
+        let recipe = PackagerRecipe::UnpackageNitrogenGas;
+        assert_eq!(recipe.max_output_speed_fluid(), 240.);
+        assert_eq!(recipe.max_output_speed_material(), 60.);
+        assert_eq!(recipe.output_speed_fluid(30., 0.), 120.);
+    }
 }
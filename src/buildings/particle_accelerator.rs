@@ -1,9 +1,16 @@
+use egui::Ui;
+
 use crate::{
     node::{Input, Output, Resource},
+    unlocks::Unlock,
     util::load_img,
+    widgets::{add_somersloop4_ui, add_speed_ui, recipe_selector},
 };
 
-use super::{calc_output3, round, Fluid, Material, Selectable, SomersloopSlot4};
+use super::{
+    clock_factor, round, BodyContext, Fluid, Machine, Material, PinSpec, RecipeItems, RecipeStats,
+    Selectable, SomersloopSlot4,
+};
 
 macro_rules! r {
     ($($literal_name:expr => $name:ident, $input_speed_fluid_0:expr => $input_fluid_0:expr, $input_speed_material_0:expr => $input_material_0:expr, $input_speed_material_1:expr => $input_material_1:expr, $duration:expr, $output_speed_material:expr => $output_material:expr),* $(,)*) => {
@@ -54,15 +61,8 @@ macro_rules! r {
             }
 
             pub fn input_speed(&self) -> (f32, f32, f32) {
-                match self {
-                    $(
-                        Self::$name => (
-                            (60. / $duration) * $input_speed_fluid_0,
-                            (60. / $duration) * $input_speed_material_0,
-                            (60. / $duration) * $input_speed_material_1,
-                        ),
-                    )*
-                }
+                let [a, b, c] = self.stats().input_speeds();
+                (a, b, c)
             }
             pub fn max_output_speed(&self) -> f32 {
                 self.output_speed_inner(None)
@@ -81,19 +81,30 @@ macro_rules! r {
                 )))
             }
 
-            // returns
-            // (duration, fluid_input_0, material_input_0, material_input_1, material_output)
-            pub fn stats(&self) -> (f32, f32, f32, f32, f32) {
-                match self {
+            /// Ingredients are (fluid, material 0, material 1).
+            pub fn stats(&self) -> RecipeStats<3, 1> {
+                let stats = match self {
                     $(
-                        Self::$name => (
+                        Self::$name => RecipeStats::new(
                             $duration,
-                            $input_speed_fluid_0,
-                            $input_speed_material_0,
-                            $input_speed_material_1,
-                            $output_speed_material,
+                            [$input_speed_fluid_0, $input_speed_material_0, $input_speed_material_1],
+                            [$output_speed_material],
                         ),
                     )*
+                };
+                stats.or_installed(&self.items())
+            }
+
+            pub(super) fn items(&self) -> RecipeItems<3, 1> {
+                let (fluid, material_0, material_1) = self.input_material();
+                RecipeItems {
+                    name: self.to_string(),
+                    ingredients: [
+                        fluid.map(Resource::Fluid),
+                        material_0.map(Resource::Material),
+                        material_1.map(Resource::Material),
+                    ],
+                    products: [Some(Resource::Material(self.output_material()))],
                 }
             }
         }
@@ -137,7 +148,7 @@ r!(
     3.,
     1. => Material::Diamonds,
 
-    "Dark Crystallization Matter " => DarkMatterCrystallization,
+    "Dark Matter Crystallization" => DarkMatterCrystallization,
     10. => Some(Fluid::DarkMatterResidue),
     0. => None, 0. => None,
     3.,
@@ -179,13 +190,6 @@ impl ParticleAcceleratorRecipe {
         let input_fluid_0_speed = input_speed.map(|(a, _, _)| a);
         let input_material_0_speed = input_speed.map(|(_, b, _)| b);
         let input_material_1_speed = input_speed.map(|(_, _, c)| c);
-        let (
-            duration,
-            fluid_input_0_speed,
-            material_input_0_speed,
-            material_input_1_speed,
-            material_output_speed,
-        ) = self.stats();
 
         if self.input_material().0.is_some() && input_fluid_0_speed == Some(0.0) {
             return 0.;
@@ -199,14 +203,9 @@ impl ParticleAcceleratorRecipe {
             return 0.;
         }
 
-        calc_output3(
-            input_speed,
-            duration,
-            material_output_speed,
-            fluid_input_0_speed,
-            material_input_0_speed,
-            material_input_1_speed,
-        )
+        self.stats()
+            .evaluate(input_speed.map(|(a, b, c)| [a, b, c]))
+            .products[0]
     }
 }
 
@@ -243,54 +242,15 @@ impl ParticleAccelerator {
         }
     }
 
-    pub fn header_image(&self) -> String {
-        load_img("Particle_Accelerator.png")
-    }
-
-    pub fn name(&self) -> String {
-        match &self.recipe {
-            Some(r) => format!("Particle Accelerator ({})", r.name()),
-            None => "Particle Accelerator".to_string(),
-        }
-    }
-
-    pub fn description(&self) -> String {
-        "Accelerates things".to_string()
-    }
-
-    pub fn num_inputs(&self) -> usize {
-        3
-    }
-
-    pub fn num_outputs(&self) -> usize {
-        1
-    }
-
-    pub fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
-        match input_id {
-            0 => crate::node::ResourceType::Fluid,
-            1 => crate::node::ResourceType::Material,
-            2 => crate::node::ResourceType::Material,
-            _ => unreachable!("3 outputs"),
-        }
-    }
-
-    pub fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
-        match output_id {
-            0 => crate::node::ResourceType::Material,
-            _ => unreachable!("1 output"),
-        }
-    }
-
     pub fn input_speed(&self) -> (f32, f32, f32) {
         let (base_0, base_1, base_2) = self
             .recipe
             .as_ref()
             .map(|r| r.input_speed())
             .unwrap_or_default();
-        let a = round(base_0 as f32 * (self.speed / 100.));
-        let b = round(base_1 as f32 * (self.speed / 100.));
-        let c = round(base_2 as f32 * (self.speed / 100.));
+        let a = round(base_0 as f32 * clock_factor(self.speed));
+        let b = round(base_1 as f32 * clock_factor(self.speed));
+        let c = round(base_2 as f32 * clock_factor(self.speed));
 
         (a, b, c)
     }
@@ -300,21 +260,25 @@ impl ParticleAccelerator {
     }
 
     pub fn output_speed(&self) -> f32 {
+        let clock = clock_factor(self.speed);
         let input_fluid_0_speed = self
             .current_input_fluid_0
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let input_material_0_speed = self
             .current_input_material_0
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let input_material_1_speed = self
             .current_input_material_1
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let base_material = self
             .recipe
             .as_ref()
@@ -327,9 +291,7 @@ impl ParticleAccelerator {
             })
             .unwrap_or_default();
         let amplification = self.amplified.factor();
-
-        // TODO: take speed into account for input_speed
-        round(base_material as f32 * (self.speed / 100.) * amplification)
+        round(base_material as f32 * clock * amplification)
     }
 
     pub fn input_material(&self) -> Option<(Option<Fluid>, Option<Material>, Option<Material>)> {
@@ -344,6 +306,130 @@ impl ParticleAccelerator {
     }
 }
 
+impl Machine for ParticleAccelerator {
+    fn header_image(&self) -> String {
+        load_img("Particle_Accelerator.png")
+    }
+
+    fn name(&self) -> String {
+        match &self.recipe {
+            Some(r) => format!("Particle Accelerator ({})", r.name()),
+            None => "Particle Accelerator".to_string(),
+        }
+    }
+
+    fn description(&self) -> String {
+        "Accelerates things".to_string()
+    }
+
+    fn unlocked_by(&self) -> Unlock {
+        Unlock::milestone(8, "Particle Enrichment")
+    }
+
+    fn num_inputs(&self) -> usize {
+        3
+    }
+
+    fn num_outputs(&self) -> usize {
+        1
+    }
+
+    fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
+        match input_id {
+            0 => crate::node::ResourceType::Fluid,
+            1 => crate::node::ResourceType::Material,
+            2 => crate::node::ResourceType::Material,
+            _ => unreachable!("3 outputs"),
+        }
+    }
+
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+        match output_id {
+            0 => crate::node::ResourceType::Material,
+            _ => unreachable!("1 output"),
+        }
+    }
+
+    fn input_spec(&self, input_id: usize) -> Option<PinSpec> {
+        let spec = match input_id {
+            0 => PinSpec {
+                resource: self.input_material().and_then(|b| b.0).map(Resource::Fluid),
+                max_speed: self.input_speed().0,
+            },
+            1 => PinSpec {
+                resource: self
+                    .input_material()
+                    .and_then(|b| b.1)
+                    .map(Resource::Material),
+                max_speed: self.input_speed().1,
+            },
+            2 => PinSpec {
+                resource: self
+                    .input_material()
+                    .and_then(|b| b.2)
+                    .map(Resource::Material),
+                max_speed: self.input_speed().2,
+            },
+            _ => unreachable!("3 inputs"),
+        };
+        Some(spec)
+    }
+
+    fn output_spec(&self, output_id: usize) -> Option<PinSpec> {
+        let factor = clock_factor(self.speed) * self.amplified.factor();
+        assert_eq!(output_id, 0, "1 output");
+        Some(PinSpec {
+            resource: self.output_material().map(Resource::Material),
+            max_speed: self
+                .recipe
+                .as_ref()
+                .map(|r| round(r.max_output_speed() * factor))
+                .unwrap_or_default(),
+        })
+    }
+
+    fn current_input(&self, input_id: usize) -> Option<Input> {
+        match input_id {
+            0 => self.current_input_fluid_0.clone(),
+            1 => self.current_input_material_0.clone(),
+            2 => self.current_input_material_1.clone(),
+            _ => unreachable!("3 inputs"),
+        }
+    }
+
+    fn current_input_mut(&mut self, input_id: usize) -> &mut Option<Input> {
+        match input_id {
+            0 => &mut self.current_input_fluid_0,
+            1 => &mut self.current_input_material_0,
+            2 => &mut self.current_input_material_1,
+            _ => unreachable!("3 inputs"),
+        }
+    }
+
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        match output_id {
+            0 => self.current_output_material(),
+            _ => unreachable!("1 outputs"),
+        }
+    }
+
+    fn clock_speed(&self) -> Option<f32> {
+        Some(self.speed)
+    }
+
+    fn show_body(&mut self, ui: &mut Ui, scale: f32, cx: &mut BodyContext) -> bool {
+        let mut changed = recipe_selector(ui, scale, &mut self.recipe, cx.unlocks).changed;
+        ui.add_space(10.0 * scale);
+
+        let matching = self.input_limited_clock();
+        changed |= add_speed_ui(ui, &mut self.speed, matching).changed;
+        ui.add_space(10.0 * scale);
+
+        changed |= add_somersloop4_ui(ui, &mut self.amplified).changed;
+        changed
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
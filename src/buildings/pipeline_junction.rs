@@ -1,9 +1,10 @@
 use crate::{
     node::{Input, Output, Resource},
+    unlocks::Unlock,
     util::load_img,
 };
 
-use super::{round, Material};
+use super::{round, Machine, Material};
 
 #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct PipelineJunction {
@@ -24,36 +25,6 @@ impl PipelineJunction {
         }
     }
 
-    pub fn header_image(&self) -> String {
-        load_img("Pipeline_Junction.png")
-    }
-
-    pub fn name(&self) -> String {
-        "Pipeline Junction".to_string()
-    }
-
-    pub fn description(&self) -> String {
-        "Junction for fluids".to_string()
-    }
-
-    pub fn num_inputs(&self) -> usize {
-        4
-    }
-
-    pub fn num_outputs(&self) -> usize {
-        4
-    }
-
-    pub fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
-        assert!(input_id < 4, "4 inputs");
-        crate::node::ResourceType::Fluid
-    }
-
-    pub fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
-        assert!(output_id < 4, "4 outputs");
-        crate::node::ResourceType::Fluid
-    }
-
     pub fn input_material(&self) -> Option<Material> {
         None
     }
@@ -147,3 +118,83 @@ impl PipelineJunction {
         }
     }
 }
+
+impl Machine for PipelineJunction {
+    fn header_image(&self) -> String {
+        load_img("Pipeline_Junction.png")
+    }
+
+    fn name(&self) -> String {
+        "Pipeline Junction".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Junction for fluids".to_string()
+    }
+
+    fn unlocked_by(&self) -> Unlock {
+        Unlock::milestone(3, "Coal Power")
+    }
+
+    fn num_inputs(&self) -> usize {
+        4
+    }
+
+    fn num_outputs(&self) -> usize {
+        4
+    }
+
+    fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
+        assert!(input_id < 4, "4 inputs");
+        crate::node::ResourceType::Fluid
+    }
+
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+        assert!(output_id < 4, "4 outputs");
+        crate::node::ResourceType::Fluid
+    }
+
+    fn current_input(&self, input_id: usize) -> Option<Input> {
+        match input_id {
+            0 => self.current_input_0.clone(),
+            1 => self.current_input_1.clone(),
+            2 => self.current_input_2.clone(),
+            3 => self.current_input_3.clone(),
+            _ => unreachable!("4 inputs"),
+        }
+    }
+
+    fn current_input_mut(&mut self, input_id: usize) -> &mut Option<Input> {
+        match input_id {
+            0 => &mut self.current_input_0,
+            1 => &mut self.current_input_1,
+            2 => &mut self.current_input_2,
+            3 => &mut self.current_input_3,
+            _ => unreachable!("4 inputs"),
+        }
+    }
+
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        match output_id {
+            0 => self.current_output_0(),
+            1 => self.current_output_1(),
+            2 => self.current_output_2(),
+            3 => self.current_output_3(),
+            _ => unreachable!("4 outputs"),
+        }
+    }
+
+    fn set_output_connected(&mut self, output_id: usize, connected: bool) {
+        match output_id {
+            0 => self.output_0_connected = connected,
+            1 => self.output_1_connected = connected,
+            2 => self.output_2_connected = connected,
+            3 => self.output_3_connected = connected,
+            _ => unreachable!("4 outputs"),
+        }
+    }
+
+    fn is_fluid_passthrough(&self) -> bool {
+        true
+    }
+}
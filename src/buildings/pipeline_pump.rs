@@ -0,0 +1,186 @@
+use egui::Ui;
+
+use crate::{
+    node::{Input, Output},
+    unlocks::{Gated, Unlock},
+    util::load_img,
+    widgets::gated_selector,
+};
+
+use super::{round, BodyContext, Machine, Selectable};
+
+/// Head lift every fluid producing building provides on its own, in meters.
+pub const BUILDING_HEAD_LIFT: f32 = 10.;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct PipelinePump {
+    pub level: PumpLevel,
+    pub current_input: Option<Input>,
+}
+
+impl Default for PipelinePump {
+    fn default() -> Self {
+        Self {
+            level: PumpLevel::Mk1,
+            current_input: None,
+        }
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    serde::Serialize,
+    serde::Deserialize,
+    PartialEq,
+    strum::VariantArray,
+    strum::Display,
+)]
+pub enum PumpLevel {
+    #[strum(to_string = "Mk.1")]
+    Mk1,
+    #[strum(to_string = "Mk.2")]
+    Mk2,
+}
+
+impl Selectable for PumpLevel {
+    const NAME: &'static str = "Pump";
+
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn image(&self) -> String {
+        // no pump images yet
+        load_img("Placeholder.png")
+    }
+}
+
+impl Gated for PumpLevel {
+    fn unlocked_by(&self) -> Unlock {
+        match self {
+            Self::Mk1 => Unlock::milestone(3, "Coal Power"),
+            Self::Mk2 => Unlock::milestone(6, "Pipeline Engineering Mk.2"),
+        }
+    }
+}
+
+impl PumpLevel {
+    /// Maximum height in meters the pump can push fluids up.
+    pub fn head_lift(&self) -> f32 {
+        match self {
+            Self::Mk1 => 22.,
+            Self::Mk2 => 50.,
+        }
+    }
+
+    /// Maximum flow through the pump in m^3/min.
+    pub fn max_flow(&self) -> f32 {
+        match self {
+            Self::Mk1 => 300.,
+            Self::Mk2 => 600.,
+        }
+    }
+}
+
+impl PipelinePump {
+    pub fn clear_clone(&self) -> Self {
+        let mut this = self.clone();
+        this.current_input = None;
+        this
+    }
+}
+
+impl Machine for PipelinePump {
+    fn header_image(&self) -> String {
+        self.level.image()
+    }
+
+    fn name(&self) -> String {
+        format!("Pipeline Pump {}", self.level.name())
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "Pumps fluids up to {}m, max {} m³/min",
+            self.level.head_lift(),
+            self.level.max_flow()
+        )
+    }
+
+    fn unlocked_by(&self) -> Unlock {
+        self.level.unlocked_by()
+    }
+
+    fn num_inputs(&self) -> usize {
+        1
+    }
+
+    fn num_outputs(&self) -> usize {
+        1
+    }
+
+    fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
+        assert_eq!(input_id, 0, "1 input");
+        crate::node::ResourceType::Fluid
+    }
+
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+        assert_eq!(output_id, 0, "1 output");
+        crate::node::ResourceType::Fluid
+    }
+
+    fn head_lift(&self, _output_id: usize) -> f32 {
+        self.level.head_lift()
+    }
+
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        assert_eq!(output_id, 0, "1 output");
+        self.current_input.as_ref().map(|input| Output {
+            speed: round(input.speed.min(self.level.max_flow())),
+            resource: input.resource,
+        })
+    }
+
+    fn current_input(&self, input_id: usize) -> Option<Input> {
+        assert_eq!(input_id, 0, "1 input");
+        self.current_input.clone()
+    }
+
+    fn current_input_mut(&mut self, input_id: usize) -> &mut Option<Input> {
+        assert_eq!(input_id, 0, "1 input");
+        &mut self.current_input
+    }
+
+    fn show_body(&mut self, ui: &mut Ui, scale: f32, cx: &mut BodyContext) -> bool {
+        let mut level = Some(self.level);
+        let changed = gated_selector(ui, scale, &mut level, cx.unlocks).changed;
+        if let Some(level) = level {
+            self.level = level;
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{buildings::Fluid, node::Resource};
+
+    #[test]
+    fn test_output_capped_by_level() {
+        let mut pump = PipelinePump::default();
+        assert!(pump.current_output(0).is_none());
+
+        pump.current_input = Some(Input {
+            speed: 450.,
+            resource: Resource::Fluid(Fluid::Water),
+        });
+        assert_eq!(pump.current_output(0).unwrap().speed, 300.);
+
+        pump.level = PumpLevel::Mk2;
+        assert_eq!(pump.current_output(0).unwrap().speed, 450.);
+        assert_eq!(pump.head_lift(0), 50.);
+    }
+}
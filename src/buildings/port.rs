@@ -0,0 +1,353 @@
+use egui::Ui;
+
+use crate::{
+    app::Logistics,
+    node::{Input, Output, ResourceType},
+    util::load_img,
+    widgets::{add_transport_ui, general_selector, INVALID_COLOR},
+};
+
+use super::{round, BodyContext, Machine, Selectable};
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ExportPort {
+    /// Import ports with the same name, in any tab, receive what goes in here.
+    pub name: String,
+    pub fluid: bool,
+    pub current_input: Option<Input>,
+}
+
+impl ExportPort {
+    pub fn clear_clone(&self) -> Self {
+        let mut this = self.clone();
+        this.current_input = None;
+        this
+    }
+}
+
+impl Machine for ExportPort {
+    fn header_image(&self) -> String {
+        // no station images yet
+        load_img("Placeholder.png")
+    }
+
+    fn name(&self) -> String {
+        if self.name.is_empty() {
+            "Export Port".to_string()
+        } else {
+            format!("Export Port ({})", self.name)
+        }
+    }
+
+    fn description(&self) -> String {
+        "Sends things to import ports with the same name".to_string()
+    }
+
+    fn num_inputs(&self) -> usize {
+        1
+    }
+
+    fn num_outputs(&self) -> usize {
+        0
+    }
+
+    fn input_resource(&self, input_id: usize) -> ResourceType {
+        assert_eq!(input_id, 0, "1 input");
+        resource_type(self.fluid)
+    }
+
+    fn output_resource(&self, _output_id: usize) -> ResourceType {
+        unreachable!("no outputs");
+    }
+
+    fn current_input(&self, input_id: usize) -> Option<Input> {
+        assert_eq!(input_id, 0, "1 input");
+        self.current_input.clone()
+    }
+
+    fn current_input_mut(&mut self, input_id: usize) -> &mut Option<Input> {
+        assert_eq!(input_id, 0, "1 input");
+        &mut self.current_input
+    }
+
+    fn show_body(&mut self, ui: &mut Ui, _scale: f32, cx: &mut BodyContext) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            changed |= ui.text_edit_singleline(&mut self.name).changed();
+            ui.label("Name");
+        });
+        if ui.checkbox(&mut self.fluid, "Fluid").changed() {
+            cx.removed_input = Some(0);
+            changed = true;
+        }
+        if cx.logistics.is_mismatched(&self.name) {
+            ui.colored_label(INVALID_COLOR, MISMATCHED);
+        }
+        changed
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ImportPort {
+    pub name: String,
+    pub fluid: bool,
+    pub transport: Option<Transport>,
+    /// Its share of what the matching export ports send, updated every frame.
+    #[serde(skip)]
+    pub incoming: Option<Output>,
+}
+
+impl ImportPort {
+    pub fn clear_clone(&self) -> Self {
+        let mut this = self.clone();
+        this.incoming = None;
+        this
+    }
+
+    /// Takes its share of what is exported under its name, returns whether it changed.
+    pub fn update_incoming(&mut self, logistics: &Logistics) -> bool {
+        let incoming = logistics
+            .import(&self.name)
+            .filter(|o| (o.resource.typ() == ResourceType::Fluid) == self.fluid);
+        let changed = match (&self.incoming, &incoming) {
+            (Some(a), Some(b)) => a.speed != b.speed || a.resource != b.resource,
+            (None, None) => false,
+            _ => true,
+        };
+        self.incoming = incoming;
+        changed
+    }
+
+    /// True if the transport can not keep up with the incoming rate.
+    pub fn is_over_capacity(&self) -> bool {
+        match (&self.incoming, &self.transport) {
+            (Some(incoming), Some(transport)) => incoming.speed > transport.throughput(),
+            _ => false,
+        }
+    }
+}
+
+impl Machine for ImportPort {
+    fn header_image(&self) -> String {
+        // no station images yet
+        load_img("Placeholder.png")
+    }
+
+    fn name(&self) -> String {
+        if self.name.is_empty() {
+            "Import Port".to_string()
+        } else {
+            format!("Import Port ({})", self.name)
+        }
+    }
+
+    fn description(&self) -> String {
+        "Receives things from export ports with the same name".to_string()
+    }
+
+    fn num_inputs(&self) -> usize {
+        0
+    }
+
+    fn num_outputs(&self) -> usize {
+        1
+    }
+
+    fn input_resource(&self, _input_id: usize) -> ResourceType {
+        unreachable!("no inputs");
+    }
+
+    fn output_resource(&self, output_id: usize) -> ResourceType {
+        assert_eq!(output_id, 0, "1 output");
+        resource_type(self.fluid)
+    }
+
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        assert_eq!(output_id, 0, "1 output");
+        self.incoming.as_ref().map(|incoming| {
+            let speed = match self.transport {
+                Some(ref t) => incoming.speed.min(t.throughput()),
+                None => incoming.speed,
+            };
+            Output {
+                speed: round(speed),
+                resource: incoming.resource,
+            }
+        })
+    }
+
+    fn is_output_limited(&self, output_id: usize) -> bool {
+        assert_eq!(output_id, 0, "1 output");
+        self.is_over_capacity()
+    }
+
+    fn show_body(&mut self, ui: &mut Ui, scale: f32, cx: &mut BodyContext) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            changed |= ui.text_edit_singleline(&mut self.name).changed();
+            ui.label("Name");
+        });
+        if ui.checkbox(&mut self.fluid, "Fluid").changed() {
+            if let Some(ref mut transport) = self.transport {
+                transport.capacity = transport.kind.default_capacity(resource_type(self.fluid));
+            }
+            cx.removed_output = Some(0);
+            changed = true;
+        }
+        ui.add_space(10.0 * scale);
+
+        let typ = resource_type(self.fluid);
+        let mut kind = self.transport.as_ref().map(|t| t.kind);
+        if general_selector(ui, scale, &mut kind).changed {
+            self.transport = kind.map(|kind| Transport::new(kind, typ));
+            changed = true;
+        }
+        if let Some(ref mut transport) = self.transport {
+            changed |= add_transport_ui(ui, transport);
+            if ui.button("Remove Transport").clicked() {
+                self.transport = None;
+                changed = true;
+            }
+        }
+
+        if cx.logistics.is_mismatched(&self.name) {
+            ui.colored_label(INVALID_COLOR, MISMATCHED);
+        }
+        if self.incoming.is_none() {
+            ui.colored_label(INVALID_COLOR, "No matching export port");
+        } else if self.is_over_capacity() {
+            ui.colored_label(INVALID_COLOR, "Transport over capacity");
+        }
+        changed
+    }
+}
+
+const MISMATCHED: &str = "Export ports with this name carry different resources";
+
+fn resource_type(fluid: bool) -> ResourceType {
+    if fluid {
+        ResourceType::Fluid
+    } else {
+        ResourceType::Material
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct Transport {
+    pub kind: TransportKind,
+    pub vehicles: u32,
+    /// Items or m^3 moved by a single vehicle per trip.
+    pub capacity: f32,
+    /// Time for a vehicle to get back to the export port, in seconds.
+    pub round_trip: f32,
+}
+
+impl Transport {
+    pub fn new(kind: TransportKind, typ: ResourceType) -> Self {
+        Self {
+            kind,
+            vehicles: 1,
+            capacity: kind.default_capacity(typ),
+            round_trip: 120.,
+        }
+    }
+
+    /// Maximum rate the vehicles can move, per minute.
+    pub fn throughput(&self) -> f32 {
+        if self.round_trip <= 0. {
+            return 0.;
+        }
+        round(self.vehicles as f32 * self.capacity * 60. / self.round_trip)
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    serde::Serialize,
+    serde::Deserialize,
+    PartialEq,
+    strum::VariantArray,
+    strum::Display,
+)]
+pub enum TransportKind {
+    #[strum(to_string = "Freight Train")]
+    Train,
+    #[strum(to_string = "Truck")]
+    Truck,
+    #[strum(to_string = "Drone")]
+    Drone,
+}
+
+impl Selectable for TransportKind {
+    const NAME: &'static str = "Transport";
+
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn image(&self) -> String {
+        // no vehicle images yet
+        load_img("Placeholder.png")
+    }
+}
+
+impl TransportKind {
+    /// Per trip capacity, assuming full stacks of 100 items. Trucks and
+    /// drones move fluids packaged, at 1 m^3 per item.
+    pub fn default_capacity(&self, typ: ResourceType) -> f32 {
+        match (self, typ) {
+            // one freight car, 32 slots
+            (Self::Train, ResourceType::Material) => 3200.,
+            // one fluid freight car
+            (Self::Train, ResourceType::Fluid) => 1600.,
+            // 48 slots
+            (Self::Truck, _) => 4800.,
+            // 9 slots
+            (Self::Drone, _) => 900.,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{buildings::Material, node::Resource};
+
+    #[test]
+    fn test_transport_limits_import() {
+        let mut port = ImportPort {
+            incoming: Some(Output {
+                speed: 1000.,
+                resource: Resource::Material(Material::SteelBeam),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(port.current_output(0).unwrap().speed, 1000.);
+        assert!(!port.is_over_capacity());
+
+        let mut truck = Transport::new(TransportKind::Truck, ResourceType::Material);
+        truck.round_trip = 480.;
+        assert_eq!(truck.throughput(), 600.);
+        port.transport = Some(truck);
+        assert_eq!(port.current_output(0).unwrap().speed, 600.);
+        assert!(port.is_over_capacity());
+    }
+    #[test]
+    fn test_fluid_capacity() {
+        let train = Transport::new(TransportKind::Train, ResourceType::Material);
+        assert_eq!(train.capacity, 3200.);
+        let train = Transport::new(TransportKind::Train, ResourceType::Fluid);
+        assert_eq!(train.capacity, 1600.);
+        assert_eq!(train.throughput(), 800.);
+
+        // packaged, same as items
+        for kind in [TransportKind::Truck, TransportKind::Drone] {
+            assert_eq!(
+                kind.default_capacity(ResourceType::Fluid),
+                kind.default_capacity(ResourceType::Material)
+            );
+        }
+    }
+}
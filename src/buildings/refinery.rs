@@ -1,9 +1,16 @@
+use egui::Ui;
+
 use crate::{
     node::{Input, Output, Resource},
+    unlocks::Unlock,
     util::load_img,
+    widgets::{add_somersloop2_ui, add_speed_ui, recipe_selector},
 };
 
-use super::{calc_output, calc_output2, round, Fluid, Material, Selectable, SomersloopSlot2};
+use super::{
+    clock_factor, round, BodyContext, Fluid, Machine, Material, PinSpec, RecipeItems, RecipeStats,
+    Selectable, SomersloopSlot2,
+};
 
 #[derive(
     Debug,
@@ -48,14 +55,15 @@ pub enum RefineryRecipe {
     DilutedPackagedFuel,
     #[strum(to_string = "Electrode Aluminum Scrap")]
     ElectrodeAluminumScrap,
-    #[strum(to_string = "Heavy Oi lResidue")]
+    #[strum(to_string = "Heavy Oil Residue")]
     HeavyOilResidue,
     #[strum(to_string = "Leached Caterium Ingot")]
     LeachedCateriumIngot,
     #[strum(to_string = "Leached Copper Ingot")]
     LeachedCopperIngot,
-    #[strum(to_string = "Leached Ironingot")]
-    LeachedIroningot,
+    #[strum(to_string = "Leached Iron Ingot")]
+    #[serde(alias = "LeachedIroningot")]
+    LeachedIronIngot,
     #[strum(to_string = "Polyester Fabric")]
     PolyesterFabric,
     #[strum(to_string = "Polymer Resin")]
@@ -129,7 +137,7 @@ impl RefineryRecipe {
             Self::HeavyOilResidue => None,
             Self::LeachedCateriumIngot => Some(Material::CateriumOre),
             Self::LeachedCopperIngot => Some(Material::CopperOre),
-            Self::LeachedIroningot => Some(Material::IronOre),
+            Self::LeachedIronIngot => Some(Material::IronOre),
             Self::PolyesterFabric => Some(Material::PolymerResin),
             Self::PolymerResin => None,
             Self::PureCateriumIngot => Some(Material::CateriumOre),
@@ -168,7 +176,7 @@ impl RefineryRecipe {
             Self::HeavyOilResidue => Some(Fluid::CrudeOil),
             Self::LeachedCateriumIngot => Some(Fluid::SulfuricAcid),
             Self::LeachedCopperIngot => Some(Fluid::SulfuricAcid),
-            Self::LeachedIroningot => Some(Fluid::SulfuricAcid),
+            Self::LeachedIronIngot => Some(Fluid::SulfuricAcid),
             Self::PolyesterFabric => Some(Fluid::Water),
             Self::PolymerResin => Some(Fluid::CrudeOil),
             Self::PureCateriumIngot => Some(Fluid::Water),
@@ -207,7 +215,7 @@ impl RefineryRecipe {
             Self::HeavyOilResidue => Some(Material::PolymerResin),
             Self::LeachedCateriumIngot => Some(Material::CateriumIngot),
             Self::LeachedCopperIngot => Some(Material::CopperIngot),
-            Self::LeachedIroningot => Some(Material::IronIngot),
+            Self::LeachedIronIngot => Some(Material::IronIngot),
             Self::PolyesterFabric => Some(Material::Fabric),
             Self::PolymerResin => Some(Material::PolymerResin),
             Self::PureCateriumIngot => Some(Material::CateriumIngot),
@@ -246,7 +254,7 @@ impl RefineryRecipe {
             Self::HeavyOilResidue => Some(Fluid::HeavyOilResidue),
             Self::LeachedCateriumIngot => None,
             Self::LeachedCopperIngot => None,
-            Self::LeachedIroningot => None,
+            Self::LeachedIronIngot => None,
             Self::PolyesterFabric => None,
             Self::PolymerResin => Some(Fluid::HeavyOilResidue),
             Self::PureCateriumIngot => None,
@@ -265,81 +273,11 @@ impl RefineryRecipe {
     }
 
     pub fn input_material_speed(&self) -> f32 {
-        match self {
-            Self::AluminaSolution => 120.,
-            Self::AluminumScrap => 120.,
-            Self::Fuel => 0.,
-            Self::IonizedFuel => 2.5,
-            Self::LiquidBiofuel => 90.,
-            Self::PetroleumCoke => 0.,
-            Self::Plastic => 0.,
-            Self::ResidualFuel => 0.,
-            Self::ResidualPlastic => 60.,
-            Self::ResidualRubber => 40.,
-            Self::Rubber => 0.,
-            Self::SmokelessPowder => 20.,
-            Self::SulfuricAcid => 50.,
-            Self::CoatedCable => 37.5,
-            Self::DilutedPackagedFuel => 60.,
-            Self::ElectrodeAluminumScrap => 60.,
-            Self::HeavyOilResidue => 0.,
-            Self::LeachedCateriumIngot => 54.,
-            Self::LeachedCopperIngot => 45.,
-            Self::LeachedIroningot => 50.,
-            Self::PolyesterFabric => 30.,
-            Self::PolymerResin => 0.,
-            Self::PureCateriumIngot => 24.,
-            Self::PureCopperIngot => 15.,
-            Self::PureIronIngot => 35.,
-            Self::PureQuartzCrystal => 67.5,
-            Self::QuartzPurification => 120.,
-            Self::RecycledPlastic => 30.,
-            Self::RecycledRubber => 30.,
-            Self::SloppyAlumina => 200.,
-            Self::SteamedCopperSheet => 22.5,
-            Self::TurboHeavyFuel => 30.,
-            Self::Turbofuel => 15.,
-            Self::WetConcrete => 120.,
-        }
+        self.stats().input_speeds()[0]
     }
 
     pub fn input_fluid_speed(&self) -> f32 {
-        match self {
-            Self::AluminaSolution => 180.,
-            Self::AluminumScrap => 240.,
-            Self::Fuel => 60.,
-            Self::IonizedFuel => 40.,
-            Self::LiquidBiofuel => 45.,
-            Self::PetroleumCoke => 40.,
-            Self::Plastic => 30.,
-            Self::ResidualFuel => 60.,
-            Self::ResidualPlastic => 20.,
-            Self::ResidualRubber => 40.,
-            Self::Rubber => 30.,
-            Self::SmokelessPowder => 10.,
-            Self::SulfuricAcid => 50.,
-            Self::CoatedCable => 15.,
-            Self::DilutedPackagedFuel => 30.,
-            Self::ElectrodeAluminumScrap => 180.,
-            Self::HeavyOilResidue => 30.,
-            Self::LeachedCateriumIngot => 30.,
-            Self::LeachedCopperIngot => 25.,
-            Self::LeachedIroningot => 10.,
-            Self::PolyesterFabric => 30.,
-            Self::PolymerResin => 30.,
-            Self::PureCateriumIngot => 60.,
-            Self::PureCopperIngot => 24.,
-            Self::PureIronIngot => 10.,
-            Self::PureQuartzCrystal => 37.5,
-            Self::QuartzPurification => 10.,
-            Self::RecycledPlastic => 30.,
-            Self::RecycledRubber => 30.,
-            Self::SloppyAlumina => 200.,
-            Self::SteamedCopperSheet => 22.5,
-            Self::TurboHeavyFuel => 37.5,
-            Self::Turbofuel => 22.5,
-            Self::WetConcrete => 100.,
-        }
+        self.stats().input_speeds()[1]
     }
 
     pub fn max_output_speed_material(&self) -> f32 {
@@ -347,7 +285,7 @@ impl RefineryRecipe {
             return 0.;
         }
 
-        self.output_speed_inner(None)
+        self.output_speed_inner(None, 0)
     }
 
     pub fn max_output_speed_fluid(&self) -> f32 {
@@ -355,7 +293,7 @@ impl RefineryRecipe {
             return 0.;
         }
 
-        self.output_speed_inner(None)
+        self.output_speed_inner(None, 1)
     }
 
     pub fn output_speed_material(&self, input_material_size: f32, input_fluid_size: f32) -> f32 {
@@ -363,7 +301,7 @@ impl RefineryRecipe {
             return 0.;
         }
 
-        self.output_speed_inner(Some((input_material_size, input_fluid_size)))
+        self.output_speed_inner(Some((input_material_size, input_fluid_size)), 0)
     }
 
     pub fn output_speed_fluid(&self, input_material_size: f32, input_fluid_size: f32) -> f32 {
@@ -371,13 +309,13 @@ impl RefineryRecipe {
             return 0.;
         }
 
-        self.output_speed_inner(Some((input_material_size, input_fluid_size)))
+        self.output_speed_inner(Some((input_material_size, input_fluid_size)), 1)
     }
 
-    // returns
-    // (duration, fluid_input, material_input, fluid_output, material_output)
-    fn stats(&self) -> (f32, f32, f32, f32, f32) {
-        match self {
+    /// Ingredients are (material, fluid), products (material, fluid).
+    pub(super) fn stats(&self) -> RecipeStats<2, 2> {
+        // (duration, fluid_input, material_input, fluid_output, material_output)
+        let (duration, fluid_input, material_input, fluid_output, material_output) = match self {
             Self::AluminaSolution => (6., 18., 12., 12., 5.),
             Self::AluminumScrap => (1., 4., 2., 2., 6.),
             Self::Fuel => (6., 6., 0., 4., 3.),
@@ -397,11 +335,11 @@ impl RefineryRecipe {
             Self::HeavyOilResidue => (6., 3., 0., 4., 2.),
             Self::LeachedCateriumIngot => (10., 5., 9., 0., 6.),
             Self::LeachedCopperIngot => (12., 5., 9., 0., 22.),
-            Self::LeachedIroningot => (6., 1., 5., 0., 22.),
+            Self::LeachedIronIngot => (6., 1., 5., 0., 22.),
             Self::PolyesterFabric => (2., 1., 1., 0., 1.),
             Self::PolymerResin => (6., 6., 0., 2., 13.),
             Self::PureCateriumIngot => (5., 2., 2., 0., 1.),
-            Self::PureCopperIngot => (23., 4., 6., 0., 15.),
+            Self::PureCopperIngot => (24., 4., 6., 0., 15.),
             Self::PureIronIngot => (12., 4., 7., 0., 13.),
             Self::PureQuartzCrystal => (8., 5., 9., 0., 7.),
             Self::QuartzPurification => (12., 2., 24., 12., 15.),
@@ -412,64 +350,44 @@ impl RefineryRecipe {
             Self::TurboHeavyFuel => (8., 5., 4., 4., 0.),
             Self::Turbofuel => (16., 6., 4., 5., 0.),
             Self::WetConcrete => (3., 5., 6., 0., 4.),
+        };
+        RecipeStats::new(
+            duration,
+            [material_input, fluid_input],
+            [material_output, fluid_output],
+        )
+        .or_installed(&self.items())
+    }
+
+    pub(super) fn items(&self) -> RecipeItems<2, 2> {
+        RecipeItems {
+            name: self.to_string(),
+            ingredients: [
+                self.input_material().map(Resource::Material),
+                self.input_fluid().map(Resource::Fluid),
+            ],
+            products: [
+                self.output_material().map(Resource::Material),
+                self.output_fluid().map(Resource::Fluid),
+            ],
         }
     }
 
-    fn output_speed_inner(&self, input_size: Option<(f32, f32)>) -> f32 {
+    /// `output` is 0 for the material and 1 for the fluid product.
+    fn output_speed_inner(&self, input_size: Option<(f32, f32)>, output: usize) -> f32 {
         let input_material_size = input_size.map(|(a, _)| a);
         let input_fluid_size = input_size.map(|(_, b)| b);
 
-        let (
-            duration,
-            fluid_input_size,
-            material_input_size,
-            _fluid_output_size,
-            material_output_size,
-        ) = self.stats();
-
-        match (
-            self.input_material().is_some(),
-            self.input_fluid().is_some(),
-        ) {
-            (true, true) => {
-                if input_fluid_size == Some(0.0) || input_material_size == Some(0.0) {
-                    return 0.;
-                }
-
-                calc_output2(
-                    input_size,
-                    duration,
-                    material_output_size,
-                    material_input_size,
-                    fluid_input_size,
-                )
-            }
-            (false, true) => {
-                if input_fluid_size == Some(0.0) {
-                    return 0.;
-                }
-
-                calc_output(
-                    input_fluid_size,
-                    duration,
-                    material_output_size,
-                    fluid_input_size,
-                )
-            }
-            (true, false) => {
-                if input_material_size == Some(0.0) {
-                    return 0.;
-                }
-
-                calc_output(
-                    input_material_size,
-                    duration,
-                    material_output_size,
-                    material_input_size,
-                )
-            }
-            (false, false) => unreachable!("always one input"),
+        if (self.input_material().is_some() && input_material_size == Some(0.0))
+            || (self.input_fluid().is_some() && input_fluid_size == Some(0.0))
+        {
+            return 0.;
         }
+
+        // unused ingredients have an amount of 0 and never limit the recipe
+        self.stats()
+            .evaluate(input_size.map(|(a, b)| [a, b]))
+            .products[output]
     }
 }
 
@@ -502,56 +420,38 @@ impl Refinery {
         this
     }
 
-    pub fn header_image(&self) -> String {
-        load_img("Refinery.png")
-    }
-
-    pub fn name(&self) -> String {
-        match &self.recipe {
-            Some(r) => format!("Refinery ({})", r.name()),
-            None => "Refinery".to_string(),
-        }
-    }
-
-    pub fn description(&self) -> String {
-        "Smelts things".to_string()
-    }
-
-    pub fn num_inputs(&self) -> usize {
-        2
-    }
-
-    pub fn num_outputs(&self) -> usize {
-        2
-    }
-
-    pub fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
-        match input_id {
-            0 => crate::node::ResourceType::Fluid,
-            1 => crate::node::ResourceType::Material,
-            _ => unreachable!("2 inputs"),
-        }
+    pub fn input_material_speed(&self) -> f32 {
+        let base = self
+            .recipe
+            .as_ref()
+            .map(|r| r.input_material_speed())
+            .unwrap_or_default();
+        round(base * clock_factor(self.speed))
     }
 
-    pub fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
-        match output_id {
-            0 => crate::node::ResourceType::Fluid,
-            1 => crate::node::ResourceType::Material,
-            _ => unreachable!("2 outputs"),
-        }
+    pub fn input_fluid_speed(&self) -> f32 {
+        let base = self
+            .recipe
+            .as_ref()
+            .map(|r| r.input_fluid_speed())
+            .unwrap_or_default();
+        round(base * clock_factor(self.speed))
     }
 
     pub fn output_material_speed(&self) -> f32 {
+        let clock = clock_factor(self.speed);
         let input_material_speed = self
             .current_input_material
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let input_fluid_speed = self
             .current_input_fluid
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let base = self
             .recipe
             .as_ref()
@@ -559,22 +459,23 @@ impl Refinery {
             .unwrap_or_default();
         let amplification = self.amplified.factor();
 
-        // TODO: take speed into account for input_speed
-
-        round(base as f32 * (self.speed / 100.) * amplification)
+        round(base as f32 * clock * amplification)
     }
 
     pub fn output_fluid_speed(&self) -> f32 {
+        let clock = clock_factor(self.speed);
         let input_material_speed = self
             .current_input_material
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let input_fluid_speed = self
             .current_input_fluid
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
         let base = self
             .recipe
             .as_ref()
@@ -582,9 +483,7 @@ impl Refinery {
             .unwrap_or_default();
         let amplification = self.amplified.factor();
 
-        // TODO: take speed into account for input_speed
-
-        round(base as f32 * (self.speed / 100.) * amplification)
+        round(base as f32 * clock * amplification)
     }
 
     pub fn output_material(&self) -> Option<Material> {
@@ -620,6 +519,130 @@ impl Refinery {
     }
 }
 
+impl Machine for Refinery {
+    fn header_image(&self) -> String {
+        load_img("Refinery.png")
+    }
+
+    fn name(&self) -> String {
+        match &self.recipe {
+            Some(r) => format!("Refinery ({})", r.name()),
+            None => "Refinery".to_string(),
+        }
+    }
+
+    fn description(&self) -> String {
+        "Smelts things".to_string()
+    }
+
+    fn unlocked_by(&self) -> Unlock {
+        Unlock::milestone(5, "Oil Processing")
+    }
+
+    fn num_inputs(&self) -> usize {
+        2
+    }
+
+    fn num_outputs(&self) -> usize {
+        2
+    }
+
+    fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
+        match input_id {
+            0 => crate::node::ResourceType::Fluid,
+            1 => crate::node::ResourceType::Material,
+            _ => unreachable!("2 inputs"),
+        }
+    }
+
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+        match output_id {
+            0 => crate::node::ResourceType::Fluid,
+            1 => crate::node::ResourceType::Material,
+            _ => unreachable!("2 outputs"),
+        }
+    }
+
+    fn input_spec(&self, input_id: usize) -> Option<PinSpec> {
+        let spec = match input_id {
+            0 => PinSpec {
+                resource: self.input_fluid().map(Resource::Fluid),
+                max_speed: self.input_fluid_speed(),
+            },
+            1 => PinSpec {
+                resource: self.input_material().map(Resource::Material),
+                max_speed: self.input_material_speed(),
+            },
+            _ => unreachable!("2 inputs"),
+        };
+        Some(spec)
+    }
+
+    fn output_spec(&self, output_id: usize) -> Option<PinSpec> {
+        let factor = clock_factor(self.speed) * self.amplified.factor();
+        let spec = match output_id {
+            0 => PinSpec {
+                resource: self.output_fluid().map(Resource::Fluid),
+                max_speed: self
+                    .recipe
+                    .as_ref()
+                    .map(|r| round(r.max_output_speed_fluid() * factor))
+                    .unwrap_or_default(),
+            },
+            1 => PinSpec {
+                resource: self.output_material().map(Resource::Material),
+                max_speed: self
+                    .recipe
+                    .as_ref()
+                    .map(|r| round(r.max_output_speed_material() * factor))
+                    .unwrap_or_default(),
+            },
+            _ => unreachable!("2 outputs"),
+        };
+        Some(spec)
+    }
+
+    fn current_input(&self, input_id: usize) -> Option<Input> {
+        match input_id {
+            0 => self.current_input_fluid.clone(),
+            1 => self.current_input_material.clone(),
+            _ => unreachable!("2 inputs"),
+        }
+    }
+
+    fn current_input_mut(&mut self, input_id: usize) -> &mut Option<Input> {
+        match input_id {
+            0 => &mut self.current_input_fluid,
+            1 => &mut self.current_input_material,
+            _ => unreachable!("2 inputs"),
+        }
+    }
+
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        match output_id {
+            0 => self.current_output_fluid(),
+            1 => self.current_output_material(),
+            _ => unreachable!("2 outputs"),
+        }
+    }
+
+    fn clock_speed(&self) -> Option<f32> {
+        Some(self.speed)
+    }
+
+    fn show_body(&mut self, ui: &mut Ui, scale: f32, cx: &mut BodyContext) -> bool {
+        let mut changed = recipe_selector(ui, scale, &mut self.recipe, cx.unlocks).changed;
+        ui.add_space(10.0 * scale);
+
+        let matching = self.input_limited_clock();
+        changed |= add_speed_ui(ui, &mut self.speed, matching).changed;
+        ui.add_space(10.0 * scale);
+
+        changed |= add_somersloop2_ui(ui, &mut self.amplified).changed;
+        changed
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -643,4 +666,14 @@ mod tests {
             0.
         );
     }
+
+    #[test]
+    fn test_output_speed_fuel() {
+        // 6 oil to 4 fuel and 3 resin every 6s
+        assert_eq!(RefineryRecipe::Fuel.max_output_speed_fluid(), 40.);
+        assert_eq!(RefineryRecipe::Fuel.max_output_speed_material(), 30.);
+        assert_eq!(RefineryRecipe::Fuel.output_speed_fluid(0., 30.), 20.);
+        assert_eq!(RefineryRecipe::Fuel.output_speed_material(0., 30.), 15.);
+        assert_eq!(RefineryRecipe::Fuel.output_speed_fluid(0., 0.), 0.);
+    }
 }
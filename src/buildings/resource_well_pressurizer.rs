@@ -0,0 +1,202 @@
+use egui::Ui;
+
+use crate::{
+    node::{Output, Resource},
+    unlocks::Unlock,
+    util::load_img,
+    widgets::{add_speed_ui, general_selector, purity_selector},
+};
+
+use super::{miner::ResourcePurity, round, BodyContext, Fluid, Machine, Selectable};
+
+/// Resource wells never have more satellite nodes than this.
+pub const MAX_SATELLITES: usize = 10;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct ResourceWellPressurizer {
+    pub resource: Option<WellResource>,
+    /// Purity of each Resource Well Extractor placed on a satellite node.
+    pub satellites: Vec<ResourcePurity>,
+    pub speed: f32,
+}
+
+impl Default for ResourceWellPressurizer {
+    fn default() -> Self {
+        Self {
+            resource: None,
+            satellites: vec![ResourcePurity::Normal],
+            speed: 100.,
+        }
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    serde::Serialize,
+    serde::Deserialize,
+    PartialEq,
+    strum::VariantArray,
+    strum::Display,
+)]
+pub enum WellResource {
+    #[strum(to_string = "Nitrogen Gas")]
+    NitrogenGas,
+    #[strum(to_string = "Water")]
+    Water,
+    #[strum(to_string = "Crude Oil")]
+    CrudeOil,
+}
+
+impl Selectable for WellResource {
+    const NAME: &'static str = "Well";
+
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn image(&self) -> String {
+        self.output_fluid().image()
+    }
+}
+
+impl WellResource {
+    pub fn output_fluid(&self) -> Fluid {
+        match self {
+            Self::NitrogenGas => Fluid::NitrogenGas,
+            Self::Water => Fluid::Water,
+            Self::CrudeOil => Fluid::CrudeOil,
+        }
+    }
+}
+
+impl ResourceWellPressurizer {
+    pub fn clear_clone(&self) -> Self {
+        self.clone()
+    }
+
+    /// Power usage in MW, scaled by the clock speed.
+    pub fn power_usage(&self) -> f32 {
+        round(150. * (self.speed / 100.).powf(1.321928))
+    }
+
+    /// Extraction rate of a single satellite in m^3/min.
+    pub fn satellite_speed(&self, output_id: usize) -> f32 {
+        let purity = self.satellites[output_id];
+        round(60. * purity.modifier() * (self.speed / 100.))
+    }
+
+    pub fn output_speed(&self) -> f32 {
+        let speed = (0..self.satellites.len())
+            .map(|i| self.satellite_speed(i))
+            .sum();
+        round(speed)
+    }
+
+    pub fn output_fluid(&self) -> Option<Fluid> {
+        self.resource.map(|r| r.output_fluid())
+    }
+}
+
+impl Machine for ResourceWellPressurizer {
+    fn header_image(&self) -> String {
+        // no pressurizer image yet
+        load_img("Placeholder.png")
+    }
+
+    fn name(&self) -> String {
+        match &self.resource {
+            Some(r) => format!("Resource Well Pressurizer ({})", r.name()),
+            None => "Resource Well Pressurizer".to_string(),
+        }
+    }
+
+    fn description(&self) -> String {
+        "Pressurizes resource wells for its extractors".to_string()
+    }
+
+    fn unlocked_by(&self) -> Unlock {
+        Unlock::milestone(8, "Advanced Aluminum Production")
+    }
+
+    fn num_inputs(&self) -> usize {
+        0
+    }
+
+    /// One output per Resource Well Extractor.
+    fn num_outputs(&self) -> usize {
+        self.satellites.len()
+    }
+
+    fn input_resource(&self, _input_id: usize) -> crate::node::ResourceType {
+        unreachable!("no inputs");
+    }
+
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+        assert!(output_id < self.satellites.len(), "1 output per satellite");
+        crate::node::ResourceType::Fluid
+    }
+
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        self.output_fluid().map(|f| Output {
+            speed: self.satellite_speed(output_id),
+            resource: Resource::Fluid(f),
+        })
+    }
+
+    fn show_body(&mut self, ui: &mut Ui, scale: f32, cx: &mut BodyContext) -> bool {
+        let mut changed = general_selector(ui, scale, &mut self.resource).changed;
+        ui.add_space(10.0 * scale);
+
+        for (i, purity) in self.satellites.iter_mut().enumerate() {
+            ui.push_id(i, |ui| {
+                changed |= purity_selector(ui, scale, purity).changed;
+            });
+        }
+        ui.horizontal(|ui| {
+            if self.satellites.len() < MAX_SATELLITES && ui.button("+").clicked() {
+                self.satellites.push(ResourcePurity::Normal);
+                changed = true;
+            }
+            if self.satellites.len() > 1 && ui.button("-").clicked() {
+                self.satellites.pop();
+                cx.removed_output = Some(self.satellites.len());
+                changed = true;
+            }
+            ui.label("Extractors");
+        });
+        ui.add_space(10.0 * scale);
+
+        changed |= add_speed_ui(ui, &mut self.speed, None).changed;
+        ui.label(format!("Total: {}/m^3", self.output_speed()));
+        ui.label(format!("Power: {}MW", self.power_usage()));
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_speed() {
+        let mut well = ResourceWellPressurizer {
+            resource: Some(WellResource::NitrogenGas),
+            satellites: vec![
+                ResourcePurity::Impure,
+                ResourcePurity::Normal,
+                ResourcePurity::Pure,
+            ],
+            speed: 100.,
+        };
+        assert_eq!(well.satellite_speed(0), 30.);
+        assert_eq!(well.satellite_speed(2), 120.);
+        assert_eq!(well.output_speed(), 210.);
+        assert_eq!(well.power_usage(), 150.);
+
+        well.speed = 250.;
+        assert_eq!(well.satellite_speed(2), 300.);
+        assert_eq!(well.output_speed(), 525.);
+    }
+}
@@ -1,17 +1,14 @@
-use crate::{node::Input, util::load_img};
+use egui::Ui;
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+use crate::{node::Input, unlocks::Unlock, util::load_img, widgets::INVALID_COLOR};
+
+use super::{round, BodyContext, Machine};
+
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
 pub struct AwesomeSink {
     pub current_input: Option<Input>,
 }
 
-impl Default for AwesomeSink {
-    fn default() -> Self {
-        Self {
-            current_input: None,
-        }
-    }
-}
 impl AwesomeSink {
     pub fn clear_clone(&self) -> Self {
         let mut this = self.clone();
@@ -19,34 +16,125 @@ impl AwesomeSink {
         this
     }
 
-    pub fn header_image(&self) -> String {
+    /// Points/min for what is fed in, fluids and unsinkable items award nothing.
+    pub fn points_per_minute(&self) -> f32 {
+        match self.current_input {
+            Some(Input { speed, resource }) => {
+                round(speed * resource.metadata().sink_points.unwrap_or_default() as f32)
+            }
+            None => 0.,
+        }
+    }
+
+    /// The input can not go into a sink.
+    pub fn is_rejected(&self) -> bool {
+        self.current_input
+            .as_ref()
+            .is_some_and(|input| input.resource.metadata().sink_points.is_none())
+    }
+}
+
+/// Points needed for the next coupon after `coupons` have been printed.
+pub fn points_for_coupon(coupons: u32) -> u64 {
+    let step = (coupons / 3) as u64;
+    500 * step * step + 1000
+}
+
+impl Machine for AwesomeSink {
+    fn header_image(&self) -> String {
         load_img("AWESOME_Sink.png")
     }
 
-    pub fn name(&self) -> String {
+    fn name(&self) -> String {
         "AWESOME Sink".to_string()
     }
 
-    pub fn description(&self) -> String {
+    fn description(&self) -> String {
         "Awesomely sinks things".to_string()
     }
 
-    pub fn num_inputs(&self) -> usize {
+    fn unlocked_by(&self) -> Unlock {
+        Unlock::milestone(2, "Resource Sink Bonus Program")
+    }
+
+    fn num_inputs(&self) -> usize {
         1
     }
 
-    pub fn num_outputs(&self) -> usize {
+    fn num_outputs(&self) -> usize {
         0
     }
 
-    pub fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
+    fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
         match input_id {
             0 => crate::node::ResourceType::Material,
             _ => unreachable!("1 input"),
         }
     }
 
-    pub fn output_resource(&self, _output_id: usize) -> crate::node::ResourceType {
+    fn output_resource(&self, _output_id: usize) -> crate::node::ResourceType {
         unreachable!("no outputs");
     }
+
+    fn current_input(&self, input_id: usize) -> Option<Input> {
+        assert_eq!(input_id, 0, "1 input");
+        self.current_input.clone()
+    }
+
+    fn current_input_mut(&mut self, input_id: usize) -> &mut Option<Input> {
+        assert_eq!(input_id, 0, "1 input");
+        &mut self.current_input
+    }
+
+    fn show_body(&mut self, ui: &mut Ui, _scale: f32, _cx: &mut BodyContext) -> bool {
+        if self.is_rejected() {
+            ui.colored_label(INVALID_COLOR, "Can not be sunk");
+        }
+        ui.label(format!("Points: {}/min", self.points_per_minute()));
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        buildings::{Fluid, Material},
+        node::Resource,
+    };
+
+    #[test]
+    fn test_points_per_minute() {
+        let mut sink = AwesomeSink {
+            current_input: Some(Input {
+                speed: 30.,
+                resource: Resource::Material(Material::ReinforcedIronPlate),
+            }),
+        };
+        assert_eq!(sink.points_per_minute(), 3600.);
+        assert!(!sink.is_rejected());
+
+        sink.current_input = Some(Input {
+            speed: 10.,
+            resource: Resource::Material(Material::UraniumWaste),
+        });
+        assert_eq!(sink.points_per_minute(), 0.);
+        assert!(sink.is_rejected());
+
+        sink.current_input = Some(Input {
+            speed: 10.,
+            resource: Resource::Fluid(Fluid::Water),
+        });
+        assert_eq!(sink.points_per_minute(), 0.);
+        assert!(sink.is_rejected());
+    }
+
+    #[test]
+    fn test_points_for_coupon() {
+        assert_eq!(points_for_coupon(0), 1000);
+        assert_eq!(points_for_coupon(2), 1000);
+        assert_eq!(points_for_coupon(3), 1500);
+        assert_eq!(points_for_coupon(6), 3000);
+        assert_eq!(points_for_coupon(9), 5500);
+    }
 }
@@ -1,11 +1,16 @@
+use egui::Ui;
 use strum::VariantArray;
 
 use crate::{
     node::{Input, Output, Resource},
     util::load_img,
+    widgets::{add_somersloop1_ui, add_speed_ui, recipe_selector},
 };
 
-use super::{calc_output, round, Material, Selectable, SomersloopSlot1};
+use super::{
+    clock_factor, round, BodyContext, Machine, Material, PinSpec, RecipeItems, RecipeStats,
+    Selectable, SomersloopSlot1,
+};
 
 #[derive(
     Debug,
@@ -59,13 +64,8 @@ impl SmelterRecipe {
         }
     }
 
-    pub fn input_speed(&self) -> usize {
-        match self {
-            Self::CateriumIngot => 45,
-            Self::CopperIngot => 30,
-            Self::IronIngot => 30,
-            Self::PureAluminumIngot => 60,
-        }
+    pub fn input_speed(&self) -> f32 {
+        self.stats().input_speeds()[0]
     }
 
     pub fn max_output_speed(&self) -> f32 {
@@ -76,19 +76,30 @@ impl SmelterRecipe {
         self.output_speed_inner(Some(input_size))
     }
 
-    fn output_speed_inner(&self, input_size: Option<f32>) -> f32 {
-        if input_size == Some(0.) {
-            return 0.;
-        }
-
+    pub(super) fn stats(&self) -> RecipeStats<1, 1> {
         let (duration, output_size, input_base) = match self {
             Self::CateriumIngot => (4., 1., 3.),
             Self::CopperIngot => (2., 1., 1.),
             Self::IronIngot => (2., 1., 1.),
             Self::PureAluminumIngot => (2., 1., 2.),
         };
+        RecipeStats::new(duration, [input_base], [output_size]).or_installed(&self.items())
+    }
 
-        calc_output(input_size, duration, output_size, input_base)
+    pub(super) fn items(&self) -> RecipeItems<1, 1> {
+        RecipeItems {
+            name: self.to_string(),
+            ingredients: [Some(Resource::Material(self.input_material()))],
+            products: [Some(Resource::Material(self.output_material()))],
+        }
+    }
+
+    fn output_speed_inner(&self, input_size: Option<f32>) -> f32 {
+        if input_size == Some(0.) {
+            return 0.;
+        }
+
+        self.stats().evaluate(input_size.map(|s| [s])).products[0]
     }
 }
 
@@ -118,58 +129,27 @@ impl Smelter {
         this
     }
 
-    pub fn header_image(&self) -> String {
-        load_img("Smelter.png")
-    }
-
     pub fn available_recipes(&self) -> &'static [SmelterRecipe] {
         SmelterRecipe::VARIANTS
     }
 
-    pub fn name(&self) -> String {
-        match &self.recipe {
-            Some(r) => format!("Smelter ({})", r.name()),
-            None => "Smelter".to_string(),
-        }
-    }
-
-    pub fn description(&self) -> String {
-        "Smelts things".to_string()
-    }
-
-    pub fn num_inputs(&self) -> usize {
-        1
-    }
-
-    pub fn num_outputs(&self) -> usize {
-        1
-    }
-
-    pub fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
-        assert_eq!(input_id, 0, "1 input");
-        crate::node::ResourceType::Material
-    }
-
-    pub fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
-        assert_eq!(output_id, 0, "1 output");
-        crate::node::ResourceType::Material
-    }
-
     pub fn input_speed(&self) -> f32 {
         let base = self
             .recipe
             .as_ref()
             .map(|r| r.input_speed())
             .unwrap_or_default();
-        round(base as f32 * (self.speed / 100.))
+        round(base * clock_factor(self.speed))
     }
 
     pub fn output_speed(&self) -> f32 {
+        let clock = clock_factor(self.speed);
         let input_speed = self
             .current_input
             .as_ref()
             .map(|i| i.speed)
-            .unwrap_or_default();
+            .unwrap_or_default()
+            / clock;
 
         let base = self
             .recipe
@@ -178,9 +158,7 @@ impl Smelter {
             .unwrap_or_default();
         let amplification = self.amplified.factor();
 
-        // TODO: take speed into account for input_speed
-
-        round(base as f32 * (self.speed / 100.) * amplification)
+        round(base as f32 * clock * amplification)
     }
 
     pub fn input_material(&self) -> Option<Material> {
@@ -190,18 +168,102 @@ impl Smelter {
     pub fn output_material(&self) -> Option<Material> {
         self.recipe.as_ref().map(|r| r.output_material())
     }
+}
+
+impl Machine for Smelter {
+    fn header_image(&self) -> String {
+        load_img("Smelter.png")
+    }
 
-    pub fn current_output(&self) -> Option<Output> {
+    fn name(&self) -> String {
+        match &self.recipe {
+            Some(r) => format!("Smelter ({})", r.name()),
+            None => "Smelter".to_string(),
+        }
+    }
+
+    fn description(&self) -> String {
+        "Smelts things".to_string()
+    }
+
+    fn num_inputs(&self) -> usize {
+        1
+    }
+
+    fn num_outputs(&self) -> usize {
+        1
+    }
+
+    fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
+        assert_eq!(input_id, 0, "1 input");
+        crate::node::ResourceType::Material
+    }
+
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+        assert_eq!(output_id, 0, "1 output");
+        crate::node::ResourceType::Material
+    }
+
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        assert_eq!(output_id, 0, "1 output");
         self.recipe.map(|r| Output {
             speed: self.output_speed(),
             resource: Resource::Material(r.output_material()),
         })
     }
+
+    fn input_spec(&self, input_id: usize) -> Option<PinSpec> {
+        assert_eq!(input_id, 0, "1 input");
+        Some(PinSpec {
+            resource: self.input_material().map(Resource::Material),
+            max_speed: self.input_speed(),
+        })
+    }
+
+    fn output_spec(&self, output_id: usize) -> Option<PinSpec> {
+        let factor = clock_factor(self.speed) * self.amplified.factor();
+        assert_eq!(output_id, 0, "1 output");
+        Some(PinSpec {
+            resource: self.output_material().map(Resource::Material),
+            max_speed: self
+                .recipe
+                .as_ref()
+                .map(|r| round(r.max_output_speed() * factor))
+                .unwrap_or_default(),
+        })
+    }
+
+    fn current_input(&self, input_id: usize) -> Option<Input> {
+        assert_eq!(input_id, 0, "1 input");
+        self.current_input.clone()
+    }
+
+    fn current_input_mut(&mut self, input_id: usize) -> &mut Option<Input> {
+        assert_eq!(input_id, 0, "1 input");
+        &mut self.current_input
+    }
+
+    fn clock_speed(&self) -> Option<f32> {
+        Some(self.speed)
+    }
+
+    fn show_body(&mut self, ui: &mut Ui, scale: f32, cx: &mut BodyContext) -> bool {
+        let mut changed = recipe_selector(ui, scale, &mut self.recipe, cx.unlocks).changed;
+        ui.add_space(10.0 * scale);
+
+        let matching = self.input_limited_clock();
+        changed |= add_speed_ui(ui, &mut self.speed, matching).changed;
+        ui.add_space(10.0 * scale);
+
+        changed |= add_somersloop1_ui(ui, &mut self.amplified).changed;
+        changed
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::buildings::MAX_CLOCK_SPEED;
 
     #[test]
     fn test_output_speed() {
@@ -221,4 +283,32 @@ mod tests {
         assert_eq!(SmelterRecipe::PureAluminumIngot.output_speed(60.), 30.);
         assert_eq!(SmelterRecipe::PureAluminumIngot.output_speed(120.), 30.);
     }
+
+    #[test]
+    fn test_clock_speed() {
+        let mut smelter = Smelter {
+            recipe: Some(SmelterRecipe::IronIngot),
+            speed: 200.,
+            ..Default::default()
+        };
+        assert_eq!(smelter.input_speed(), 60.);
+        assert_eq!(smelter.input_limited_clock(), None);
+
+        smelter.current_input = Some(Input {
+            speed: 45.,
+            resource: Resource::Material(Material::IronOre),
+        });
+        assert_eq!(smelter.output_speed(), 45.);
+        assert_eq!(smelter.input_limited_clock(), Some(150.));
+
+        smelter.speed = 50.;
+        assert_eq!(smelter.input_speed(), 15.);
+        assert_eq!(smelter.output_speed(), 15.);
+
+        smelter.current_input = Some(Input {
+            speed: 600.,
+            resource: Resource::Material(Material::IronOre),
+        });
+        assert_eq!(smelter.input_limited_clock(), Some(MAX_CLOCK_SPEED));
+    }
 }
@@ -1,9 +1,10 @@
 use crate::{
     node::{Input, Output},
+    unlocks::Unlock,
     util::load_img,
 };
 
-use super::{round, Material};
+use super::{round, Machine, Material};
 
 #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Splitter {
@@ -23,36 +24,6 @@ impl Splitter {
         }
     }
 
-    pub fn header_image(&self) -> String {
-        load_img("Conveyor_Splitter.png")
-    }
-
-    pub fn name(&self) -> String {
-        "Splitter".to_string()
-    }
-
-    pub fn description(&self) -> String {
-        "Splits things".to_string()
-    }
-
-    pub fn num_inputs(&self) -> usize {
-        1
-    }
-
-    pub fn num_outputs(&self) -> usize {
-        3
-    }
-
-    pub fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
-        assert_eq!(input_id, 0, "1 input");
-        crate::node::ResourceType::Material
-    }
-
-    pub fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
-        assert!(output_id < 3, "3 outputs");
-        crate::node::ResourceType::Material
-    }
-
     pub fn input_material(&self) -> Option<Material> {
         None
     }
@@ -108,3 +79,67 @@ impl Splitter {
         }
     }
 }
+
+impl Machine for Splitter {
+    fn header_image(&self) -> String {
+        load_img("Conveyor_Splitter.png")
+    }
+
+    fn name(&self) -> String {
+        "Splitter".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Splits things".to_string()
+    }
+
+    fn unlocked_by(&self) -> Unlock {
+        Unlock::milestone(1, "Logistics")
+    }
+
+    fn num_inputs(&self) -> usize {
+        1
+    }
+
+    fn num_outputs(&self) -> usize {
+        3
+    }
+
+    fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
+        assert_eq!(input_id, 0, "1 input");
+        crate::node::ResourceType::Material
+    }
+
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+        assert!(output_id < 3, "3 outputs");
+        crate::node::ResourceType::Material
+    }
+
+    fn current_input(&self, input_id: usize) -> Option<Input> {
+        assert_eq!(input_id, 0, "1 input");
+        self.current_input.clone()
+    }
+
+    fn current_input_mut(&mut self, input_id: usize) -> &mut Option<Input> {
+        assert_eq!(input_id, 0, "1 input");
+        &mut self.current_input
+    }
+
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        match output_id {
+            0 => self.current_output_0(),
+            1 => self.current_output_1(),
+            2 => self.current_output_2(),
+            _ => unreachable!("3 outputs"),
+        }
+    }
+
+    fn set_output_connected(&mut self, output_id: usize, connected: bool) {
+        match output_id {
+            0 => self.output_0_connected = connected,
+            1 => self.output_1_connected = connected,
+            2 => self.output_2_connected = connected,
+            _ => unreachable!("3 outputs"),
+        }
+    }
+}
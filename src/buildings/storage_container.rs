@@ -1,84 +1,414 @@
+use egui::Ui;
+
 use crate::{
-    node::{Output, Resource},
+    node::{Input, Output, Resource},
+    unlocks::{Gated, Unlock},
     util::load_img,
+    widgets::{gated_selector, general_selector, INVALID_COLOR},
 };
 
-use super::{Belt, Material, Selectable};
+use super::{round, Belt, BodyContext, Machine, Material, PinSpec, Selectable};
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct StorageContainer {
+    #[serde(default)]
+    pub kind: ContainerKind,
+    /// Material emitted when nothing is connected to the inputs.
     pub material: Option<Material>,
     pub output_belt: Option<Belt>,
+    #[serde(default)]
+    pub current_input_0: Option<Input>,
+    #[serde(default)]
+    pub current_input_1: Option<Input>,
+    #[serde(default)]
+    pub output_0_connected: bool,
+    #[serde(default)]
+    pub output_1_connected: bool,
+}
+
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    serde::Serialize,
+    serde::Deserialize,
+    PartialEq,
+    strum::VariantArray,
+    strum::Display,
+)]
+pub enum ContainerKind {
+    #[default]
+    #[strum(to_string = "Storage Container")]
+    Standard,
+    #[strum(to_string = "Industrial Storage Container")]
+    Industrial,
 }
 
-impl Default for StorageContainer {
-    fn default() -> Self {
-        Self {
-            material: None,
-            output_belt: None,
+impl Selectable for ContainerKind {
+    const NAME: &'static str = "Container";
+
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn image(&self) -> String {
+        match self {
+            Self::Standard => load_img("Storage_Container.png"),
+            // no industrial container image yet
+            Self::Industrial => load_img("Placeholder.png"),
         }
     }
 }
-impl StorageContainer {
-    pub fn clear_clone(&self) -> Self {
-        let this = self.clone();
-        this
-    }
 
-    pub fn header_image(&self) -> String {
-        load_img("Storage_Container.png")
+impl Gated for ContainerKind {
+    fn unlocked_by(&self) -> Unlock {
+        match self {
+            Self::Standard => Unlock::ONBOARDING,
+            Self::Industrial => Unlock::milestone(4, "Logistics Mk.3"),
+        }
     }
+}
 
-    pub fn name(&self) -> String {
-        match &self.material {
-            Some(r) => format!("Storage Container ({})", r.name()),
-            None => "Storage Container".to_string(),
+impl ContainerKind {
+    pub fn slots(&self) -> usize {
+        match self {
+            Self::Standard => 24,
+            Self::Industrial => 48,
         }
     }
 
-    pub fn description(&self) -> String {
-        "Stores things".to_string()
+    /// Number of inputs and outputs.
+    pub fn ports(&self) -> usize {
+        match self {
+            Self::Standard => 1,
+            Self::Industrial => 2,
+        }
     }
+}
 
-    pub fn num_inputs(&self) -> usize {
-        0
+impl StorageContainer {
+    pub fn clear_clone(&self) -> Self {
+        let mut this = self.clone();
+        this.current_input_0 = None;
+        this.current_input_1 = None;
+        this.output_0_connected = false;
+        this.output_1_connected = false;
+        this
     }
 
-    pub fn num_outputs(&self) -> usize {
-        1
+    fn inputs(&self) -> impl Iterator<Item = &Input> {
+        [&self.current_input_0, &self.current_input_1]
+            .into_iter()
+            .take(self.kind.ports())
+            .flatten()
     }
 
-    pub fn input_resource(&self, _input_id: usize) -> crate::node::ResourceType {
-        unreachable!("no inputs");
+    pub fn has_inputs(&self) -> bool {
+        self.inputs().next().is_some()
     }
 
-    pub fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
-        assert_eq!(output_id, 0, "1 output");
-        crate::node::ResourceType::Material
+    /// True if the inputs carry more than one material.
+    pub fn has_mixed_inputs(&self) -> bool {
+        let material = self.output_material().map(Resource::Material);
+        self.inputs().any(|i| Some(i.resource) != material)
     }
 
+    /// Items/min arriving of the material this container outputs.
     pub fn input_speed(&self) -> f32 {
-        0.
+        let material = self.output_material().map(Resource::Material);
+        let speed = self
+            .inputs()
+            .filter(|i| Some(i.resource) == material)
+            .map(|i| i.speed)
+            .sum();
+        round(speed)
     }
 
-    pub fn output_speed(&self) -> f32 {
+    /// Maximum speed of a single output.
+    pub fn belt_speed(&self) -> f32 {
         self.output_belt
             .as_ref()
             .map(|b| b.speed())
             .unwrap_or_default()
     }
 
-    pub fn input_material(&self) -> Option<Material> {
-        None
-    }
+    /// The material of the first connected input, otherwise the selected one.
     pub fn output_material(&self) -> Option<Material> {
-        self.material
+        self.inputs()
+            .find_map(|i| match i.resource {
+                Resource::Material(m) => Some(m),
+                Resource::Fluid(_) => None,
+            })
+            .or(self.material)
+    }
+
+    fn is_output_active(&self, output_id: usize) -> bool {
+        match (self.kind, output_id) {
+            (ContainerKind::Standard, 0) => true,
+            (ContainerKind::Industrial, 0) => self.output_0_connected,
+            (ContainerKind::Industrial, 1) => self.output_1_connected,
+            _ => unreachable!("{} outputs", self.num_outputs()),
+        }
+    }
+
+    fn num_outputs_active(&self) -> usize {
+        (0..self.num_outputs())
+            .filter(|o| self.is_output_active(*o))
+            .count()
+    }
+
+    /// Speed of one output: passes the inputs through, or drains at belt speed without inputs.
+    pub fn output_speed(&self, output_id: usize) -> f32 {
+        if !self.is_output_active(output_id) {
+            return 0.;
+        }
+        if !self.has_inputs() {
+            return self.belt_speed();
+        }
+
+        let share = self.input_speed() / self.num_outputs_active() as f32;
+        match self.output_belt {
+            Some(belt) => round(share.min(belt.speed())),
+            None => round(share),
+        }
+    }
+
+    /// Items/min going into storage, negative when draining.
+    pub fn net_flow(&self) -> f32 {
+        let out: f32 = (0..self.num_outputs()).map(|o| self.output_speed(o)).sum();
+        round(self.input_speed() - out)
+    }
+
+    /// Number of items the container holds of its material.
+    pub fn capacity(&self) -> Option<f32> {
+        self.output_material()
+            .and_then(|m| m.metadata().stack_size)
+            .map(|stack| (self.kind.slots() * stack) as f32)
+    }
+
+    /// Minutes until an empty container is full, if it is filling up.
+    pub fn fill_time(&self) -> Option<f32> {
+        let net = self.net_flow();
+        if net > 0. {
+            self.capacity().map(|c| round(c / net))
+        } else {
+            None
+        }
+    }
+
+    /// Minutes until a full container is empty, if it is draining.
+    pub fn drain_time(&self) -> Option<f32> {
+        let net = self.net_flow();
+        if net < 0. {
+            self.capacity().map(|c| round(c / -net))
+        } else {
+            None
+        }
+    }
+}
+
+impl Machine for StorageContainer {
+    fn header_image(&self) -> String {
+        self.kind.image()
+    }
+
+    fn name(&self) -> String {
+        match &self.output_material() {
+            Some(r) => format!("{} ({})", self.kind.name(), r.name()),
+            None => self.kind.name(),
+        }
+    }
+
+    fn description(&self) -> String {
+        "Stores things, or buffers what passes through".to_string()
+    }
+
+    fn unlocked_by(&self) -> Unlock {
+        self.kind.unlocked_by()
     }
 
-    pub fn current_output(&self) -> Option<Output> {
-        self.material.map(|m| Output {
-            speed: self.output_speed(),
+    fn num_inputs(&self) -> usize {
+        self.kind.ports()
+    }
+
+    fn num_outputs(&self) -> usize {
+        self.kind.ports()
+    }
+
+    fn input_resource(&self, input_id: usize) -> crate::node::ResourceType {
+        assert!(input_id < self.num_inputs(), "{} inputs", self.num_inputs());
+        crate::node::ResourceType::Material
+    }
+
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+        assert!(
+            output_id < self.num_outputs(),
+            "{} outputs",
+            self.num_outputs()
+        );
+        crate::node::ResourceType::Material
+    }
+
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        if !self.is_output_active(output_id) {
+            return None;
+        }
+        self.output_material().map(|m| Output {
+            speed: self.output_speed(output_id),
             resource: Resource::Material(m),
         })
     }
+
+    fn input_spec(&self, input_id: usize) -> Option<PinSpec> {
+        assert!(input_id < self.num_inputs(), "{} inputs", self.num_inputs());
+        Some(PinSpec {
+            resource: self.output_material().map(Resource::Material),
+            max_speed: self.belt_speed(),
+        })
+    }
+
+    fn output_spec(&self, output_id: usize) -> Option<PinSpec> {
+        assert!(
+            output_id < self.num_outputs(),
+            "{} outputs",
+            self.num_outputs()
+        );
+        Some(PinSpec {
+            resource: self.output_material().map(Resource::Material),
+            max_speed: self.belt_speed(),
+        })
+    }
+
+    fn current_input(&self, input_id: usize) -> Option<Input> {
+        match input_id {
+            0 => self.current_input_0.clone(),
+            1 => self.current_input_1.clone(),
+            _ => unreachable!("2 inputs"),
+        }
+    }
+
+    fn current_input_mut(&mut self, input_id: usize) -> &mut Option<Input> {
+        match input_id {
+            0 => &mut self.current_input_0,
+            1 => &mut self.current_input_1,
+            _ => unreachable!("2 inputs"),
+        }
+    }
+
+    fn set_output_connected(&mut self, output_id: usize, connected: bool) {
+        match output_id {
+            0 => self.output_0_connected = connected,
+            1 => self.output_1_connected = connected,
+            _ => unreachable!("2 outputs"),
+        }
+    }
+
+    fn show_body(&mut self, ui: &mut Ui, scale: f32, cx: &mut BodyContext) -> bool {
+        let mut changed = false;
+        let mut kind = Some(self.kind);
+        if gated_selector(ui, scale, &mut kind, cx.unlocks).changed {
+            if let Some(kind) = kind {
+                if kind.ports() < self.kind.ports() {
+                    cx.removed_input = Some(1);
+                    cx.removed_output = Some(1);
+                }
+                self.kind = kind;
+            }
+            changed = true;
+        }
+        ui.add_space(10.0 * scale);
+
+        if !self.has_inputs() {
+            changed |= general_selector(ui, scale, &mut self.material).changed;
+            ui.add_space(10.0 * scale);
+        }
+
+        changed |= gated_selector(ui, scale, &mut self.output_belt, cx.unlocks).changed;
+        ui.add_space(10.0 * scale);
+
+        if self.has_mixed_inputs() {
+            ui.colored_label(INVALID_COLOR, "Inputs carry different materials");
+        }
+        if let Some(capacity) = self.capacity() {
+            ui.label(format!("Capacity: {}", capacity));
+        }
+        if let Some(time) = self.fill_time() {
+            ui.label(format!("Full after {} min", time));
+        } else if let Some(time) = self.drain_time() {
+            ui.label(format!("Empty after {} min when full", time));
+        } else if self.output_material().is_some() {
+            ui.label("Balanced");
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(material: Material, speed: f32) -> Option<Input> {
+        Some(Input {
+            speed,
+            resource: Resource::Material(material),
+        })
+    }
+
+    #[test]
+    fn test_source_drains() {
+        let container = StorageContainer {
+            material: Some(Material::IronPlate),
+            output_belt: Some(Belt::Mk1),
+            ..Default::default()
+        };
+        assert_eq!(container.current_output(0).unwrap().speed, 60.);
+        assert_eq!(container.net_flow(), -60.);
+        // 24 slots of 200 plates
+        assert_eq!(container.drain_time(), Some(80.));
+        assert_eq!(container.fill_time(), None);
+    }
+
+    #[test]
+    fn test_pass_through() {
+        let mut container = StorageContainer {
+            output_belt: Some(Belt::Mk1),
+            current_input_0: input(Material::Screw, 90.),
+            ..Default::default()
+        };
+        assert_eq!(container.output_material(), Some(Material::Screw));
+        assert_eq!(container.current_output(0).unwrap().speed, 60.);
+        assert_eq!(container.net_flow(), 30.);
+        // 24 slots of 500 screws
+        assert_eq!(container.fill_time(), Some(400.));
+
+        container.output_belt = Some(Belt::Mk2);
+        assert_eq!(container.net_flow(), 0.);
+        assert_eq!(container.fill_time(), None);
+        assert_eq!(container.drain_time(), None);
+    }
+
+    #[test]
+    fn test_industrial_splits_outputs() {
+        let mut container = StorageContainer {
+            kind: ContainerKind::Industrial,
+            output_belt: Some(Belt::Mk3),
+            current_input_0: input(Material::IronIngot, 120.),
+            current_input_1: input(Material::IronIngot, 60.),
+            output_0_connected: true,
+            ..Default::default()
+        };
+        assert_eq!(container.num_inputs(), 2);
+        assert_eq!(container.current_output(0).unwrap().speed, 180.);
+        assert!(container.current_output(1).is_none());
+
+        container.output_1_connected = true;
+        assert_eq!(container.current_output(0).unwrap().speed, 90.);
+        assert_eq!(container.current_output(1).unwrap().speed, 90.);
+        assert!(!container.has_mixed_inputs());
+
+        container.current_input_1 = input(Material::CopperIngot, 60.);
+        assert!(container.has_mixed_inputs());
+        assert_eq!(container.input_speed(), 120.);
+    }
 }
@@ -1,9 +1,13 @@
+use egui::Ui;
+
 use crate::{
     node::{Output, Resource},
+    unlocks::Unlock,
     util::load_img,
+    widgets::{add_speed_ui, extraction_ui, pipe_selector},
 };
 
-use super::{round, Fluid, Pipe};
+use super::{round, BodyContext, Fluid, Machine, Pipe};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct WaterExtractor {
@@ -26,54 +30,77 @@ impl WaterExtractor {
         this
     }
 
-    pub fn header_image(&self) -> String {
+    /// What the extractor pumps, before the output pipe limits it.
+    pub fn theoretical_speed(&self) -> f32 {
+        round(120. * (self.speed / 100.))
+    }
+
+    /// Nothing flows until a pipe is picked.
+    pub fn output_speed(&self) -> f32 {
+        let max = self.output_pipe.map(|p| p.speed()).unwrap_or_default();
+        self.theoretical_speed().min(max)
+    }
+
+    pub fn output_fluid(&self) -> Fluid {
+        Fluid::Water
+    }
+}
+
+impl Machine for WaterExtractor {
+    fn header_image(&self) -> String {
         load_img("Water_Extractor.png")
     }
 
-    pub fn name(&self) -> String {
+    fn name(&self) -> String {
         "Water Extractor".to_string()
     }
 
-    pub fn description(&self) -> String {
+    fn description(&self) -> String {
         "Extracts water".to_string()
     }
 
-    pub fn num_inputs(&self) -> usize {
+    fn unlocked_by(&self) -> Unlock {
+        Unlock::milestone(3, "Coal Power")
+    }
+
+    fn num_inputs(&self) -> usize {
         0
     }
 
-    pub fn num_outputs(&self) -> usize {
+    fn num_outputs(&self) -> usize {
         1
     }
 
-    pub fn input_resource(&self, _input_id: usize) -> crate::node::ResourceType {
+    fn input_resource(&self, _input_id: usize) -> crate::node::ResourceType {
         unreachable!("no inputs");
     }
 
-    pub fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
+    fn output_resource(&self, output_id: usize) -> crate::node::ResourceType {
         assert_eq!(output_id, 0, "1 output");
         crate::node::ResourceType::Fluid
     }
 
-    pub fn output_speed(&self) -> f32 {
-        let max = self.output_pipe.map(|p| p.speed()).unwrap_or_default();
-        let val = round(120. * (self.speed / 100.));
-
-        if val > max {
-            max
-        } else {
-            val
-        }
+    fn current_output(&self, output_id: usize) -> Option<Output> {
+        assert_eq!(output_id, 0, "1 output");
+        self.output_pipe.map(|_| Output {
+            speed: self.output_speed(),
+            resource: Resource::Fluid(self.output_fluid()),
+        })
     }
 
-    pub fn output_fluid(&self) -> Fluid {
-        Fluid::Water
+    fn is_output_limited(&self, output_id: usize) -> bool {
+        assert_eq!(output_id, 0, "1 output");
+        self.output_pipe.is_some() && self.output_speed() < self.theoretical_speed()
     }
 
-    pub fn current_output(&self) -> Option<Output> {
-        self.output_pipe.map(|_| Output {
-            speed: self.output_speed(),
-            resource: Resource::Fluid(Fluid::Water),
-        })
+    fn show_body(&mut self, ui: &mut Ui, scale: f32, cx: &mut BodyContext) -> bool {
+        let mut changed = pipe_selector(ui, scale, &mut self.output_pipe, cx.unlocks).changed;
+        ui.add_space(10.0 * scale);
+
+        changed |= add_speed_ui(ui, &mut self.speed, None).changed;
+        if self.output_pipe.is_some() {
+            extraction_ui(ui, self.theoretical_speed(), self.output_speed(), "m^3/min");
+        }
+        changed
     }
 }
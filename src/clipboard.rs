@@ -0,0 +1,228 @@
+//! Copying buildings with the wires between them, to paste them in any tab
+//! or share them as JSON.
+
+use std::collections::HashMap;
+
+use egui::{Pos2, Vec2};
+use egui_snarl::{InPinId, NodeId, OutPinId};
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{NodeGraph, Snarl},
+    history::Command,
+    node::refresh_node,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Clipboard {
+    /// Top left corner of the copied nodes, positions in `snarl` are relative to it.
+    pub origin: Pos2,
+    graph: NodeGraph,
+    snarl: Snarl,
+}
+
+impl Clipboard {
+    /// Copies the `selected` nodes and the wires between them.
+    pub fn copy(graph: &NodeGraph, snarl: &Snarl, selected: &[NodeId]) -> Self {
+        let origin = selected
+            .iter()
+            .map(|id| snarl.get_node_info(*id).unwrap().pos)
+            .reduce(|a, b| a.min(b))
+            .unwrap_or_default();
+
+        let mut copy_graph = NodeGraph::default();
+        let mut copy_snarl = Snarl::new();
+        let mut copied = HashMap::new();
+        for id in selected {
+            let info = snarl.get_node_info(*id).unwrap();
+            let graph_idx = copy_graph.add_node(graph[info.value].clear_clone());
+            let copy_id = copy_snarl.insert_node((info.pos - origin).to_pos2(), graph_idx);
+            copied.insert(info.value, (graph_idx, copy_id));
+        }
+
+        for edge in graph.edge_references() {
+            if let (Some(&(source, source_id)), Some(&(target, target_id))) =
+                (copied.get(&edge.source()), copied.get(&edge.target()))
+            {
+                let details = edge.weight().clone();
+                copy_snarl.connect(
+                    OutPinId {
+                        node: source_id,
+                        output: details.output,
+                    },
+                    InPinId {
+                        node: target_id,
+                        input: details.input,
+                    },
+                );
+                copy_graph.add_edge(source, target, details);
+            }
+        }
+
+        Self {
+            origin,
+            graph: copy_graph,
+            snarl: copy_snarl,
+        }
+    }
+
+    /// Copies the `selected` nodes and removes them, unwiring them from the
+    /// nodes left behind. Returns the removal, to undo it.
+    pub fn cut(graph: &mut NodeGraph, snarl: &mut Snarl, selected: &[NodeId]) -> (Self, Command) {
+        let clipboard = Self::copy(graph, snarl, selected);
+        let mut removal = Command::remove(graph, snarl, selected);
+        removal.apply(graph, snarl);
+        (clipboard, removal)
+    }
+
+    /// Adds the copied nodes with their top left corner at `pos`, returns their
+    /// ids in the order they were added.
+    pub fn paste(&self, graph: &mut NodeGraph, snarl: &mut Snarl, pos: Pos2) -> Vec<NodeId> {
+        let mut pasted = HashMap::new();
+        let mut added = Vec::new();
+        for info in self.snarl.nodes_info() {
+            let graph_idx = graph.add_node(self.graph[info.value].clear_clone());
+            let new_id = snarl.insert_node(pos + info.pos.to_vec2(), graph_idx);
+            pasted.insert(info.value, (graph_idx, new_id));
+            added.push((graph_idx, new_id));
+        }
+
+        for edge in self.graph.edge_references() {
+            let (source, source_id) = pasted[&edge.source()];
+            let (target, target_id) = pasted[&edge.target()];
+            let details = edge.weight().clone();
+            snarl.connect(
+                OutPinId {
+                    node: source_id,
+                    output: details.output,
+                },
+                InPinId {
+                    node: target_id,
+                    input: details.input,
+                },
+            );
+            graph[source].set_current_output_connected(details.output);
+            graph.add_edge(source, target, details);
+        }
+
+        for &(graph_idx, _) in &added {
+            refresh_node(graph, graph_idx);
+        }
+        added.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// Pastes a little below and right of where the nodes were copied from.
+    pub fn paste_offset(&self, graph: &mut NodeGraph, snarl: &mut Snarl) -> Vec<NodeId> {
+        self.paste(graph, snarl, self.origin + Vec2::splat(20.))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// `None` if the text is not something copied from here.
+    pub fn from_json(text: &str) -> Option<Self> {
+        serde_json::from_str(text).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::GraphIdx,
+        buildings::{Building, Splitter},
+        node::{Input, Node},
+        test_util::{add, merger, miner, miner_merger_sink, wire, wire_pins},
+    };
+
+    #[test]
+    fn test_copy_paste() {
+        let mut graph = NodeGraph::default();
+        let mut snarl = Snarl::new();
+        let [miner, merger, _] = miner_merger_sink(&mut graph, &mut snarl);
+
+        // only the wire between the copied nodes comes along
+        let clipboard = Clipboard::copy(&graph, &snarl, &[miner.1, merger.1]);
+        assert_eq!(clipboard.origin, Pos2::new(10., 10.));
+        let clipboard = Clipboard::from_json(&clipboard.to_json()).unwrap();
+        assert!(Clipboard::from_json("hello").is_none());
+
+        let mut other_graph = NodeGraph::default();
+        let mut other_snarl = Snarl::new();
+        let pasted = clipboard.paste(&mut other_graph, &mut other_snarl, Pos2::new(0., 100.));
+        assert_eq!(pasted.len(), 2);
+        assert_eq!(other_graph.edge_count(), 1);
+        assert_eq!(other_snarl.wires().count(), 1);
+
+        let merger_id = pasted
+            .iter()
+            .find(|id| {
+                matches!(
+                    other_graph[other_snarl[**id]],
+                    Node::Building(Building::Merger(_))
+                )
+            })
+            .unwrap();
+        assert_eq!(
+            other_snarl.get_node_info(*merger_id).unwrap().pos,
+            Pos2::new(90., 140.)
+        );
+        let Node::Building(merger) = &other_graph[other_snarl[*merger_id]] else {
+            panic!("not a building");
+        };
+        assert_eq!(merger.current_input(0).unwrap().speed, 60.);
+    }
+
+    fn sink_input(graph: &NodeGraph, sink: GraphIdx) -> Option<Input> {
+        let Node::Building(sink) = &graph[sink] else {
+            panic!("not a building");
+        };
+        sink.current_input(0)
+    }
+
+    #[test]
+    fn test_cut() {
+        let mut graph = NodeGraph::default();
+        let mut snarl = Snarl::new();
+        let [miner, merger, sink] = miner_merger_sink(&mut graph, &mut snarl);
+        assert_eq!(sink_input(&graph, sink.0).unwrap().speed, 60.);
+
+        // the sink no longer gets what the cut merger passed on
+        let (clipboard, _) = Clipboard::cut(&mut graph, &mut snarl, &[miner.1, merger.1]);
+        assert_eq!(graph.node_count(), 1);
+        assert_eq!(graph.edge_count(), 0);
+        assert!(sink_input(&graph, sink.0).is_none());
+
+        clipboard.paste_offset(&mut graph, &mut snarl);
+        assert_eq!(graph.edge_count(), 1);
+    }
+
+    #[test]
+    fn test_cut_frees_upstream_outputs() {
+        let mut graph = NodeGraph::default();
+        let mut snarl = Snarl::new();
+        let miner = add(&mut graph, &mut snarl, miner());
+        let splitter = add(
+            &mut graph,
+            &mut snarl,
+            Building::Splitter(Splitter::default()),
+        );
+        let merger = add(&mut graph, &mut snarl, merger());
+        let sink = add(
+            &mut graph,
+            &mut snarl,
+            Building::AwesomeSink(Default::default()),
+        );
+        wire(&mut graph, &mut snarl, miner, splitter);
+        wire(&mut graph, &mut snarl, splitter, merger);
+        wire_pins(&mut graph, &mut snarl, splitter, 1, sink, 0);
+        assert_eq!(sink_input(&graph, sink.0).unwrap().speed, 30.);
+
+        // the splitter sends everything to the output still wired
+        Clipboard::cut(&mut graph, &mut snarl, &[merger.1]);
+        assert_eq!(graph.edge_count(), 2);
+        assert_eq!(sink_input(&graph, sink.0).unwrap().speed, 60.);
+    }
+}
@@ -0,0 +1,382 @@
+//! Recipe database parsed from the game's `Docs.json`
+//! (`CommunityResources/Docs/en-US.json` in the install directory).
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+/// Where the native app looks for `Docs.json` on startup.
+#[cfg(not(target_arch = "wasm32"))]
+pub const DOCS_PATH: &str = "assets/Docs.json";
+
+/// Fluid amounts are stored in liters, recipes show m^3.
+const LITERS_PER_CUBIC_METER: f32 = 1000.;
+
+/// The database buildings take their recipe rates from, see [`install`].
+static INSTALLED: OnceLock<RecipeDatabase> = OnceLock::new();
+
+/// Makes `db` the source of all recipe rates for the rest of the run. Only
+/// the first database installed is kept.
+pub fn install(db: RecipeDatabase) -> &'static RecipeDatabase {
+    INSTALLED.get_or_init(|| db)
+}
+
+/// The installed database, `None` until [`install`] was called.
+pub fn installed() -> Option<&'static RecipeDatabase> {
+    INSTALLED.get()
+}
+
+#[derive(Debug, Default)]
+pub struct RecipeDatabase {
+    /// Items by class name, e.g. `Desc_IronIngot_C`.
+    pub items: HashMap<String, Item>,
+    /// Display names of the production buildings by class name, e.g. `Build_ConstructorMk1_C`.
+    pub buildings: HashMap<String, String>,
+    /// All recipes made in a production building, sorted by name.
+    pub recipes: Vec<Recipe>,
+    /// Class names of the recipes each schematic unlocks, by schematic class name,
+    /// e.g. `Schematic_Alternate_BoltedFrame_C`.
+    pub schematics: HashMap<String, Vec<String>>,
+    /// Indices into `recipes` by lowercase short name, some names are used
+    /// by recipes of different buildings.
+    by_name: HashMap<String, Vec<usize>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub name: String,
+    pub fluid: bool,
+    pub stack_size: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recipe {
+    pub class_name: String,
+    /// Display name, alternates are prefixed with `Alternate: `.
+    pub name: String,
+    /// Seconds per cycle.
+    pub duration: f32,
+    pub ingredients: Vec<ItemAmount>,
+    pub products: Vec<ItemAmount>,
+    /// Class names of the buildings this recipe can be made in.
+    pub produced_in: Vec<String>,
+    pub alternate: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemAmount {
+    /// Class name of the item.
+    pub item: String,
+    /// Per cycle, fluids in liters.
+    pub amount: f32,
+}
+
+#[derive(Deserialize)]
+struct NativeClass {
+    #[serde(rename = "NativeClass")]
+    native_class: String,
+    #[serde(rename = "Classes")]
+    classes: Vec<HashMap<String, serde_json::Value>>,
+}
+
+impl RecipeDatabase {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &str) -> Result<Self> {
+        let bytes = std::fs::read(path).with_context(|| format!("reading {path}"))?;
+        Self::parse(&bytes)
+    }
+
+    /// Parse the raw file, which the game ships as UTF-16 with a BOM.
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let text = decode(bytes)?;
+        let native_classes: Vec<NativeClass> = serde_json::from_str(&text)?;
+
+        let mut db = RecipeDatabase::default();
+        let mut raw_recipes = Vec::new();
+        for native in &native_classes {
+            for class in &native.classes {
+                let Some(class_name) = field(class, "ClassName") else {
+                    continue;
+                };
+                if native.native_class.ends_with(".FGRecipe'") {
+                    raw_recipes.push((class_name, class));
+                } else if native.native_class.ends_with(".FGSchematic'") {
+                    let recipes = class
+                        .get("mUnlocks")
+                        .and_then(|u| u.as_array())
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|unlock| unlock.get("mRecipes")?.as_str())
+                        .flat_map(parse_class_list)
+                        .collect();
+                    db.schematics.insert(class_name.to_string(), recipes);
+                } else if let (Some(stack_size), Some(form)) =
+                    (field(class, "mStackSize"), field(class, "mForm"))
+                {
+                    let item = Item {
+                        name: field(class, "mDisplayName")
+                            .unwrap_or(class_name)
+                            .to_string(),
+                        fluid: matches!(form, "RF_LIQUID" | "RF_GAS"),
+                        stack_size: parse_stack_size(stack_size),
+                    };
+                    db.items.insert(class_name.to_string(), item);
+                } else if class_name.starts_with("Build_")
+                    && class.contains_key("mManufacturingSpeed")
+                {
+                    let name = field(class, "mDisplayName").unwrap_or(class_name);
+                    db.buildings
+                        .insert(class_name.to_string(), name.to_string());
+                }
+            }
+        }
+
+        for (class_name, class) in raw_recipes {
+            let produced_in: Vec<_> = parse_class_list(field(class, "mProducedIn").unwrap_or(""))
+                .into_iter()
+                .filter(|b| db.buildings.contains_key(b))
+                .collect();
+            if produced_in.is_empty() {
+                // build gun, workbench and equipment recipes
+                continue;
+            }
+
+            let name = field(class, "mDisplayName")
+                .unwrap_or(class_name)
+                .to_string();
+            let duration = field(class, "mManufactoringDuration")
+                .context("missing duration")?
+                .parse()
+                .with_context(|| format!("invalid duration in {class_name}"))?;
+
+            db.recipes.push(Recipe {
+                class_name: class_name.to_string(),
+                // not the class name, MAM recipes like Turbofuel are `Recipe_Alternate_*` too
+                alternate: name.starts_with("Alternate:"),
+                name,
+                duration,
+                ingredients: parse_amounts(field(class, "mIngredients").unwrap_or(""))?,
+                products: parse_amounts(field(class, "mProduct").unwrap_or(""))?,
+                produced_in,
+            });
+        }
+        db.recipes.sort_by(|a, b| a.name.cmp(&b.name));
+        for (i, recipe) in db.recipes.iter().enumerate() {
+            db.by_name
+                .entry(recipe.short_name().to_lowercase())
+                .or_default()
+                .push(i);
+        }
+
+        Ok(db)
+    }
+
+    /// Find a recipe by its display name, ignoring case and the `Alternate: ` prefix.
+    pub fn recipe(&self, name: &str) -> Option<&Recipe> {
+        self.recipes_named(name).next()
+    }
+
+    /// All recipes with the display name, like [`RecipeDatabase::recipe`].
+    pub fn recipes_named<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a Recipe> {
+        self.by_name
+            .get(&name.to_lowercase())
+            .into_iter()
+            .flatten()
+            .map(|i| &self.recipes[*i])
+    }
+
+    /// Recipes unlocked by a schematic, skipping those not made in a production building.
+    pub fn unlocked_by<'a>(&'a self, schematic: &str) -> impl Iterator<Item = &'a Recipe> {
+        self.schematics
+            .get(schematic)
+            .into_iter()
+            .flatten()
+            .filter_map(|class| self.recipes.iter().find(|r| &r.class_name == class))
+    }
+
+    pub fn item_name<'a>(&'a self, class_name: &'a str) -> &'a str {
+        self.items
+            .get(class_name)
+            .map(|i| i.name.as_str())
+            .unwrap_or(class_name)
+    }
+
+    pub fn building_name<'a>(&'a self, class_name: &'a str) -> &'a str {
+        self.buildings
+            .get(class_name)
+            .map(|b| b.as_str())
+            .unwrap_or(class_name)
+    }
+
+    /// Items (m^3 for fluids) of one ingredient or product per cycle.
+    pub fn per_cycle(&self, amount: &ItemAmount) -> f32 {
+        let fluid = self.items.get(&amount.item).is_some_and(|i| i.fluid);
+        if fluid {
+            amount.amount / LITERS_PER_CUBIC_METER
+        } else {
+            amount.amount
+        }
+    }
+
+    /// Items/min (m^3/min for fluids) of one ingredient or product at 100%.
+    pub fn per_minute(&self, recipe: &Recipe, amount: &ItemAmount) -> f32 {
+        self.per_cycle(amount) * 60. / recipe.duration
+    }
+
+    /// The given recipe names that are not in the database.
+    pub fn unknown_recipes(&self, names: impl IntoIterator<Item = String>) -> Vec<String> {
+        names
+            .into_iter()
+            .filter(|n| self.recipe(n).is_none())
+            .collect()
+    }
+}
+
+impl Recipe {
+    pub fn short_name(&self) -> &str {
+        self.name
+            .strip_prefix("Alternate:")
+            .map(|n| n.trim_start())
+            .unwrap_or(&self.name)
+    }
+}
+
+fn field<'a>(class: &'a HashMap<String, serde_json::Value>, key: &str) -> Option<&'a str> {
+    class.get(key).and_then(|v| v.as_str())
+}
+
+fn decode(bytes: &[u8]) -> Result<String> {
+    match bytes {
+        [0xFF, 0xFE, rest @ ..] => {
+            let units: Vec<u16> = rest
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+            Ok(String::from_utf16(&units)?)
+        }
+        [0xEF, 0xBB, 0xBF, rest @ ..] => Ok(std::str::from_utf8(rest)?.to_string()),
+        _ => Ok(std::str::from_utf8(bytes)?.to_string()),
+    }
+}
+
+fn parse_stack_size(s: &str) -> usize {
+    match s {
+        "SS_ONE" => 1,
+        "SS_SMALL" => 50,
+        "SS_MEDIUM" => 100,
+        "SS_BIG" => 200,
+        "SS_HUGE" => 500,
+        "SS_FLUID" => 50_000,
+        _ => 0,
+    }
+}
+
+/// Last path segment of a class reference, e.g. `Desc_IronIngot_C`.
+fn class_name_of(path: &str) -> String {
+    let path = path.trim_matches(|c| matches!(c, '"' | '\'' | '(' | ')' | ' '));
+    path.rsplit('.')
+        .next()
+        .unwrap_or(path)
+        .trim_matches(|c| matches!(c, '"' | '\''))
+        .to_string()
+}
+
+/// Parses `("/Game/.../Build_SmelterMk1.Build_SmelterMk1_C","...")`.
+fn parse_class_list(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(class_name_of)
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+/// Parses `((ItemClass="/Script/...Desc_IronIngot.Desc_IronIngot_C'",Amount=3),(...))`.
+fn parse_amounts(s: &str) -> Result<Vec<ItemAmount>> {
+    let mut amounts = Vec::new();
+    for part in s.split("ItemClass=").skip(1) {
+        let Some((path, rest)) = part.split_once(",Amount=") else {
+            bail!("missing amount in {s}");
+        };
+        let amount = rest.split([')', ',']).next().unwrap_or_default();
+        amounts.push(ItemAmount {
+            item: class_name_of(path),
+            amount: amount
+                .parse()
+                .with_context(|| format!("invalid amount {amount}"))?,
+        });
+    }
+    Ok(amounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> RecipeDatabase {
+        let file = std::fs::read("./tests/docs_sample.json").unwrap();
+        RecipeDatabase::parse(&file).unwrap()
+    }
+
+    #[test]
+    fn test_parse_sample() {
+        let db = sample();
+        assert_eq!(db.items.len(), 152);
+        assert_eq!(db.buildings.len(), 11);
+        // the workbench only recipe is skipped
+        assert!(db.recipe("Portable Miner").is_none());
+
+        let plate = db.recipe("Iron Plate").unwrap();
+        assert!(!plate.alternate);
+        assert_eq!(plate.produced_in, vec!["Build_ConstructorMk1_C"]);
+        assert_eq!(db.building_name(&plate.produced_in[0]), "Constructor");
+        assert_eq!(db.per_minute(plate, &plate.ingredients[0]), 30.);
+        assert_eq!(db.per_minute(plate, &plate.products[0]), 20.);
+        assert_eq!(db.items["Desc_IronPlate_C"].stack_size, 200);
+
+        let leached = db.recipe("Leached Iron Ingot").unwrap();
+        assert!(leached.alternate);
+        assert_eq!(db.item_name(&leached.ingredients[1].item), "Sulfuric Acid");
+        assert_eq!(db.per_minute(leached, &leached.ingredients[1]), 10.);
+
+        let turbofuel = db.recipe("Turbofuel").unwrap();
+        assert_eq!(turbofuel.class_name, "Recipe_Alternate_Turbofuel_C");
+        assert!(!turbofuel.alternate);
+    }
+
+    #[test]
+    fn test_schematics() {
+        let db = sample();
+        assert_eq!(db.schematics.len(), 2);
+        assert_eq!(db.schematics["Schematic_Tutorial1_C"].len(), 2);
+
+        let unlocked: Vec<_> = db
+            .unlocked_by("Schematic_Tutorial1_C")
+            .map(|r| r.name.as_str())
+            .collect();
+        assert_eq!(unlocked, vec!["Iron Plate"]);
+
+        let unlocked: Vec<_> = db
+            .unlocked_by("Schematic_Alternate_LeachedIronIngot_C")
+            .map(|r| r.short_name())
+            .collect();
+        assert_eq!(unlocked, vec!["Leached Iron ingot"]);
+        assert_eq!(db.unlocked_by("Schematic_Unknown_C").count(), 0);
+    }
+
+    #[test]
+    fn test_utf16() {
+        let text = "[]";
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(text.encode_utf16().flat_map(|u| u.to_le_bytes()));
+        let db = RecipeDatabase::parse(&bytes).unwrap();
+        assert!(db.recipes.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_recipes() {
+        let db = sample();
+        let unknown = db.unknown_recipes(["Iron Plate".to_string(), "Iron Plat".to_string()]);
+        assert_eq!(unknown, vec!["Iron Plat".to_string()]);
+    }
+}
@@ -0,0 +1,523 @@
+//! Moving buildings into a [`Node::Group`] while keeping the factory wired up.
+
+use std::collections::{HashMap, HashSet};
+
+use egui::Pos2;
+use egui_snarl::{InPinId, NodeId, OutPinId};
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+
+use crate::{
+    app::{EdgeDetails, GraphIdx, NodeGraph, Snarl},
+    node::{refresh_node, visible, GroupPin, Node},
+};
+
+/// Moves the `selected` nodes into a new group at `pos`.
+///
+/// Wires between selected nodes move into the group, wires crossing the
+/// selection are reconnected to the matching pins of the group.
+pub fn group(graph: &mut NodeGraph, snarl: &mut Snarl, selected: &[NodeId], pos: Pos2) -> NodeId {
+    let mut selected = selected.to_vec();
+    selected.sort();
+
+    let node_ids: HashMap<GraphIdx, NodeId> = snarl
+        .nodes_ids_data()
+        .map(|(id, node)| (node.value, id))
+        .collect();
+
+    let mut sub_graph = NodeGraph::default();
+    let mut sub_snarl = Snarl::new();
+    // old graph index to the index and snarl id inside the group
+    let mut moved = HashMap::new();
+    for id in &selected {
+        let info = snarl.get_node_info(*id).unwrap();
+        let graph_idx = info.value;
+        let new_graph_idx = sub_graph.add_node(graph[graph_idx].clone());
+        let new_id = sub_snarl.insert_node(info.pos, new_graph_idx);
+        moved.insert(graph_idx, (new_graph_idx, new_id));
+    }
+
+    let mut incoming = Vec::new();
+    let mut outgoing = Vec::new();
+    for edge in (&*graph).edge_references() {
+        let details = edge.weight().clone();
+        match (moved.get(&edge.source()), moved.get(&edge.target())) {
+            (Some(&(source, source_id)), Some(&(target, target_id))) => {
+                sub_snarl.connect(
+                    OutPinId {
+                        node: source_id,
+                        output: details.output,
+                    },
+                    InPinId {
+                        node: target_id,
+                        input: details.input,
+                    },
+                );
+                sub_graph.add_edge(source, target, details);
+            }
+            (None, Some(&(target, _))) => incoming.push((edge.source(), target, details)),
+            (Some(&(source, _)), None) => outgoing.push((source, edge.target(), details)),
+            (None, None) => {}
+        }
+    }
+
+    let removed: HashSet<_> = moved.keys().copied().collect();
+    for id in &selected {
+        snarl.remove_node(*id);
+    }
+    for graph_idx in removed {
+        graph.remove_node(graph_idx);
+    }
+
+    let node = Node::new_group(sub_graph, sub_snarl);
+    let (inputs, outputs) = match &node {
+        Node::Group {
+            inputs, outputs, ..
+        } => (inputs.clone(), outputs.clone()),
+        Node::Building(_) => unreachable!("just created a group"),
+    };
+    let group_idx = graph.add_node(node);
+    let group_id = snarl.insert_node(pos, group_idx);
+
+    for (source, inner_target, details) in incoming {
+        let Some(input) = inputs
+            .iter()
+            .position(|p| p.node_idx == inner_target && p.pin == details.input)
+        else {
+            continue;
+        };
+        snarl.connect(
+            OutPinId {
+                node: node_ids[&source],
+                output: details.output,
+            },
+            InPinId {
+                node: group_id,
+                input,
+            },
+        );
+        graph.add_edge(source, group_idx, EdgeDetails { input, ..details });
+        refresh_node(graph, source);
+    }
+
+    for (inner_source, target, details) in outgoing {
+        let Some(output) = outputs
+            .iter()
+            .position(|p| p.node_idx == inner_source && p.pin == details.output)
+        else {
+            continue;
+        };
+        snarl.connect(
+            OutPinId {
+                node: group_id,
+                output,
+            },
+            InPinId {
+                node: node_ids[&target],
+                input: details.input,
+            },
+        );
+        graph.add_edge(group_idx, target, EdgeDetails { output, ..details });
+    }
+    refresh_node(graph, group_idx);
+
+    group_id
+}
+
+/// Puts the nodes of a group back into the graph around where the group was,
+/// wiring them up like they were inside and to the rest of the factory.
+/// Returns their ids in the order they were added.
+pub fn ungroup(graph: &mut NodeGraph, snarl: &mut Snarl, group_id: NodeId) -> Vec<NodeId> {
+    let info = snarl.get_node_info(group_id).unwrap();
+    let (group_idx, pos) = (info.value, info.pos);
+    let Node::Group {
+        graph: inner,
+        snarl: inner_snarl,
+        inputs,
+        outputs,
+        ..
+    } = graph[group_idx].clone()
+    else {
+        return vec![group_id];
+    };
+
+    let node_ids: HashMap<GraphIdx, NodeId> = snarl
+        .nodes_ids_data()
+        .map(|(id, node)| (node.value, id))
+        .collect();
+
+    // keep the layout, with the top left node where the group was
+    let min = inner_snarl
+        .nodes_pos()
+        .map(|(pos, _)| pos)
+        .reduce(|a, b| a.min(b))
+        .unwrap_or(pos);
+    let mut moved = HashMap::new();
+    let mut added = Vec::new();
+    for (inner_pos, inner_idx) in inner_snarl.nodes_pos() {
+        let new_idx = graph.add_node(inner[*inner_idx].clone());
+        let new_id = snarl.insert_node(pos + (inner_pos - min), new_idx);
+        moved.insert(*inner_idx, (new_idx, new_id));
+        added.push(new_id);
+    }
+
+    let mut edges = Vec::new();
+    for edge in (&inner).edge_references() {
+        let (source, _) = moved[&edge.source()];
+        let (target, _) = moved[&edge.target()];
+        edges.push((source, target, edge.weight().clone()));
+    }
+    for edge in graph.edges_directed(group_idx, petgraph::Direction::Incoming) {
+        let pin = visible(&inputs, edge.weight().input);
+        let (target, _) = moved[&pin.node_idx];
+        let details = EdgeDetails {
+            input: pin.pin,
+            ..edge.weight().clone()
+        };
+        edges.push((edge.source(), target, details));
+    }
+    for edge in graph.edges_directed(group_idx, petgraph::Direction::Outgoing) {
+        let pin = visible(&outputs, edge.weight().output);
+        let (source, _) = moved[&pin.node_idx];
+        let details = EdgeDetails {
+            output: pin.pin,
+            ..edge.weight().clone()
+        };
+        edges.push((source, edge.target(), details));
+    }
+
+    snarl.remove_node(group_id);
+    graph.remove_node(group_idx);
+
+    let new_ids: HashMap<GraphIdx, NodeId> = moved.values().copied().collect();
+    let snarl_id = |idx: GraphIdx| new_ids.get(&idx).or(node_ids.get(&idx)).copied().unwrap();
+    for (source, target, details) in &edges {
+        snarl.connect(
+            OutPinId {
+                node: snarl_id(*source),
+                output: details.output,
+            },
+            InPinId {
+                node: snarl_id(*target),
+                input: details.input,
+            },
+        );
+        graph.add_edge(*source, *target, details.clone());
+    }
+    for (source, _, _) in edges {
+        refresh_node(graph, source);
+    }
+
+    added
+}
+
+/// Moves the wires on a group's pins to where the same inner pins are shown
+/// now, after they were renamed, reordered, hidden or the group was edited.
+/// Wires on pins that are gone are dropped.
+pub fn rewire(
+    graph: &mut NodeGraph,
+    snarl: &mut Snarl,
+    group_id: NodeId,
+    old_inputs: &[GroupPin],
+    old_outputs: &[GroupPin],
+) {
+    let group_idx = snarl[group_id];
+    let Node::Group {
+        inputs, outputs, ..
+    } = &graph[group_idx]
+    else {
+        return;
+    };
+    let input_map = pin_map(old_inputs, inputs);
+    let output_map = pin_map(old_outputs, outputs);
+
+    let mut moved = Vec::new();
+    for edge in graph.edges_directed(group_idx, petgraph::Direction::Incoming) {
+        let details = edge.weight();
+        let new_input = input_map.get(details.input).copied().flatten();
+        if new_input != Some(details.input) {
+            moved.push((
+                edge.id(),
+                edge.source(),
+                edge.target(),
+                new_input.map(|input| EdgeDetails {
+                    input,
+                    ..details.clone()
+                }),
+            ));
+        }
+    }
+    for edge in graph.edges_directed(group_idx, petgraph::Direction::Outgoing) {
+        let details = edge.weight();
+        let new_output = output_map.get(details.output).copied().flatten();
+        if new_output != Some(details.output) {
+            moved.push((
+                edge.id(),
+                edge.source(),
+                edge.target(),
+                new_output.map(|output| EdgeDetails {
+                    output,
+                    ..details.clone()
+                }),
+            ));
+        }
+    }
+
+    let node_ids: HashMap<GraphIdx, NodeId> = snarl
+        .nodes_ids_data()
+        .map(|(id, node)| (node.value, id))
+        .collect();
+    let pins = |source, target, details: &EdgeDetails| {
+        (
+            OutPinId {
+                node: node_ids[&source],
+                output: details.output,
+            },
+            InPinId {
+                node: node_ids[&target],
+                input: details.input,
+            },
+        )
+    };
+
+    let mut added = Vec::new();
+    for (edge, source, target, new_details) in moved {
+        let details = graph.remove_edge(edge).unwrap();
+        let (from, to) = pins(source, target, &details);
+        snarl.disconnect(from, to);
+        match new_details {
+            Some(details) => added.push((source, target, details)),
+            None if source == group_idx => {
+                graph[target].clear_current_input(details.input);
+                refresh_node(graph, target);
+            }
+            None => {
+                graph[source].set_current_output_disconnected(details.output);
+                refresh_node(graph, source);
+            }
+        }
+    }
+    for (source, target, details) in added {
+        let (from, to) = pins(source, target, &details);
+        snarl.connect(from, to);
+        graph.add_edge(source, target, details);
+    }
+}
+
+/// Pins shown before that are not shown anymore, their wires are dropped by [`rewire`].
+pub fn lost_pins(old: &[GroupPin], new: &[GroupPin]) -> Vec<usize> {
+    pin_map(old, new)
+        .into_iter()
+        .enumerate()
+        .filter(|(_, pin)| pin.is_none())
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// New position of each pin shown before, `None` if it isn't shown anymore.
+fn pin_map(old: &[GroupPin], new: &[GroupPin]) -> Vec<Option<usize>> {
+    let new: Vec<_> = new.iter().filter(|p| !p.hidden).collect();
+    old.iter()
+        .filter(|p| !p.hidden)
+        .map(|old| new.iter().position(|p| p.is_same(old)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buildings::{Building, Material, MinerLevel};
+    use crate::node::{Input, Resource};
+    use crate::test_util::{add, merger, miner, miner_merger_sink, wire};
+
+    fn current_input(node: &Node, input: usize) -> Option<Input> {
+        let (building, input) = node.input_building(input);
+        building.current_input(input)
+    }
+
+    #[test]
+    fn test_group_keeps_external_wires() {
+        let mut graph = NodeGraph::default();
+        let mut snarl = Snarl::new();
+        let miner = add(&mut graph, &mut snarl, miner());
+        let first = add(&mut graph, &mut snarl, merger());
+        let second = add(&mut graph, &mut snarl, merger());
+        wire(&mut graph, &mut snarl, miner, first);
+
+        let group_id = group(&mut graph, &mut snarl, &[first.1, second.1], Pos2::ZERO);
+        let group_idx = snarl[group_id];
+
+        assert_eq!(graph.node_count(), 2);
+        assert_eq!(snarl.wires().count(), 1);
+        let edge = graph.edges_connecting(miner.0, group_idx).next().unwrap();
+        let input = edge.weight().input;
+        assert_eq!(
+            current_input(&graph[group_idx], input).unwrap().speed,
+            graph[miner.0].current_output(0).unwrap().speed
+        );
+    }
+
+    #[test]
+    fn test_group_moves_internal_wires() {
+        let mut graph = NodeGraph::default();
+        let mut snarl = Snarl::new();
+        let [miner, merger, sink] = miner_merger_sink(&mut graph, &mut snarl);
+
+        let group_id = group(&mut graph, &mut snarl, &[miner.1, merger.1], Pos2::ZERO);
+        let group_idx = snarl[group_id];
+        let Node::Group {
+            graph: inner,
+            snarl: inner_snarl,
+            outputs,
+            ..
+        } = &graph[group_idx]
+        else {
+            panic!("not a group");
+        };
+        assert_eq!(inner.edge_count(), 1);
+        assert_eq!(inner_snarl.wires().count(), 1);
+        // the wired merger input and miner output are not shown
+        assert_eq!(graph[group_idx].inputs(), 2);
+        assert_eq!(graph[group_idx].outputs(), 1);
+
+        let edge = graph.edges_connecting(group_idx, sink.0).next().unwrap();
+        assert_eq!(outputs[edge.weight().output].pin, 0);
+        let ore = current_input(&graph[sink.0], 0).unwrap();
+        assert_eq!(ore.resource, Resource::Material(Material::IronOre));
+        assert_eq!(ore.speed, 60.);
+    }
+
+    #[test]
+    fn test_rewire_pins() {
+        let mut graph = NodeGraph::default();
+        let mut snarl = Snarl::new();
+        let miner = add(&mut graph, &mut snarl, miner());
+        let merger = add(&mut graph, &mut snarl, merger());
+        wire(&mut graph, &mut snarl, miner, merger);
+        let group_id = group(&mut graph, &mut snarl, &[merger.1], Pos2::ZERO);
+        let group_idx = snarl[group_id];
+
+        let reorder = |graph: &mut NodeGraph, snarl: &mut Snarl, f: fn(&mut Vec<GroupPin>)| {
+            let Node::Group {
+                inputs, outputs, ..
+            } = &mut graph[group_idx]
+            else {
+                panic!("not a group");
+            };
+            let (old_inputs, old_outputs) = (inputs.clone(), outputs.clone());
+            f(inputs);
+            rewire(graph, snarl, group_id, &old_inputs, &old_outputs);
+        };
+
+        reorder(&mut graph, &mut snarl, |pins| pins.swap(0, 1));
+        let edge = graph.edges_connecting(miner.0, group_idx).next().unwrap();
+        assert_eq!(edge.weight().input, 1);
+        let wire = snarl.wires().next().unwrap();
+        assert_eq!(wire.1.input, 1);
+        assert_eq!(current_input(&graph[group_idx], 1).unwrap().speed, 60.);
+
+        reorder(&mut graph, &mut snarl, |pins| pins[1].hidden = true);
+        assert_eq!(graph[group_idx].inputs(), 2);
+        assert_eq!(graph.edge_count(), 0);
+        assert_eq!(snarl.wires().count(), 0);
+    }
+
+    #[test]
+    fn test_ungroup() {
+        let mut graph = NodeGraph::default();
+        let mut snarl = Snarl::new();
+        let [miner, merger, sink] = miner_merger_sink(&mut graph, &mut snarl);
+
+        let group_id = group(&mut graph, &mut snarl, &[miner.1, merger.1], Pos2::ZERO);
+        let ids = ungroup(&mut graph, &mut snarl, group_id);
+        assert_eq!(ids.len(), 2);
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.edge_count(), 2);
+        assert_eq!(snarl.wires().count(), 2);
+        assert_eq!(current_input(&graph[sink.0], 0).unwrap().speed, 60.);
+
+        let merger_idx = graph
+            .node_indices()
+            .find(|idx| matches!(graph[*idx], Node::Building(Building::Merger(_))))
+            .unwrap();
+        let edge = graph.edges_connecting(merger_idx, sink.0).next().unwrap();
+        assert_eq!(edge.weight().output, 0);
+    }
+
+    #[test]
+    fn test_nested_groups() {
+        let mut graph = NodeGraph::default();
+        let mut snarl = Snarl::new();
+        let [miner, merger, sink] = miner_merger_sink(&mut graph, &mut snarl);
+
+        let inner = group(&mut graph, &mut snarl, &[merger.1], Pos2::ZERO);
+        let outer = group(&mut graph, &mut snarl, &[inner, sink.1], Pos2::ZERO);
+        let outer_idx = snarl[outer];
+        assert_eq!(graph.node_count(), 2);
+
+        let edge = graph.edges_connecting(miner.0, outer_idx).next().unwrap();
+        let input = edge.weight().input;
+        let (building, _) = graph[outer_idx].input_building(input);
+        assert!(matches!(building, Building::Merger(_)));
+
+        // more ore reaches the sink through both groups
+        if let Node::Building(Building::Miner(m)) = &mut graph[miner.0] {
+            m.level = MinerLevel::Mk2;
+        }
+        refresh_node(&mut graph, miner.0);
+        let Node::Group { graph: outer, .. } = &graph[outer_idx] else {
+            panic!("not a group");
+        };
+        let sink = outer
+            .node_weights()
+            .find_map(|n| match n {
+                Node::Building(b @ Building::AwesomeSink(_)) => Some(b),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(sink.current_input(0).unwrap().speed, 120.);
+    }
+
+    #[test]
+    fn test_group_copies() {
+        let mut graph = NodeGraph::default();
+        let mut snarl = Snarl::new();
+        let [miner, merger, sink] = miner_merger_sink(&mut graph, &mut snarl);
+
+        let group_id = group(&mut graph, &mut snarl, &[merger.1], Pos2::ZERO);
+        let group_idx = snarl[group_id];
+        if let Node::Group { copies, .. } = &mut graph[group_idx] {
+            *copies = 4;
+        }
+        refresh_node(&mut graph, miner.0);
+
+        // each copy gets a quarter, the sink still gets everything
+        let node = &graph[group_idx];
+        assert_eq!(current_input(node, 0).unwrap().speed, 15.);
+        assert_eq!(node.input_copies(0), 4);
+        assert_eq!(node.current_output(0).unwrap().speed, 60.);
+        assert_eq!(current_input(&graph[sink.0], 0).unwrap().speed, 60.);
+        assert_eq!(node.building_count(), 4);
+    }
+
+    #[test]
+    fn test_editing_survives_regroup() {
+        let mut graph = NodeGraph::default();
+        let mut snarl = Snarl::new();
+        let merger = add(&mut graph, &mut snarl, merger());
+        let group_id = group(&mut graph, &mut snarl, &[merger.1], Pos2::ZERO);
+        let node = &mut graph[snarl[group_id]];
+        node.set_editing(Some(7));
+
+        let Node::Group {
+            graph: inner,
+            snarl: inner_snarl,
+            ..
+        } = node.clone()
+        else {
+            panic!("not a group");
+        };
+        assert_eq!(node.regroup(inner, inner_snarl).editing(), Some(7));
+        // a duplicate is not the group being edited
+        assert_eq!(node.clear_clone().editing(), None);
+    }
+}
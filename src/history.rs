@@ -0,0 +1,789 @@
+//! Undo and redo for the edits made in a tab.
+//!
+//! Every entry is a [`Command`] holding the state an edit changed, from
+//! before and after it, so it can be reverted and applied again. What ripples
+//! through the graph from an edit, like the inputs of the nodes downstream,
+//! is not stored but refreshed from the wires.
+//!
+//! Nodes are referred to by their snarl id. The snarl and the graph both
+//! reuse the last freed slot first, so reverting the edits in reverse order
+//! puts removed nodes back under the ids later edits know them by.
+
+use std::collections::{HashMap, HashSet};
+
+use egui::Pos2;
+use egui_snarl::{InPinId, NodeId, OutPinId};
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+
+use crate::{
+    app::{EdgeDetails, GraphIdx, NodeGraph, Snarl},
+    group::{group, rewire, ungroup},
+    node::{refresh_node, GroupPin, Node},
+};
+
+/// Oldest edits are dropped beyond this many.
+const MAX_ENTRIES: usize = 200;
+
+/// Edits of the same kind closer together than this, in seconds, are undone together.
+const MERGE_SECONDS: f64 = 1.;
+
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Entry>,
+    redo: Vec<Entry>,
+}
+
+struct Entry {
+    label: String,
+    command: Command,
+    /// Time of the last step of a continuous edit, like dragging a slider.
+    merge: Option<f64>,
+}
+
+/// A wire between two pins.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wire {
+    pub from: OutPinId,
+    pub to: InPinId,
+    /// Height of the pipe, see [`EdgeDetails::height`].
+    pub height: f32,
+}
+
+/// Nodes with the wires touching them, in the order they are added.
+#[derive(Debug, Clone)]
+pub struct Nodes {
+    nodes: Vec<(NodeId, Pos2, Node)>,
+    wires: Vec<Wire>,
+}
+
+/// Pins shown on a group, inputs and outputs.
+pub type Pins = (Vec<GroupPin>, Vec<GroupPin>);
+
+/// An edit of a tab that can be reverted and applied again.
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)] // at most a few hundred are kept
+pub enum Command {
+    Add(Nodes),
+    Remove(Nodes),
+    /// Nodes moved from the first position to the second.
+    Move(Vec<(NodeId, Pos2, Pos2)>),
+    Connect(Wire),
+    Disconnect(Wire),
+    /// A building edited or a group replaced.
+    SetNode {
+        node: NodeId,
+        before: Node,
+        after: Node,
+    },
+    SetCopies {
+        node: NodeId,
+        before: u32,
+        after: u32,
+    },
+    SetPins {
+        node: NodeId,
+        before: Pins,
+        after: Pins,
+    },
+    SetBlueprint {
+        node: NodeId,
+        before: Option<u64>,
+        after: Option<u64>,
+    },
+    SetHeight {
+        from: OutPinId,
+        to: InPinId,
+        before: f32,
+        after: f32,
+    },
+    /// The `nodes` replaced by the `group` holding them.
+    Group {
+        nodes: Nodes,
+        group: Nodes,
+    },
+    /// The `group` replaced by the `nodes` it held.
+    Ungroup {
+        group: Nodes,
+        nodes: Nodes,
+    },
+    /// Applied in order, reverted in reverse order.
+    Batch(Vec<Command>),
+}
+
+impl Nodes {
+    /// The nodes with the given ids as they are now, in that order.
+    pub fn capture(graph: &NodeGraph, snarl: &Snarl, ids: &[NodeId]) -> Self {
+        let node_ids: HashMap<GraphIdx, NodeId> = snarl
+            .nodes_ids_data()
+            .map(|(id, node)| (node.value, id))
+            .collect();
+        let nodes = ids
+            .iter()
+            .map(|id| {
+                let info = snarl.get_node_info(*id).unwrap();
+                (*id, info.pos, graph[info.value].clone())
+            })
+            .collect();
+        let captured: HashSet<_> = ids.iter().map(|id| snarl[*id]).collect();
+        let wires = graph
+            .edge_references()
+            .filter(|e| captured.contains(&e.source()) || captured.contains(&e.target()))
+            .map(|e| wire(&node_ids, e.source(), e.target(), e.weight()))
+            .collect();
+        Self { nodes, wires }
+    }
+
+    fn ids(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes.iter().map(|(id, _, _)| *id)
+    }
+
+    /// The other end of every wire.
+    fn neighbors(&self) -> Vec<NodeId> {
+        let ids: HashSet<_> = self.ids().collect();
+        self.wires
+            .iter()
+            .flat_map(|w| [w.from.node, w.to.node])
+            .filter(|id| !ids.contains(id))
+            .collect()
+    }
+
+    fn insert(&mut self, graph: &mut NodeGraph, snarl: &mut Snarl) {
+        for (id, pos, node) in &mut self.nodes {
+            let graph_idx = graph.add_node(node.clone());
+            let new_id = snarl.insert_node(*pos, graph_idx);
+            debug_assert_eq!(new_id, *id, "reverted out of order");
+            *id = new_id;
+        }
+        for wire in &self.wires {
+            connect(graph, snarl, wire);
+        }
+        let touched: Vec<_> = self.ids().chain(self.neighbors()).collect();
+        settle(graph, snarl, touched);
+    }
+
+    fn remove(&self, graph: &mut NodeGraph, snarl: &mut Snarl) {
+        for (id, _, _) in self.nodes.iter().rev() {
+            graph.remove_node(snarl.remove_node(*id));
+        }
+        settle(graph, snarl, self.neighbors());
+    }
+}
+
+impl Command {
+    /// Removing the nodes with the given ids and their wires.
+    pub fn remove(graph: &NodeGraph, snarl: &Snarl, ids: &[NodeId]) -> Self {
+        Self::Remove(Nodes::capture(graph, snarl, ids))
+    }
+
+    /// Adding the nodes with the given ids, which were just added.
+    pub fn added(graph: &NodeGraph, snarl: &Snarl, ids: &[NodeId]) -> Self {
+        Self::Add(Nodes::capture(graph, snarl, ids))
+    }
+
+    /// Moves the `selected` nodes into a group with [`group`], returns the edit.
+    pub fn group(graph: &mut NodeGraph, snarl: &mut Snarl, selected: &[NodeId], pos: Pos2) -> Self {
+        // removed by increasing id, so added back the other way around
+        let mut ids = selected.to_vec();
+        ids.sort_by(|a, b| b.cmp(a));
+        let nodes = Nodes::capture(graph, snarl, &ids);
+        let group_id = group(graph, snarl, selected, pos);
+        Self::Group {
+            nodes,
+            group: Nodes::capture(graph, snarl, &[group_id]),
+        }
+    }
+
+    /// Puts the nodes of a group back with [`ungroup`], returns the edit.
+    pub fn ungroup(graph: &mut NodeGraph, snarl: &mut Snarl, group_id: NodeId) -> Self {
+        let group = Nodes::capture(graph, snarl, &[group_id]);
+        let ids = ungroup(graph, snarl, group_id);
+        Self::Ungroup {
+            group,
+            nodes: Nodes::capture(graph, snarl, &ids),
+        }
+    }
+
+    /// Replacing a node with `after`.
+    pub fn set_node(graph: &NodeGraph, snarl: &Snarl, node: NodeId, after: Node) -> Self {
+        Self::SetNode {
+            node,
+            before: graph[snarl[node]].clone(),
+            after,
+        }
+    }
+
+    /// Disconnecting the `dropped` wires, then this.
+    pub fn after_dropping(self, dropped: Vec<Wire>) -> Self {
+        if dropped.is_empty() {
+            return self;
+        }
+        let mut commands: Vec<_> = dropped.into_iter().map(Self::Disconnect).collect();
+        commands.push(self);
+        Self::Batch(commands)
+    }
+
+    pub fn apply(&mut self, graph: &mut NodeGraph, snarl: &mut Snarl) {
+        match self {
+            Self::Add(nodes) => nodes.insert(graph, snarl),
+            Self::Remove(nodes) => nodes.remove(graph, snarl),
+            Self::Move(moves) => {
+                for (id, _, to) in moves {
+                    snarl.get_node_info_mut(*id).unwrap().pos = *to;
+                }
+            }
+            Self::Connect(wire) => {
+                connect(graph, snarl, wire);
+                settle(graph, snarl, [wire.from.node, wire.to.node]);
+            }
+            Self::Disconnect(wire) => disconnect(graph, snarl, wire),
+            Self::SetNode { node, after, .. } => set_node(graph, snarl, *node, after.clone()),
+            Self::SetCopies { node, after, .. } => set_copies(graph, snarl, *node, *after),
+            Self::SetPins { node, after, .. } => set_pins(graph, snarl, *node, after.clone()),
+            Self::SetBlueprint { node, after, .. } => graph[snarl[*node]].set_blueprint(*after),
+            Self::SetHeight {
+                from, to, after, ..
+            } => set_height(graph, snarl, *from, *to, *after),
+            Self::Group { nodes, group } => {
+                nodes.remove(graph, snarl);
+                group.insert(graph, snarl);
+            }
+            Self::Ungroup { group, nodes } => {
+                nodes.insert(graph, snarl);
+                group.remove(graph, snarl);
+            }
+            Self::Batch(commands) => {
+                for command in commands {
+                    command.apply(graph, snarl);
+                }
+            }
+        }
+    }
+
+    pub fn revert(&mut self, graph: &mut NodeGraph, snarl: &mut Snarl) {
+        match self {
+            Self::Add(nodes) => nodes.remove(graph, snarl),
+            Self::Remove(nodes) => nodes.insert(graph, snarl),
+            Self::Move(moves) => {
+                for (id, from, _) in moves {
+                    snarl.get_node_info_mut(*id).unwrap().pos = *from;
+                }
+            }
+            Self::Connect(wire) => disconnect(graph, snarl, wire),
+            Self::Disconnect(wire) => {
+                connect(graph, snarl, wire);
+                settle(graph, snarl, [wire.from.node, wire.to.node]);
+            }
+            Self::SetNode { node, before, .. } => set_node(graph, snarl, *node, before.clone()),
+            Self::SetCopies { node, before, .. } => set_copies(graph, snarl, *node, *before),
+            Self::SetPins { node, before, .. } => set_pins(graph, snarl, *node, before.clone()),
+            Self::SetBlueprint { node, before, .. } => graph[snarl[*node]].set_blueprint(*before),
+            Self::SetHeight {
+                from, to, before, ..
+            } => set_height(graph, snarl, *from, *to, *before),
+            Self::Group { nodes, group } => {
+                group.remove(graph, snarl);
+                nodes.insert(graph, snarl);
+            }
+            Self::Ungroup { group, nodes } => {
+                group.insert(graph, snarl);
+                nodes.remove(graph, snarl);
+            }
+            Self::Batch(commands) => {
+                for command in commands.iter_mut().rev() {
+                    command.revert(graph, snarl);
+                }
+            }
+        }
+    }
+
+    /// Takes the end state of `next` if it continues this edit, returns
+    /// false if it doesn't.
+    fn merge(&mut self, next: &Command) -> bool {
+        match (self, next) {
+            (Self::Move(moves), Self::Move(next)) => {
+                for &(id, from, to) in next {
+                    match moves.iter_mut().find(|(moved, _, _)| *moved == id) {
+                        Some((_, _, last)) => *last = to,
+                        None => moves.push((id, from, to)),
+                    }
+                }
+                true
+            }
+            (
+                Self::SetNode { node, after, .. },
+                Self::SetNode {
+                    node: next_node,
+                    after: next_after,
+                    ..
+                },
+            ) if node == next_node => {
+                *after = next_after.clone();
+                true
+            }
+            (
+                Self::SetCopies { node, after, .. },
+                Self::SetCopies {
+                    node: next_node,
+                    after: next_after,
+                    ..
+                },
+            ) if node == next_node => {
+                *after = *next_after;
+                true
+            }
+            (
+                Self::SetHeight {
+                    from, to, after, ..
+                },
+                Self::SetHeight {
+                    from: next_from,
+                    to: next_to,
+                    after: next_after,
+                    ..
+                },
+            ) if from == next_from && to == next_to => {
+                *after = *next_after;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The wires on the given pins of a node.
+pub fn wires_at(
+    graph: &NodeGraph,
+    snarl: &Snarl,
+    node: NodeId,
+    inputs: &[usize],
+    outputs: &[usize],
+) -> Vec<Wire> {
+    let node_ids: HashMap<GraphIdx, NodeId> = snarl
+        .nodes_ids_data()
+        .map(|(id, node)| (node.value, id))
+        .collect();
+    let graph_idx = snarl[node];
+    let incoming = graph
+        .edges_directed(graph_idx, petgraph::Direction::Incoming)
+        .filter(|e| inputs.contains(&e.weight().input));
+    let outgoing = graph
+        .edges_directed(graph_idx, petgraph::Direction::Outgoing)
+        .filter(|e| outputs.contains(&e.weight().output));
+    incoming
+        .chain(outgoing)
+        .map(|e| wire(&node_ids, e.source(), e.target(), e.weight()))
+        .collect()
+}
+
+fn wire(
+    node_ids: &HashMap<GraphIdx, NodeId>,
+    source: GraphIdx,
+    target: GraphIdx,
+    details: &EdgeDetails,
+) -> Wire {
+    Wire {
+        from: OutPinId {
+            node: node_ids[&source],
+            output: details.output,
+        },
+        to: InPinId {
+            node: node_ids[&target],
+            input: details.input,
+        },
+        height: details.height,
+    }
+}
+
+/// Adds the wire, leaving the cached values to [`settle`].
+fn connect(graph: &mut NodeGraph, snarl: &mut Snarl, wire: &Wire) {
+    graph.add_edge(
+        snarl[wire.from.node],
+        snarl[wire.to.node],
+        EdgeDetails {
+            input: wire.to.input,
+            output: wire.from.output,
+            height: wire.height,
+        },
+    );
+    snarl.connect(wire.from, wire.to);
+}
+
+fn disconnect(graph: &mut NodeGraph, snarl: &mut Snarl, wire: &Wire) {
+    if let Some(edge) = find_edge(graph, snarl, wire.from, wire.to) {
+        graph.remove_edge(edge);
+    }
+    snarl.disconnect(wire.from, wire.to);
+    settle(graph, snarl, [wire.from.node, wire.to.node]);
+}
+
+fn find_edge(
+    graph: &NodeGraph,
+    snarl: &Snarl,
+    from: OutPinId,
+    to: InPinId,
+) -> Option<petgraph::stable_graph::EdgeIndex> {
+    graph
+        .edges_connecting(snarl[from.node], snarl[to.node])
+        .find(|e| e.weight().output == from.output && e.weight().input == to.input)
+        .map(|e| e.id())
+}
+
+fn set_node(graph: &mut NodeGraph, snarl: &mut Snarl, node: NodeId, new: Node) {
+    let graph_idx = snarl[node];
+    let old = std::mem::replace(&mut graph[graph_idx], new);
+    if let Node::Group {
+        inputs, outputs, ..
+    } = old
+    {
+        rewire(graph, snarl, node, &inputs, &outputs);
+    }
+    settle(graph, snarl, [node]);
+}
+
+fn set_copies(graph: &mut NodeGraph, snarl: &Snarl, node: NodeId, new: u32) {
+    if let Node::Group { copies, .. } = &mut graph[snarl[node]] {
+        *copies = new;
+    }
+    settle(graph, snarl, [node]);
+}
+
+fn set_pins(
+    graph: &mut NodeGraph,
+    snarl: &mut Snarl,
+    node: NodeId,
+    (new_inputs, new_outputs): Pins,
+) {
+    let Node::Group {
+        inputs, outputs, ..
+    } = &mut graph[snarl[node]]
+    else {
+        return;
+    };
+    let old_inputs = std::mem::replace(inputs, new_inputs);
+    let old_outputs = std::mem::replace(outputs, new_outputs);
+    rewire(graph, snarl, node, &old_inputs, &old_outputs);
+    settle(graph, snarl, [node]);
+}
+
+fn set_height(graph: &mut NodeGraph, snarl: &Snarl, from: OutPinId, to: InPinId, height: f32) {
+    if let Some(edge) = find_edge(graph, snarl, from, to) {
+        graph[edge].height = height;
+    }
+}
+
+/// Brings the cached values of the `nodes` still in the graph in line with
+/// their wires, and passes on what they make and take to the nodes around.
+fn settle(graph: &mut NodeGraph, snarl: &Snarl, nodes: impl IntoIterator<Item = NodeId>) {
+    let mut refresh = Vec::new();
+    for graph_idx in nodes
+        .into_iter()
+        .filter_map(|id| snarl.get_node(id).copied())
+    {
+        let wired_inputs: HashSet<_> = graph
+            .edges_directed(graph_idx, petgraph::Direction::Incoming)
+            .map(|e| e.weight().input)
+            .collect();
+        let wired_outputs: HashSet<_> = graph
+            .edges_directed(graph_idx, petgraph::Direction::Outgoing)
+            .map(|e| e.weight().output)
+            .collect();
+        refresh.extend(graph.neighbors_directed(graph_idx, petgraph::Direction::Incoming));
+        refresh.push(graph_idx);
+
+        let node = &mut graph[graph_idx];
+        for input in (0..node.inputs()).filter(|i| !wired_inputs.contains(i)) {
+            node.clear_current_input(input);
+        }
+        for output in 0..node.outputs() {
+            if wired_outputs.contains(&output) {
+                node.set_current_output_connected(output);
+            } else {
+                node.set_current_output_disconnected(output);
+            }
+        }
+    }
+    refresh.sort();
+    refresh.dedup();
+    for graph_idx in refresh {
+        refresh_node(graph, graph_idx);
+    }
+}
+
+impl History {
+    /// Saves an edit that was already made.
+    pub fn push(&mut self, label: impl Into<String>, command: Command) {
+        self.push_entry(label.into(), command, None);
+    }
+
+    /// Like [`History::push`], but an edit continuing the last one is merged into it.
+    pub fn push_merged(&mut self, label: &str, time: f64, command: Command) {
+        if self.redo.is_empty() {
+            if let Some(last) = self.undo.last_mut() {
+                if let Some(last_time) = &mut last.merge {
+                    if last.label == label
+                        && time - *last_time < MERGE_SECONDS
+                        && last.command.merge(&command)
+                    {
+                        *last_time = time;
+                        return;
+                    }
+                }
+            }
+        }
+        self.push_entry(label.to_string(), command, Some(time));
+    }
+
+    /// Makes an edit and saves it.
+    pub fn apply(
+        &mut self,
+        label: impl Into<String>,
+        mut command: Command,
+        graph: &mut NodeGraph,
+        snarl: &mut Snarl,
+    ) {
+        command.apply(graph, snarl);
+        self.push(label, command);
+    }
+
+    fn push_entry(&mut self, label: String, command: Command, merge: Option<f64>) {
+        self.redo.clear();
+        self.undo.push(Entry {
+            label,
+            command,
+            merge,
+        });
+        let excess = self.undo.len().saturating_sub(MAX_ENTRIES);
+        self.undo.drain(..excess);
+    }
+
+    /// Reverts the last edit, returns false if there is none.
+    pub fn undo(&mut self, graph: &mut NodeGraph, snarl: &mut Snarl) -> bool {
+        let Some(mut entry) = self.undo.pop() else {
+            return false;
+        };
+        entry.command.revert(graph, snarl);
+        entry.merge = None;
+        self.redo.push(entry);
+        true
+    }
+
+    /// Applies the last reverted edit again, returns false if there is none.
+    pub fn redo(&mut self, graph: &mut NodeGraph, snarl: &mut Snarl) -> bool {
+        let Some(mut entry) = self.redo.pop() else {
+            return false;
+        };
+        entry.command.apply(graph, snarl);
+        self.undo.push(entry);
+        true
+    }
+
+    /// Edits that can be undone, oldest first.
+    pub fn undo_labels(&self) -> impl Iterator<Item = &str> {
+        self.undo.iter().map(|e| e.label.as_str())
+    }
+
+    /// Edits that can be redone, next one first.
+    pub fn redo_labels(&self) -> impl Iterator<Item = &str> {
+        self.redo.iter().rev().map(|e| e.label.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buildings::{Building, MinerLevel};
+    use crate::test_util::{add, merger, miner_merger_sink};
+
+    fn sink_speed(graph: &NodeGraph, snarl: &Snarl, sink: NodeId) -> Option<f32> {
+        let (building, input) = graph[snarl[sink]].input_building(0);
+        building.current_input(input).map(|input| input.speed)
+    }
+
+    fn add_merger(graph: &mut NodeGraph, snarl: &mut Snarl, history: &mut History) -> NodeId {
+        let (_, id) = add(graph, snarl, merger());
+        history.push("Add", Command::added(graph, snarl, &[id]));
+        id
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut graph = NodeGraph::default();
+        let mut snarl = Snarl::new();
+        let mut history = History::default();
+        assert!(!history.undo(&mut graph, &mut snarl));
+
+        add_merger(&mut graph, &mut snarl, &mut history);
+        let second = add_merger(&mut graph, &mut snarl, &mut history);
+        assert_eq!(history.undo_labels().count(), 2);
+
+        assert!(history.undo(&mut graph, &mut snarl));
+        assert_eq!(graph.node_count(), 1);
+        assert_eq!(snarl.nodes().count(), 1);
+        assert!(history.undo(&mut graph, &mut snarl));
+        assert_eq!(graph.node_count(), 0);
+        assert!(!history.undo(&mut graph, &mut snarl));
+
+        assert!(history.redo(&mut graph, &mut snarl));
+        assert!(history.redo(&mut graph, &mut snarl));
+        assert_eq!(graph.node_count(), 2);
+        // back under the same id
+        assert!(snarl.get_node(second).is_some());
+
+        history.undo(&mut graph, &mut snarl);
+        assert_eq!(history.redo_labels().collect::<Vec<_>>(), vec!["Add"]);
+        // a new edit drops what was undone
+        add_merger(&mut graph, &mut snarl, &mut history);
+        assert!(!history.redo(&mut graph, &mut snarl));
+    }
+
+    #[test]
+    fn test_remove_restores_wires() {
+        let mut graph = NodeGraph::default();
+        let mut snarl = Snarl::new();
+        let [miner, merger, sink] = miner_merger_sink(&mut graph, &mut snarl);
+        let mut history = History::default();
+
+        let command = Command::remove(&graph, &snarl, &[miner.1, merger.1]);
+        history.apply("Cut", command, &mut graph, &mut snarl);
+        assert_eq!(graph.node_count(), 1);
+        assert!(sink_speed(&graph, &snarl, sink.1).is_none());
+
+        history.undo(&mut graph, &mut snarl);
+        assert_eq!(graph.edge_count(), 2);
+        assert_eq!(snarl.wires().count(), 2);
+        assert_eq!(sink_speed(&graph, &snarl, sink.1), Some(60.));
+        let pos = snarl.get_node_info(merger.1).unwrap().pos;
+        assert_eq!(pos, Pos2::new(100., 50.));
+
+        history.redo(&mut graph, &mut snarl);
+        assert_eq!(graph.edge_count(), 0);
+        assert!(sink_speed(&graph, &snarl, sink.1).is_none());
+    }
+
+    #[test]
+    fn test_connect_and_set_node() {
+        let mut graph = NodeGraph::default();
+        let mut snarl = Snarl::new();
+        let [miner, merger, sink] = miner_merger_sink(&mut graph, &mut snarl);
+        let mut history = History::default();
+
+        let wire = Wire {
+            from: OutPinId {
+                node: merger.1,
+                output: 0,
+            },
+            to: InPinId {
+                node: sink.1,
+                input: 0,
+            },
+            height: 0.,
+        };
+        history.apply(
+            "Disconnect",
+            Command::Disconnect(wire),
+            &mut graph,
+            &mut snarl,
+        );
+        assert!(sink_speed(&graph, &snarl, sink.1).is_none());
+        history.undo(&mut graph, &mut snarl);
+        assert_eq!(sink_speed(&graph, &snarl, sink.1), Some(60.));
+
+        let Node::Building(Building::Miner(mut faster)) = graph[miner.0].clone() else {
+            panic!("not a miner");
+        };
+        faster.level = MinerLevel::Mk2;
+        let after = Node::Building(Building::Miner(faster));
+        let command = Command::set_node(&graph, &snarl, miner.1, after);
+        history.apply("Edit Miner", command, &mut graph, &mut snarl);
+        assert_eq!(sink_speed(&graph, &snarl, sink.1), Some(120.));
+        history.undo(&mut graph, &mut snarl);
+        assert_eq!(sink_speed(&graph, &snarl, sink.1), Some(60.));
+    }
+
+    #[test]
+    fn test_group_ungroup() {
+        let mut graph = NodeGraph::default();
+        let mut snarl = Snarl::new();
+        let [miner, merger, sink] = miner_merger_sink(&mut graph, &mut snarl);
+        let mut history = History::default();
+
+        let command = Command::group(&mut graph, &mut snarl, &[miner.1, merger.1], Pos2::ZERO);
+        history.push("Group", command);
+        let group_id = snarl.node_ids().map(|(id, _)| id).find(|id| *id != sink.1);
+        let group_id = group_id.unwrap();
+        history.push(
+            "Ungroup",
+            Command::ungroup(&mut graph, &mut snarl, group_id),
+        );
+        assert_eq!(graph.node_count(), 3);
+
+        history.undo(&mut graph, &mut snarl);
+        assert_eq!(graph.node_count(), 2);
+        assert!(matches!(graph[snarl[group_id]], Node::Group { .. }));
+        assert_eq!(sink_speed(&graph, &snarl, sink.1), Some(60.));
+
+        history.undo(&mut graph, &mut snarl);
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.edge_count(), 2);
+        assert!(matches!(
+            graph[snarl[miner.1]],
+            Node::Building(Building::Miner(_))
+        ));
+        assert_eq!(sink_speed(&graph, &snarl, sink.1), Some(60.));
+
+        history.redo(&mut graph, &mut snarl);
+        history.redo(&mut graph, &mut snarl);
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(sink_speed(&graph, &snarl, sink.1), Some(60.));
+    }
+
+    #[test]
+    fn test_merged_edits() {
+        let mut graph = NodeGraph::default();
+        let mut snarl = Snarl::new();
+        let [miner, merger, _] = miner_merger_sink(&mut graph, &mut snarl);
+        let mut history = History::default();
+
+        let height = |before, after| Command::SetHeight {
+            from: OutPinId {
+                node: miner.1,
+                output: 0,
+            },
+            to: InPinId {
+                node: merger.1,
+                input: 0,
+            },
+            before,
+            after,
+        };
+        history.push_merged("Edit Height", 0., height(0., 1.));
+        history.push_merged("Edit Height", 0.5, height(1., 2.));
+        history.push_merged("Edit Height", 1.2, height(2., 3.));
+        assert_eq!(history.undo_labels().count(), 1);
+
+        history.push_merged("Edit Height", 3., height(3., 4.));
+        let moved = Command::Move(vec![(miner.1, Pos2::new(10., 10.), Pos2::ZERO)]);
+        history.push_merged("Move", 3.1, moved);
+        assert_eq!(history.undo_labels().count(), 3);
+
+        // undoing the first edit goes back to before all of its steps
+        for _ in 0..3 {
+            history.undo(&mut graph, &mut snarl);
+        }
+        let edge = graph.edges_connecting(miner.0, merger.0).next().unwrap();
+        assert_eq!(edge.weight().height, 0.);
+    }
+
+    #[test]
+    fn test_max_entries() {
+        let mut graph = NodeGraph::default();
+        let mut snarl = Snarl::new();
+        let mut history = History::default();
+        for _ in 0..MAX_ENTRIES + 5 {
+            add_merger(&mut graph, &mut snarl, &mut history);
+        }
+        assert_eq!(history.undo_labels().count(), MAX_ENTRIES);
+        while history.undo(&mut graph, &mut snarl) {}
+        assert_eq!(graph.node_count(), 5);
+    }
+}
@@ -0,0 +1,167 @@
+//! Groups saved under a name, to be placed again as blueprints in any tab.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{NodeGraph, Snarl},
+    group::rewire,
+    node::{refresh_node, Node},
+};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Library {
+    pub blueprints: Vec<Blueprint>,
+    next_id: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Blueprint {
+    /// Stays the same when the blueprint is renamed, instances link to it.
+    pub id: u64,
+    pub name: String,
+    /// Always a [`Node::Group`].
+    pub group: Node,
+}
+
+impl Library {
+    /// Saves a copy of `group` and returns the id to link it with.
+    pub fn add(&mut self, name: String, group: &Node) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        let mut group = group.clone();
+        group.set_blueprint(Some(id));
+        self.blueprints.push(Blueprint { id, name, group });
+        id
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Blueprint> {
+        self.blueprints.iter().find(|b| b.id == id)
+    }
+
+    pub fn remove(&mut self, id: u64) {
+        self.blueprints.retain(|b| b.id != id);
+    }
+
+    /// Replaces the definition of a blueprint, returns false if it was removed.
+    pub fn update(&mut self, id: u64, group: &Node) -> bool {
+        match self.blueprints.iter_mut().find(|b| b.id == id) {
+            Some(blueprint) => {
+                blueprint.group = group.clone();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Blueprint {
+    /// A new group linked to this blueprint.
+    pub fn instance(&self) -> Node {
+        self.group.clear_clone()
+    }
+}
+
+/// Rebuilds every group linked to `blueprint` in `graph`, also inside other
+/// groups, keeping their wires and pin settings. Returns whether any changed.
+pub fn update_instances(graph: &mut NodeGraph, snarl: &mut Snarl, blueprint: &Blueprint) -> bool {
+    let Node::Group {
+        graph: definition,
+        snarl: definition_snarl,
+        ..
+    } = &blueprint.group
+    else {
+        return false;
+    };
+
+    let mut changed = false;
+    let node_ids: Vec<_> = snarl.node_ids().map(|(id, idx)| (id, *idx)).collect();
+    for (node_id, graph_idx) in node_ids {
+        let Node::Group {
+            graph: inner,
+            snarl: inner_snarl,
+            inputs,
+            outputs,
+            blueprint: link,
+            ..
+        } = &mut graph[graph_idx]
+        else {
+            continue;
+        };
+        let (old_inputs, old_outputs) = (inputs.clone(), outputs.clone());
+        let (new_graph, new_snarl) = if *link == Some(blueprint.id) {
+            (definition.clone(), definition_snarl.clone())
+        } else if update_instances(inner, inner_snarl, blueprint) {
+            // the pins of the instances inside may have changed
+            (inner.clone(), inner_snarl.clone())
+        } else {
+            continue;
+        };
+        changed = true;
+
+        let node = &mut graph[graph_idx];
+        *node = node.regroup(new_graph, new_snarl);
+        rewire(graph, snarl, node_id, &old_inputs, &old_outputs);
+
+        let sources: Vec<_> = graph
+            .neighbors_directed(graph_idx, petgraph::Direction::Incoming)
+            .collect();
+        for source in sources {
+            refresh_node(graph, source);
+        }
+        refresh_node(graph, graph_idx);
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::Pos2;
+
+    use super::*;
+    use crate::buildings::{Building, Merger, Splitter};
+    use crate::group::group;
+
+    fn add(graph: &mut NodeGraph, snarl: &mut Snarl, building: Building) -> egui_snarl::NodeId {
+        let idx = graph.add_node(Node::Building(building));
+        snarl.insert_node(Pos2::ZERO, idx)
+    }
+
+    #[test]
+    fn test_update_instances() {
+        let mut graph = NodeGraph::default();
+        let mut snarl = Snarl::new();
+        let merger = add(&mut graph, &mut snarl, Building::Merger(Merger::default()));
+        let group_id = group(&mut graph, &mut snarl, &[merger], Pos2::ZERO);
+
+        let mut library = Library::default();
+        let id = library.add("Merge".to_string(), &graph[snarl[group_id]]);
+        graph[snarl[group_id]].set_blueprint(Some(id));
+        let blueprint = library.get(id).unwrap();
+        assert_eq!(blueprint.name, "Merge");
+
+        // a second instance, inside another group
+        let instance = graph.add_node(blueprint.instance());
+        let instance_id = snarl.insert_node(Pos2::ZERO, instance);
+        let outer = group(&mut graph, &mut snarl, &[instance_id], Pos2::ZERO);
+
+        // the definition now splits instead
+        let mut definition_graph = NodeGraph::default();
+        let mut definition_snarl = Snarl::new();
+        add(
+            &mut definition_graph,
+            &mut definition_snarl,
+            Building::Splitter(Splitter::default()),
+        );
+        let definition = graph[snarl[group_id]].regroup(definition_graph, definition_snarl);
+        assert!(library.update(id, &definition));
+        update_instances(&mut graph, &mut snarl, library.get(id).unwrap());
+
+        assert_eq!(graph[snarl[group_id]].inputs(), 1);
+        assert_eq!(graph[snarl[group_id]].outputs(), 3);
+        assert_eq!(graph[snarl[outer]].inputs(), 1);
+        assert_eq!(graph[snarl[outer]].outputs(), 3);
+
+        library.remove(id);
+        assert!(!library.update(id, &definition));
+    }
+}
@@ -1,9 +1,19 @@
 mod app;
 mod buildings;
+mod clipboard;
+mod docs;
+mod group;
+mod history;
+mod library;
 mod node;
+mod palette;
 pub mod savegame;
+#[cfg(test)]
+mod test_util;
+mod unlocks;
 mod util;
 mod viewer;
+mod widgets;
 
 use app::App;
 
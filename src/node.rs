@@ -1,29 +1,220 @@
 use crate::{
-    app::{NodeGraph, Snarl},
-    buildings::{Building, Fluid, Material, Selectable},
+    app::{EdgeDetails, GraphIdx, NodeGraph, Snarl},
+    buildings::{round, Building, Fluid, Material, Metadata, Selectable},
 };
 use egui::Color32;
 use petgraph::prelude::NodeIndex;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[allow(clippy::large_enum_variant)] // groups are few, boxing them isn't worth it
 pub enum Node {
     Building(Building),
     Group {
         snarl: Snarl,
         graph: NodeGraph,
-        inputs: Vec<(egui_snarl::NodeId, NodeIndex, usize, Option<Input>)>,
-        outputs: Vec<(egui_snarl::NodeId, NodeIndex, usize, Option<Output>)>,
+        /// Pins of the inner nodes that are not wired up inside the group.
+        inputs: Vec<GroupPin>,
+        outputs: Vec<GroupPin>,
+        /// Library blueprint this group is an instance of, updated along with it.
+        #[serde(default)]
+        blueprint: Option<u64>,
+        /// Identical copies built of the group, scaling everything going in and out.
+        #[serde(default = "one_copy")]
+        copies: u32,
+        /// Id of the tab the group is open in for editing.
+        #[serde(default)]
+        editing: Option<usize>,
     },
 }
 
+fn one_copy() -> u32 {
+    1
+}
+
+/// An input or output of a node inside a group, shown on the group node.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "GroupPinRepr")]
+pub struct GroupPin {
+    pub node_id: egui_snarl::NodeId,
+    pub node_idx: NodeIndex,
+    /// Input or output number on the inner node.
+    pub pin: usize,
+    /// Shown on the group node, instead of just the resource.
+    pub name: Option<String>,
+    pub hidden: bool,
+}
+
+impl GroupPin {
+    pub fn new(node_id: egui_snarl::NodeId, node_idx: NodeIndex, pin: usize) -> Self {
+        Self {
+            node_id,
+            node_idx,
+            pin,
+            name: None,
+            hidden: false,
+        }
+    }
+
+    /// Same inner node and pin, regardless of name and visibility.
+    pub fn is_same(&self, other: &GroupPin) -> bool {
+        self.node_idx == other.node_idx && self.pin == other.pin
+    }
+}
+
+/// Groups used to be saved with a tuple per pin.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GroupPinRepr {
+    Pin {
+        node_id: egui_snarl::NodeId,
+        node_idx: NodeIndex,
+        pin: usize,
+        #[serde(default)]
+        name: Option<String>,
+        #[serde(default)]
+        hidden: bool,
+    },
+    Tuple(egui_snarl::NodeId, NodeIndex, usize, serde::de::IgnoredAny),
+}
+
+impl From<GroupPinRepr> for GroupPin {
+    fn from(value: GroupPinRepr) -> Self {
+        match value {
+            GroupPinRepr::Pin {
+                node_id,
+                node_idx,
+                pin,
+                name,
+                hidden,
+            } => Self {
+                node_id,
+                node_idx,
+                pin,
+                name,
+                hidden,
+            },
+            GroupPinRepr::Tuple(node_id, node_idx, pin, _) => Self::new(node_id, node_idx, pin),
+        }
+    }
+}
+
+/// The `id`th pin shown on the group node.
+pub fn visible(pins: &[GroupPin], id: usize) -> &GroupPin {
+    pins.iter()
+        .filter(|p| !p.hidden)
+        .nth(id)
+        .expect("group pin out of range")
+}
+
 impl Node {
+    /// A group of the nodes in `snarl`, exposing the pins not wired up between them.
+    pub fn new_group(graph: NodeGraph, snarl: Snarl) -> Self {
+        let mut inputs = Vec::new();
+        let mut outputs = Vec::new();
+        for (id, node) in snarl.nodes_ids_data() {
+            let graph_idx = node.value;
+            let node = graph.node_weight(graph_idx).unwrap();
+
+            let wired = |direction, pin: usize| {
+                graph.edges_directed(graph_idx, direction).any(|e| {
+                    let weight: &EdgeDetails = e.weight();
+                    match direction {
+                        petgraph::Direction::Incoming => weight.input == pin,
+                        petgraph::Direction::Outgoing => weight.output == pin,
+                    }
+                })
+            };
+            for i in 0..node.outputs() {
+                if !wired(petgraph::Direction::Outgoing, i) {
+                    outputs.push(GroupPin::new(id, graph_idx, i));
+                }
+            }
+            for i in 0..node.inputs() {
+                if !wired(petgraph::Direction::Incoming, i) {
+                    inputs.push(GroupPin::new(id, graph_idx, i));
+                }
+            }
+        }
+        Self::Group {
+            snarl,
+            graph,
+            inputs,
+            outputs,
+            blueprint: None,
+            copies: 1,
+            editing: None,
+        }
+    }
+
+    /// Like [`Node::new_group`], but keeping the names, order and visibility
+    /// of the pins the group already had.
+    pub fn regroup(&self, graph: NodeGraph, snarl: Snarl) -> Self {
+        let mut new = Self::new_group(graph, snarl);
+        if let (
+            Self::Group {
+                inputs: old_inputs,
+                outputs: old_outputs,
+                blueprint: old_blueprint,
+                copies: old_copies,
+                editing: old_editing,
+                ..
+            },
+            Self::Group {
+                inputs,
+                outputs,
+                blueprint,
+                copies,
+                editing,
+                ..
+            },
+        ) = (self, &mut new)
+        {
+            keep_pin_settings(old_inputs, inputs);
+            keep_pin_settings(old_outputs, outputs);
+            *blueprint = *old_blueprint;
+            *copies = *old_copies;
+            *editing = *old_editing;
+        }
+        new
+    }
+
+    pub fn blueprint(&self) -> Option<u64> {
+        match self {
+            Self::Group { blueprint, .. } => *blueprint,
+            Self::Building(_) => None,
+        }
+    }
+
+    /// Links or unlinks a group from a library blueprint.
+    pub fn set_blueprint(&mut self, id: Option<u64>) {
+        if let Self::Group { blueprint, .. } = self {
+            *blueprint = id;
+        }
+    }
+
+    pub fn editing(&self) -> Option<usize> {
+        match self {
+            Self::Group { editing, .. } => *editing,
+            Self::Building(_) => None,
+        }
+    }
+
+    /// Marks a group as open for editing in the tab with this id.
+    pub fn set_editing(&mut self, tab_id: Option<usize>) {
+        if let Self::Group { editing, .. } = self {
+            *editing = tab_id;
+        }
+    }
+
     /// Clone, but with caches reset
     pub fn clear_clone(&self) -> Self {
         match self {
             Self::Group { .. } => {
-                // TODO
-                self.clone()
+                // TODO: clear the inner caches
+                let mut this = self.clone();
+                this.set_editing(None);
+                this
             }
             Self::Building(b) => Self::Building(b.clear_clone()),
         }
@@ -32,7 +223,34 @@ impl Node {
     pub fn name(&self) -> String {
         match self {
             Self::Building(b) => b.name(),
-            Self::Group { snarl, .. } => format!("Group ({})", snarl.nodes().count()),
+            Self::Group { snarl, copies, .. } => match copies {
+                1 => format!("Group ({})", snarl.nodes().count()),
+                n => format!("Group ({}) x {}", snarl.nodes().count(), n),
+            },
+        }
+    }
+
+    /// Buildings needed, counting every copy of nested groups.
+    pub fn building_count(&self) -> u32 {
+        match self {
+            Self::Group { graph, copies, .. } => {
+                copies
+                    * graph
+                        .node_weights()
+                        .map(|n| n.building_count())
+                        .sum::<u32>()
+            }
+            Self::Building(_) => 1,
+        }
+    }
+
+    /// MW generated, or used when negative, by all copies.
+    pub fn power(&self) -> f32 {
+        match self {
+            Self::Group { graph, copies, .. } => {
+                *copies as f32 * graph.node_weights().map(|n| n.power()).sum::<f32>()
+            }
+            Self::Building(b) => b.power(),
         }
     }
 
@@ -46,21 +264,103 @@ impl Node {
     pub fn inputs(&self) -> usize {
         match self {
             Self::Building(b) => b.inputs(),
-            Self::Group { inputs, .. } => inputs.len(),
+            Self::Group { inputs, .. } => inputs.iter().filter(|p| !p.hidden).count(),
         }
     }
 
     pub fn outputs(&self) -> usize {
         match self {
             Self::Building(b) => b.outputs(),
-            Self::Group { outputs, .. } => outputs.len(),
+            Self::Group { outputs, .. } => outputs.iter().filter(|p| !p.hidden).count(),
+        }
+    }
+
+    /// Name given to a group's input.
+    pub fn input_name(&self, input_id: usize) -> Option<&str> {
+        match self {
+            Self::Group { inputs, .. } => visible(inputs, input_id).name.as_deref(),
+            Self::Building(_) => None,
+        }
+    }
+
+    /// Name given to a group's output.
+    pub fn output_name(&self, output_id: usize) -> Option<&str> {
+        match self {
+            Self::Group { outputs, .. } => visible(outputs, output_id).name.as_deref(),
+            Self::Building(_) => None,
+        }
+    }
+
+    /// The building behind an input, looking through nested groups.
+    pub fn input_building(&self, input_id: usize) -> (&Building, usize) {
+        match self {
+            Self::Group { inputs, graph, .. } => {
+                let GroupPin {
+                    node_idx,
+                    pin: inner_input_id,
+                    ..
+                } = visible(inputs, input_id);
+                graph[*node_idx].input_building(*inner_input_id)
+            }
+            Self::Building(b) => (b, input_id),
+        }
+    }
+
+    /// How many times the rates of [`Node::input_building`] are multiplied on this input.
+    pub fn input_copies(&self, input_id: usize) -> u32 {
+        match self {
+            Self::Group {
+                inputs,
+                graph,
+                copies,
+                ..
+            } => {
+                let pin = visible(inputs, input_id);
+                copies * graph[pin.node_idx].input_copies(pin.pin)
+            }
+            Self::Building(_) => 1,
+        }
+    }
+
+    /// The building behind an output, looking through nested groups.
+    pub fn output_building(&self, output_id: usize) -> (&Building, usize) {
+        match self {
+            Self::Group { outputs, graph, .. } => {
+                let GroupPin {
+                    node_idx,
+                    pin: inner_output_id,
+                    ..
+                } = visible(outputs, output_id);
+                graph[*node_idx].output_building(*inner_output_id)
+            }
+            Self::Building(b) => (b, output_id),
+        }
+    }
+
+    /// How many times the rates of [`Node::output_building`] are multiplied on this output.
+    pub fn output_copies(&self, output_id: usize) -> u32 {
+        match self {
+            Self::Group {
+                outputs,
+                graph,
+                copies,
+                ..
+            } => {
+                let pin = visible(outputs, output_id);
+                copies * graph[pin.node_idx].output_copies(pin.pin)
+            }
+            Self::Building(_) => 1,
         }
     }
 
     pub fn input_resource(&self, input_id: usize) -> ResourceType {
         match self {
             Self::Group { inputs, graph, .. } => {
-                let (_, node_idx, inner_input_id, _) = &inputs[input_id];
+                let GroupPin {
+                    node_idx,
+                    pin: inner_input_id,
+                    ..
+                } = visible(inputs, input_id);
                 let node = graph.node_weight(*node_idx).unwrap();
                 node.input_resource(*inner_input_id)
             }
@@ -71,7 +371,11 @@ impl Node {
     pub fn output_resource(&self, output_id: usize) -> ResourceType {
         match self {
             Self::Group { outputs, graph, .. } => {
-                let (_, node_idx, inner_output_id, _) = &outputs[output_id];
+                let GroupPin {
+                    node_idx,
+                    pin: inner_output_id,
+                    ..
+                } = visible(outputs, output_id);
                 let node = graph.node_weight(*node_idx).unwrap();
                 node.output_resource(*inner_output_id)
             }
@@ -81,32 +385,48 @@ impl Node {
 
     pub fn current_output(&self, output_id: usize) -> Option<Output> {
         match self {
-            Self::Group { outputs, graph, .. } => {
-                let (_, node_idx, inner_output_id, _) = &outputs[output_id];
+            Self::Group {
+                outputs,
+                graph,
+                copies,
+                ..
+            } => {
+                let GroupPin {
+                    node_idx,
+                    pin: inner_output_id,
+                    ..
+                } = visible(outputs, output_id);
                 let node = graph.node_weight(*node_idx).unwrap();
-                node.current_output(*inner_output_id)
+                node.current_output(*inner_output_id).map(|output| Output {
+                    speed: round(output.speed * *copies as f32),
+                    resource: output.resource,
+                })
             }
             Self::Building(b) => b.current_output(output_id),
         }
     }
 
-    pub fn current_input(&self, input_id: usize) -> Option<Input> {
-        match self {
-            Self::Group { inputs, graph, .. } => {
-                let (_, node_idx, inner_input_id, _) = &inputs[input_id];
-                let node = graph.node_weight(*node_idx).unwrap();
-                node.current_input(*inner_input_id)
-            }
-            Self::Building(b) => b.current_input(input_id),
-        }
-    }
-
     pub fn set_current_input(&mut self, input: Output, input_id: usize) {
         match self {
-            Self::Group { inputs, graph, .. } => {
-                let (_, node_idx, inner_input_id, _) = &inputs[input_id];
+            Self::Group {
+                inputs,
+                graph,
+                copies,
+                ..
+            } => {
+                let GroupPin {
+                    node_idx,
+                    pin: inner_input_id,
+                    ..
+                } = visible(inputs, input_id);
+                // split evenly between the copies
+                let input = Output {
+                    speed: round(input.speed / *copies as f32),
+                    resource: input.resource,
+                };
                 let node = graph.node_weight_mut(*node_idx).unwrap();
                 node.set_current_input(input, *inner_input_id);
+                refresh_node(graph, *node_idx);
             }
             Self::Building(b) => b.set_current_input(input, input_id),
         }
@@ -115,9 +435,14 @@ impl Node {
     pub fn clear_current_input(&mut self, input_id: usize) {
         match self {
             Self::Group { inputs, graph, .. } => {
-                let (_, node_idx, inner_input_id, _) = &inputs[input_id];
+                let GroupPin {
+                    node_idx,
+                    pin: inner_input_id,
+                    ..
+                } = visible(inputs, input_id);
                 let node = graph.node_weight_mut(*node_idx).unwrap();
                 node.clear_current_input(*inner_input_id);
+                refresh_node(graph, *node_idx);
             }
             Self::Building(b) => b.clear_current_input(input_id),
         }
@@ -126,7 +451,11 @@ impl Node {
     pub fn set_current_output_connected(&mut self, output_id: usize) {
         match self {
             Self::Group { outputs, graph, .. } => {
-                let (_, node_idx, inner_output_id, _) = &outputs[output_id];
+                let GroupPin {
+                    node_idx,
+                    pin: inner_output_id,
+                    ..
+                } = visible(outputs, output_id);
                 let node = graph.node_weight_mut(*node_idx).unwrap();
                 node.set_current_output_connected(*inner_output_id);
             }
@@ -137,7 +466,11 @@ impl Node {
     pub fn set_current_output_disconnected(&mut self, output_id: usize) {
         match self {
             Self::Group { outputs, graph, .. } => {
-                let (_, node_idx, inner_output_id, _) = &outputs[output_id];
+                let GroupPin {
+                    node_idx,
+                    pin: inner_output_id,
+                    ..
+                } = visible(outputs, output_id);
                 let node = graph.node_weight_mut(*node_idx).unwrap();
                 node.set_current_output_disconnected(*inner_output_id);
             }
@@ -145,6 +478,28 @@ impl Node {
         }
     }
 
+    pub fn head_lift(&self, output_id: usize) -> f32 {
+        match self {
+            Self::Group { outputs, graph, .. } => {
+                let GroupPin {
+                    node_idx,
+                    pin: inner_output_id,
+                    ..
+                } = visible(outputs, output_id);
+                let node = graph.node_weight(*node_idx).unwrap();
+                node.head_lift(*inner_output_id)
+            }
+            Self::Building(b) => b.head_lift(output_id),
+        }
+    }
+
+    pub fn is_fluid_passthrough(&self) -> bool {
+        match self {
+            Self::Group { .. } => false,
+            Self::Building(b) => b.is_fluid_passthrough(),
+        }
+    }
+
     pub fn description(&self) -> String {
         match self {
             Self::Group { .. } => "Group".to_string(),
@@ -153,6 +508,82 @@ impl Node {
     }
 }
 
+/// Orders `pins` like `old`, with new pins at the end, and copies their settings.
+fn keep_pin_settings(old: &[GroupPin], pins: &mut Vec<GroupPin>) {
+    let mut kept = Vec::with_capacity(pins.len());
+    for old_pin in old {
+        if let Some(i) = pins.iter().position(|p| p.is_same(old_pin)) {
+            let mut pin = pins.remove(i);
+            pin.name = old_pin.name.clone();
+            pin.hidden = old_pin.hidden;
+            kept.push(pin);
+        }
+    }
+    kept.append(pins);
+    *pins = kept;
+}
+
+/// Pushes the outputs of `node_idx` downstream, along every path to the end of the graph.
+pub fn refresh_node(graph: &mut NodeGraph, node_idx: GraphIdx) {
+    // Find all paths
+    fn all_paths_dfs(
+        graph: &NodeGraph,
+        start: NodeIndex,
+        end: NodeIndex,
+        path: &mut Vec<NodeIndex>,
+        all_paths: &mut Vec<Vec<NodeIndex>>,
+    ) {
+        path.push(start); // Add the current node to the path
+        if start == end {
+            // If the current node is the destination
+            all_paths.push(path.clone()); // Add a copy of the path to the results
+        } else {
+            // Explore neighbors
+            for neighbor in graph.neighbors_directed(start, petgraph::Direction::Outgoing) {
+                let count = path.iter().filter(|n| *n == &neighbor).count();
+                // This number is..special
+                // A tradeoff between accuracy and calculation time.
+                const MAX_COUNT: usize = 5;
+                if count < MAX_COUNT {
+                    all_paths_dfs(graph, neighbor, end, path, all_paths);
+                }
+            }
+        }
+        // Backtrack to explore other paths
+        path.pop();
+    }
+
+    let externals: Vec<_> = graph.externals(petgraph::Direction::Outgoing).collect();
+    for target in externals {
+        let mut path = Vec::new();
+        let mut paths = Vec::new();
+        all_paths_dfs(graph, node_idx, target, &mut path, &mut paths);
+        for path in paths {
+            let mut start_idx = path[0];
+            for next_node_idx in path.into_iter().skip(1) {
+                let edges: Vec<_> = graph
+                    .edges_connecting(start_idx, next_node_idx)
+                    .map(|e| e.weight().clone())
+                    .collect();
+                for edge in edges {
+                    let output = graph
+                        .node_weight(start_idx)
+                        .unwrap()
+                        .current_output(edge.output);
+                    let node = graph.node_weight_mut(next_node_idx).unwrap();
+                    if let Some(output) = output {
+                        node.set_current_input(output, edge.input);
+                    } else {
+                        node.clear_current_input(edge.input);
+                    }
+                }
+                // step forward
+                start_idx = next_node_idx;
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Output {
     pub speed: f32,
@@ -207,10 +638,29 @@ impl Resource {
         }
     }
 
+    pub fn class_name(&self) -> &'static str {
+        match self {
+            Self::Material(m) => m.class_name(),
+            Self::Fluid(f) => f.class_name(),
+        }
+    }
+
     pub fn image(&self) -> String {
         match self {
             Self::Material(m) => m.image(),
             Self::Fluid(f) => f.image(),
         }
     }
+
+    pub fn metadata(&self) -> Metadata {
+        match self {
+            Self::Material(m) => m.metadata(),
+            Self::Fluid(f) => f.metadata(),
+        }
+    }
+
+    /// Radiation of a belt carrying this at `speed` items/min, `None` if it isn't radioactive.
+    pub fn radiation(&self, speed: f32) -> Option<f32> {
+        self.metadata().radioactivity.map(|r| r * speed)
+    }
 }
@@ -0,0 +1,114 @@
+//! Finding buildings to add by typing what they are or what they make.
+
+use std::sync::OnceLock;
+
+use egui::{Key, Pos2, Ui};
+
+use crate::{
+    buildings::{presets, Building},
+    unlocks::Unlocks,
+};
+
+/// Results shown at most.
+const MAX_RESULTS: usize = 20;
+
+/// The floating search opened with Ctrl+Space or by typing over a graph.
+pub struct Palette {
+    /// Id of the tab to add the building to.
+    pub tab: usize,
+    /// Screen position it was opened at, the building is placed there.
+    pub pos: Pos2,
+    pub query: String,
+}
+
+struct Preset {
+    building: Building,
+    /// Name of the recipe or resource, to check that it is unlocked.
+    picked: Option<String>,
+    /// Lowercase name and products, to match the query against.
+    text: String,
+}
+
+fn all_presets() -> &'static [Preset] {
+    static PRESETS: OnceLock<Vec<Preset>> = OnceLock::new();
+    PRESETS.get_or_init(|| {
+        let plain = Building::all().into_iter().map(|b| (b, None));
+        let picked = presets().into_iter().map(|(b, name)| (b, Some(name)));
+        plain
+            .chain(picked)
+            .map(|(building, picked)| {
+                let machine = building.machine();
+                let products = (0..machine.num_outputs())
+                    .filter_map(|i| machine.output_spec(i)?.resource)
+                    .map(|r| r.name());
+                let text = std::iter::once(building.name())
+                    .chain(products)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .to_lowercase();
+                Preset {
+                    building,
+                    picked,
+                    text,
+                }
+            })
+            .collect()
+    })
+}
+
+/// Buildings whose name or products contain every word of `query`, unlocked ones only.
+pub fn search<'a>(
+    query: &str,
+    unlocks: &'a Unlocks,
+) -> impl Iterator<Item = &'static Building> + 'a {
+    let words: Vec<_> = query.split_whitespace().map(str::to_lowercase).collect();
+    all_presets()
+        .iter()
+        .filter(move |p| !words.is_empty() && words.iter().all(|w| p.text.contains(w)))
+        .filter(|p| unlocks.missing(&p.building).is_none())
+        .filter(|p| p.picked.as_ref().is_none_or(|n| unlocks.is_unlocked(n)))
+        .map(|p| &p.building)
+        .take(MAX_RESULTS)
+}
+
+/// A search box with the matching buildings, returns the one picked.
+/// Enter picks the first.
+pub fn search_ui(ui: &mut Ui, query: &mut String, unlocks: &Unlocks) -> Option<Building> {
+    let response = ui.add(egui::TextEdit::singleline(query).hint_text("Search buildings"));
+    response.request_focus();
+    let enter = ui.input(|i| i.key_pressed(Key::Enter));
+
+    let mut picked = None;
+    for (i, building) in search(query, unlocks).enumerate() {
+        if ui.button(building.name()).clicked() || (i == 0 && enter) {
+            picked = Some(building.clear_clone());
+        }
+    }
+    picked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(query: &str, unlocks: &Unlocks) -> Vec<String> {
+        search(query, unlocks).map(|b| b.name()).collect()
+    }
+
+    #[test]
+    fn test_search() {
+        let unlocks = Unlocks::all();
+        assert!(names("motor", &unlocks).contains(&"Assembler (Motor)".to_string()));
+        assert!(names("MERGER", &unlocks).contains(&"Merger".to_string()));
+        assert!(names("", &unlocks).is_empty());
+
+        // by product, every word has to match
+        let plates = names("iron plate", &unlocks);
+        assert!(plates.contains(&"Constructor (Iron Plate)".to_string()));
+        assert!(names("iron zzz", &unlocks).is_empty());
+
+        assert!(names("miner iron", &unlocks)
+            .iter()
+            .any(|n| n.starts_with("Miner")));
+    }
+}
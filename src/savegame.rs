@@ -743,6 +743,42 @@ impl<'a> SaveFileBody<'a> {
     }
 }
 
+/// Class names of the schematics purchased in a save, e.g. `Schematic_Alternate_BoltedFrame_C`.
+pub fn purchased_schematics(file: &[u8]) -> Result<Vec<String>> {
+    let save_game = Savegame::parse(file)?;
+    let body = save_game.decompress()?;
+    schematics_in_body(&body)
+}
+
+/// The body parser does not handle every object yet, so this looks for the
+/// `mPurchasedSchematics` property of the schematic manager directly.
+fn schematics_in_body(body: &[u8]) -> Result<Vec<String>> {
+    let name = b"mPurchasedSchematics\0";
+    let mut needle = (name.len() as i32).to_le_bytes().to_vec();
+    needle.extend_from_slice(name);
+
+    let start = body
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .ok_or_else(|| anyhow::anyhow!("no purchased schematics found"))?;
+    let mut parser = Parser::new(&body[start..]);
+    let Property::Array(ArrayProperty {
+        data: ArrayPropertyElements::Object(refs),
+        ..
+    }) = Property::parse(&mut parser)?
+    else {
+        anyhow::bail!("unexpected purchased schematics property");
+    };
+
+    Ok(refs
+        .iter()
+        .map(|r| {
+            let path = r.path_name.as_str();
+            path.rsplit('.').next().unwrap_or(path).to_string()
+        })
+        .collect())
+}
+
 #[derive(derive_more::Debug)]
 pub struct CompressedBodyChunk<'a> {
     pub compressed_size: I32,
@@ -978,4 +1014,13 @@ mod tests {
 
         assert!(false);
     }
+
+    #[test]
+    fn test_purchased_schematics() {
+        let file = std::fs::read("./tests/simple_save_1_0_0.sav").unwrap();
+        let schematics = purchased_schematics(&file).unwrap();
+        assert_eq!(schematics.len(), 78);
+        assert_eq!(schematics[0], "Schematic_StartingRecipes_C");
+        assert!(schematics.contains(&"Schematic_Alternate_BoltedFrame_C".to_string()));
+    }
 }
@@ -0,0 +1,83 @@
+//! Graphs shared by the tests of several modules.
+
+use egui::Pos2;
+use egui_snarl::{InPinId, NodeId, OutPinId};
+
+use crate::{
+    app::{EdgeDetails, GraphIdx, NodeGraph, Snarl},
+    buildings::{Building, Merger, Miner, ResourceType},
+    node::{refresh_node, Node},
+};
+
+pub fn add(graph: &mut NodeGraph, snarl: &mut Snarl, building: Building) -> (GraphIdx, NodeId) {
+    add_at(graph, snarl, building, Pos2::ZERO)
+}
+
+pub fn add_at(
+    graph: &mut NodeGraph,
+    snarl: &mut Snarl,
+    building: Building,
+    pos: Pos2,
+) -> (GraphIdx, NodeId) {
+    let idx = graph.add_node(Node::Building(building));
+    let id = snarl.insert_node(pos, idx);
+    (idx, id)
+}
+
+/// Wires the first output of `from` to the first input of `to`.
+pub fn wire(
+    graph: &mut NodeGraph,
+    snarl: &mut Snarl,
+    from: (GraphIdx, NodeId),
+    to: (GraphIdx, NodeId),
+) {
+    wire_pins(graph, snarl, from, 0, to, 0);
+}
+
+/// Wires `output` of `from` to `input` of `to`, the way connecting them in the graph does.
+pub fn wire_pins(
+    graph: &mut NodeGraph,
+    snarl: &mut Snarl,
+    from: (GraphIdx, NodeId),
+    output: usize,
+    to: (GraphIdx, NodeId),
+    input: usize,
+) {
+    let details = EdgeDetails {
+        input,
+        output,
+        height: 0.,
+    };
+    graph.add_edge(from.0, to.0, details);
+    snarl.connect(
+        OutPinId {
+            node: from.1,
+            output,
+        },
+        InPinId { node: to.1, input },
+    );
+    graph[from.0].set_current_output_connected(output);
+    refresh_node(graph, from.0);
+}
+
+/// A miner wired to a merger wired to a sink, left to right.
+pub fn miner_merger_sink(graph: &mut NodeGraph, snarl: &mut Snarl) -> [(GraphIdx, NodeId); 3] {
+    let miner = add_at(graph, snarl, miner(), Pos2::new(10., 10.));
+    let merger = add_at(graph, snarl, merger(), Pos2::new(100., 50.));
+    let sink = Building::AwesomeSink(Default::default());
+    let sink = add_at(graph, snarl, sink, Pos2::new(200., 50.));
+    wire(graph, snarl, miner, merger);
+    wire(graph, snarl, merger, sink);
+    [miner, merger, sink]
+}
+
+pub fn miner() -> Building {
+    Building::Miner(Miner {
+        resource: Some(ResourceType::IronOre),
+        ..Default::default()
+    })
+}
+
+pub fn merger() -> Building {
+    Building::Merger(Merger::default())
+}
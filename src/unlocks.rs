@@ -0,0 +1,400 @@
+//! The progression tier and alternate recipes a project has unlocked, so pickers
+//! only offer what the team actually has.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::buildings::Selectable;
+use crate::docs::RecipeDatabase;
+
+/// Hand-coded recipes that are unlocked with hard drives, by building.
+pub const ALTERNATE_RECIPES: &[&str] = &[
+    // Smelter
+    "Pure Aluminum Ingot",
+    // Constructor
+    "Aluminum Beam",
+    "Aluminum Rod",
+    "Biocoal",
+    "Cast Screw",
+    "Caterium Wire",
+    "Charcoal",
+    "Iron Pipe",
+    "Iron Wire",
+    "Steel Canister",
+    "Steel Rod",
+    "Steel Screw",
+    // Assembler
+    "Adhered Iron Plate",
+    "Alclad Casing",
+    "Automated Miner",
+    "Bolted Frame",
+    "Bolted Iron Plate",
+    "Caterium Circuit Board",
+    "Cheap Silica",
+    "Coated Iron Canister",
+    "Coated Iron Plate",
+    "Compacted Coal",
+    "Copper Rotor",
+    "Crystal Computer",
+    "Electric Motor",
+    "Electrode Circuit Board",
+    "Electromagnetic Connection Rod",
+    "Encased Industrial Pipe",
+    "Fine Black Powder",
+    "Fine Concrete",
+    "Fused Quickwire",
+    "Fused Wire",
+    "Heat Exchanger",
+    "Insulated Cable",
+    "OC Supercomputer",
+    "Plastic AI Limiter",
+    "Plutonium Fuel Unit",
+    "Quickwire Cable",
+    "Quickwire Stator",
+    "Rubber Concrete",
+    "Silicon Circuit Board",
+    "Steel Rotor",
+    "Steeled Frame",
+    "Stitched Iron Plate",
+    // Manufacturer
+    "Automated Speed Wiring",
+    "Caterium Computer",
+    "Classic Battery",
+    "Flexible Framework",
+    "Heavy Encased Frame",
+    "Heavy Flexible Frame",
+    "Infused Uranium Cell",
+    "Insulated Crystal Oscillator",
+    "Plastic Smart Plating",
+    "Radio Connection Unit",
+    "Radio Control System",
+    "Rigor Motor",
+    "Silicon High-Speed Connector",
+    "Super-State Computer",
+    "Turbo Electric Motor",
+    "Turbo Pressure Motor",
+    "Uranium Fuel Unit",
+    // Foundry
+    "Basic Iron Ingot",
+    "Coke Steel Ingot",
+    "Compacted Steel Ingot",
+    "Copper Alloy Ingot",
+    "Fused Quartz Crystal",
+    "Iron Alloy Ingot",
+    "Molded Beam",
+    "Molded Steel Pipe",
+    "Solid Steel Ingot",
+    "Steel Cast Plate",
+    "Tempered Caterium Ingot",
+    "Tempered Copper Ingot",
+    // Refinery
+    "Coated Cable",
+    "Diluted Packaged Fuel",
+    "Electrode Aluminum Scrap",
+    "Heavy Oil Residue",
+    "Leached Caterium Ingot",
+    "Leached Copper Ingot",
+    "Leached Iron Ingot",
+    "Polyester Fabric",
+    "Polymer Resin",
+    "Pure Caterium Ingot",
+    "Pure Copper Ingot",
+    "Pure Iron Ingot",
+    "Pure Quartz Crystal",
+    "Quartz Purification",
+    "Recycled Plastic",
+    "Recycled Rubber",
+    "Sloppy Alumina",
+    "Steamed Copper Sheet",
+    "Turbo Heavy Fuel",
+    "Wet Concrete",
+    // Blender
+    "Cooling Device",
+    "Diluted Fuel",
+    "Distilled Silica",
+    "Fertile Uranium",
+    "Heat-Fused Frame",
+    "Instant Scrap",
+    "Nitro Rocket Fuel",
+    "Turbo Blend Fuel",
+    // Particle Accelerator
+    "Cloudy Diamonds",
+    "Dark Matter Crystallization",
+    "Dark Matter Trap",
+    "Instant Plutonium Cell",
+    "Oil-Based Diamonds",
+    "Petroleum Diamonds",
+    "Turbo Diamonds",
+    // Converter
+    "Dark-Ion Fuel",
+    "Pink Diamonds",
+];
+
+pub fn is_alternate(name: &str) -> bool {
+    ALTERNATE_RECIPES.contains(&name)
+}
+
+/// The last tier of the HUB milestones.
+pub const MAX_TIER: u8 = 9;
+
+/// What makes a building or upgrade available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unlock {
+    /// A HUB milestone, tier 0 is the onboarding.
+    Milestone { tier: u8, name: &'static str },
+    /// MAM research, counted at the tier from which it can be researched.
+    Research { tier: u8, name: &'static str },
+}
+
+impl Unlock {
+    pub const ONBOARDING: Self = Self::Milestone {
+        tier: 0,
+        name: "Onboarding",
+    };
+
+    pub const fn milestone(tier: u8, name: &'static str) -> Self {
+        Self::Milestone { tier, name }
+    }
+
+    pub fn tier(&self) -> u8 {
+        match self {
+            Self::Milestone { tier, .. } | Self::Research { tier, .. } => *tier,
+        }
+    }
+}
+
+impl std::fmt::Display for Unlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Milestone { tier: 0, name } => write!(f, "{name}"),
+            Self::Milestone { tier, name } => write!(f, "Tier {tier}: {name}"),
+            Self::Research { name, .. } => write!(f, "MAM: {name}"),
+        }
+    }
+}
+
+/// Buildings and upgrades that only become available during the game.
+pub trait Gated {
+    fn unlocked_by(&self) -> Unlock;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Unlocks {
+    /// Highest HUB tier with a purchased milestone.
+    #[serde(default = "max_tier")]
+    pub tier: u8,
+    /// Names of the unlocked alternate recipes.
+    #[serde(deserialize_with = "deserialize_alternates")]
+    pub alternates: BTreeSet<String>,
+}
+
+/// Alternates saved under an older name, as `(old, new)`.
+const RENAMED_ALTERNATES: &[(&str, &str)] = &[(
+    "Dark Crystallization Matter ",
+    "Dark Matter Crystallization",
+)];
+
+/// Reads the alternates, accepting the names in [`RENAMED_ALTERNATES`] as aliases.
+fn deserialize_alternates<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeSet<String>, D::Error> {
+    let names = BTreeSet::<String>::deserialize(deserializer)?;
+    Ok(names
+        .into_iter()
+        .map(|name| {
+            RENAMED_ALTERNATES
+                .iter()
+                .find(|(old, _)| *old == name)
+                .map(|(_, new)| new.to_string())
+                .unwrap_or(name)
+        })
+        .collect())
+}
+
+fn max_tier() -> u8 {
+    MAX_TIER
+}
+
+impl Default for Unlocks {
+    /// Everything is unlocked until the profile is edited.
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl Unlocks {
+    pub fn all() -> Self {
+        Self {
+            tier: MAX_TIER,
+            alternates: ALTERNATE_RECIPES.iter().map(|n| n.to_string()).collect(),
+        }
+    }
+
+    pub fn is_unlocked(&self, name: &str) -> bool {
+        !is_alternate(name) || self.alternates.contains(name)
+    }
+
+    pub fn allows<S: Selectable>(&self, recipe: &S) -> bool {
+        self.is_unlocked(&recipe.name())
+    }
+
+    pub fn is_available(&self, unlock: Unlock) -> bool {
+        unlock.tier() <= self.tier
+    }
+
+    /// The unlock this still needs, if it is not available yet.
+    pub fn missing<G: Gated + ?Sized>(&self, gated: &G) -> Option<Unlock> {
+        let unlock = gated.unlocked_by();
+        (!self.is_available(unlock)).then_some(unlock)
+    }
+
+    pub fn set_unlocked(&mut self, name: &str, unlocked: bool) {
+        if unlocked {
+            self.alternates.insert(name.to_string());
+        } else {
+            self.alternates.remove(name);
+        }
+    }
+
+    /// Replace the tier and alternates with those unlocked by the given schematics, e.g.
+    /// from [`crate::savegame::purchased_schematics`]. Returns how many alternates were found.
+    ///
+    /// Uses the schematics in the recipe database when loaded, and otherwise matches
+    /// `Schematic_Alternate_BoltedFrame_C` to `Bolted Frame`.
+    pub fn import_schematics(
+        &mut self,
+        schematics: &[String],
+        db: Option<&RecipeDatabase>,
+    ) -> usize {
+        let mut alternates = BTreeSet::new();
+        let mut tier = 0;
+        for schematic in schematics {
+            if let Some(milestone) = milestone_tier(schematic) {
+                tier = tier.max(milestone);
+            }
+            if let Some(db) = db {
+                for recipe in db.unlocked_by(schematic) {
+                    if let Some(name) = find_alternate(recipe.short_name()) {
+                        alternates.insert(name.to_string());
+                    }
+                }
+            }
+            let name = schematic
+                .strip_prefix("Schematic_Alternate_")
+                .and_then(|n| n.strip_suffix("_C"));
+            if let Some(name) = name.and_then(find_alternate) {
+                alternates.insert(name.to_string());
+            }
+        }
+
+        let count = alternates.len();
+        self.tier = tier;
+        self.alternates = alternates;
+        count
+    }
+}
+
+/// The tier of a HUB milestone, e.g. `Schematic_5-3_C`.
+fn milestone_tier(schematic: &str) -> Option<u8> {
+    let (tier, _) = schematic.strip_prefix("Schematic_")?.split_once('-')?;
+    tier.parse().ok()
+}
+
+/// The hand-coded alternate with this name, ignoring case, spaces and punctuation.
+fn find_alternate(name: &str) -> Option<&'static str> {
+    fn normalize(name: &str) -> String {
+        name.chars()
+            .filter(|c| c.is_alphanumeric())
+            .map(|c| c.to_ascii_lowercase())
+            .collect()
+    }
+
+    let name = normalize(name);
+    ALTERNATE_RECIPES
+        .iter()
+        .find(|a| normalize(a) == name)
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buildings::recipe_names;
+
+    #[test]
+    fn test_alternates_exist() {
+        let names = recipe_names();
+        for alternate in ALTERNATE_RECIPES {
+            assert!(names.iter().any(|n| n == alternate), "{alternate}");
+        }
+    }
+
+    #[test]
+    fn test_alternates_in_docs() {
+        let file = std::fs::read("./tests/docs_sample.json").unwrap();
+        let db = RecipeDatabase::parse(&file).unwrap();
+        for alternate in ALTERNATE_RECIPES {
+            let recipe = db.recipe(alternate);
+            assert!(recipe.is_some_and(|r| r.alternate), "{alternate}");
+        }
+        assert!(!is_alternate("Turbofuel"));
+    }
+
+    #[test]
+    fn test_is_unlocked() {
+        let mut unlocks = Unlocks {
+            tier: 0,
+            alternates: BTreeSet::new(),
+        };
+        assert!(unlocks.is_unlocked("Iron Ingot"));
+        assert!(!unlocks.is_unlocked("Pure Aluminum Ingot"));
+        assert!(!unlocks.is_unlocked("Solid Steel Ingot"));
+
+        unlocks.set_unlocked("Solid Steel Ingot", true);
+        assert!(unlocks.is_unlocked("Solid Steel Ingot"));
+        assert!(Unlocks::default().is_unlocked("Pure Aluminum Ingot"));
+    }
+
+    #[test]
+    fn test_renamed_alternates() {
+        let json = r#"{"tier":9,"alternates":["Dark Crystallization Matter ","Cloudy Diamonds"]}"#;
+        let unlocks: Unlocks = serde_json::from_str(json).unwrap();
+        assert!(unlocks.alternates.contains("Dark Matter Crystallization"));
+        assert!(unlocks.alternates.contains("Cloudy Diamonds"));
+        assert_eq!(unlocks.alternates.len(), 2);
+    }
+
+    #[test]
+    fn test_is_available() {
+        let unlocks = Unlocks {
+            tier: 3,
+            alternates: BTreeSet::new(),
+        };
+        assert!(unlocks.is_available(Unlock::ONBOARDING));
+        assert!(unlocks.is_available(Unlock::milestone(3, "Coal Power")));
+        assert!(!unlocks.is_available(Unlock::milestone(5, "Oil Processing")));
+        assert_eq!(milestone_tier("Schematic_5-3_C"), Some(5));
+        assert_eq!(milestone_tier("Schematic_Tutorial1_5_C"), None);
+        assert_eq!(
+            Unlock::milestone(5, "Oil Processing").to_string(),
+            "Tier 5: Oil Processing"
+        );
+    }
+
+    #[test]
+    fn test_import_schematics() {
+        let schematics = [
+            "Schematic_1-1_C".to_string(),
+            "Schematic_Alternate_BoltedFrame_C".to_string(),
+            "Schematic_Alternate_CopperRotor_C".to_string(),
+            "Schematic_Alternate_InventorySlots1_C".to_string(),
+        ];
+        let mut unlocks = Unlocks::all();
+        assert_eq!(unlocks.import_schematics(&schematics, None), 2);
+        assert_eq!(unlocks.tier, 1);
+        assert!(unlocks.is_unlocked("Bolted Frame"));
+        assert!(unlocks.is_unlocked("Copper Rotor"));
+        assert!(!unlocks.is_unlocked("Steel Screw"));
+    }
+}
@@ -1,1191 +1,221 @@
-use std::collections::HashMap;
+use std::collections::HashSet;
 
-use egui::{vec2, Color32, FontId, Id, Response, RichText, Ui, Vec2};
-use egui_dock::SurfaceIndex;
+use egui::{vec2, Color32, FontId, Id, RichText, Ui, Vec2};
 use egui_snarl::{
     ui::{AnyPins, PinInfo, SnarlViewer},
-    InPin, NodeId, OutPin,
+    InPin, InPinId, NodeId, OutPin, OutPinId,
 };
 use petgraph::visit::EdgeRef;
-use strum::VariantArray;
 
 use crate::{
-    app::{EdgeDetails, GraphIdx, GroupEdit, NodeGraph, Snarl},
-    buildings::{
-        Building, Fluid, Material, MinerLevel, Pipe, ResourcePurity, Selectable, SomersloopSlot1,
-        SomersloopSlot2, SomersloopSlot4,
-    },
-    node::{Node, Output, Resource},
+    app::{GraphIdx, GroupEdit, Logistics, NodeGraph, Snarl},
+    buildings::{round, BodyContext, Building, PinSpec},
+    clipboard::Clipboard,
+    group::rewire,
+    history::{wires_at, Command, History, Wire},
+    library::Library,
+    node::{refresh_node, GroupPin, Node, Output, Resource, ResourceType},
+    palette::search_ui,
+    unlocks::Unlocks,
+    widgets::{radiation_ui, INVALID_COLOR},
 };
 
 const BUILDING_COLOR: Color32 = Color32::from_rgb(0xb0, 0xb0, 0xb0);
-const INVALID_COLOR: Color32 = Color32::from_rgb(144, 20, 0);
 
 pub struct Viewer<'a> {
     pub snarl_id_source: String,
     pub snarl_ui_id: Option<Id>,
-    pub index: (SurfaceIndex, egui_dock::NodeIndex, usize),
+    /// Id of the tab the graph is shown in.
+    pub tab_id: usize,
     pub graph: &'a mut NodeGraph,
     pub group_edits: &'a mut Vec<(GroupEdit, NodeGraph, Snarl)>,
+    pub logistics: &'a Logistics,
+    pub unlocks: &'a Unlocks,
+    pub library: &'a mut Library,
+    pub history: &'a mut History,
+    /// Shared by all tabs.
+    pub clipboard: &'a mut Option<Clipboard>,
+    /// Query of the search at the top of the graph menu.
+    pub search: &'a mut String,
 }
 
 impl Viewer<'_> {
+    /// `copies` of the building are fed through this pin.
     fn show_input_building(
         &self,
         b: &Building,
         input: usize,
+        copies: u32,
         ui: &mut Ui,
         scale: f32,
-        snarl: &Snarl,
     ) -> PinInfo {
-        match b {
-            Building::Miner(_) => {
-                unreachable!("Miner has no inputs")
-            }
-            Building::OilExtractor(_) => {
-                unreachable!("Oil Extractor has no inputs")
-            }
-            Building::WaterExtractor(_) => {
-                unreachable!("Water extractor has no inputs")
-            }
-            Building::StorageContainer(_) => {
-                unreachable!("Storage Container has no inputs")
-            }
-            Building::Packager(p) => {
-                if input == 0 {
-                    let max_input_speed =
-                        p.recipe.map(|r| r.input_fluid_speed()).unwrap_or_default();
-                    let fluid = p.input_fluid().map(Resource::Fluid);
-
-                    let actual_input_speed = p
-                        .current_input_fluid
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_fluid = p.current_input_fluid.as_ref().map(|i| i.resource);
-                    single_input(
-                        fluid,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_fluid,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::circle(),
-                    )
-                } else if input == 1 {
-                    let max_input_speed = p
-                        .recipe
-                        .map(|r| r.input_material_speed())
-                        .unwrap_or_default();
-                    let material = p.input_material().map(Resource::Material);
-                    let actual_input_speed = p
-                        .current_input_material
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_material =
-                        p.current_input_material.as_ref().map(|i| i.resource);
-                    single_input(
-                        material,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_material,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::square(),
-                    )
-                } else {
-                    unreachable!("only two inputs");
-                }
-            }
-            Building::Foundry(f) => {
-                if input == 0 {
-                    let max_input_speed = f
-                        .recipe
-                        .map(|r| r.input_material_speed().0)
-                        .unwrap_or_default();
-                    let material = f.input_material().map(|(a, _)| Resource::Material(a));
-                    let actual_input_speed = f
-                        .current_input_material_0
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_material =
-                        f.current_input_material_0.as_ref().map(|i| i.resource);
-                    single_input(
-                        material,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_material,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::square(),
-                    )
-                } else if input == 1 {
-                    let max_input_speed = f
-                        .recipe
-                        .map(|r| r.input_material_speed().1)
-                        .unwrap_or_default();
-                    let material = f.input_material().map(|(_, b)| Resource::Material(b));
-                    let actual_input_speed = f
-                        .current_input_material_1
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_material =
-                        f.current_input_material_1.as_ref().map(|i| i.resource);
-                    single_input(
-                        material,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_material,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::square(),
-                    )
-                } else {
-                    unreachable!("only two inputs");
-                }
-            }
-            Building::Assembler(f) => {
-                if input == 0 {
-                    let max_input_speed = f
-                        .recipe
-                        .map(|r| r.input_material_speed().0)
-                        .unwrap_or_default();
-                    let material = f.input_material().map(|(a, _)| Resource::Material(a));
-
-                    let actual_input_speed = f
-                        .current_input_material_0
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_material =
-                        f.current_input_material_0.as_ref().map(|i| i.resource);
-                    single_input(
-                        material,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_material,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::square(),
-                    )
-                } else if input == 1 {
-                    let max_input_speed = f
-                        .recipe
-                        .map(|r| r.input_material_speed().1)
-                        .unwrap_or_default();
-                    let material = f.input_material().map(|(_, b)| Resource::Material(b));
-                    let actual_input_speed = f
-                        .current_input_material_1
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_material =
-                        f.current_input_material_1.as_ref().map(|i| i.resource);
-                    single_input(
-                        material,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_material,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::square(),
-                    )
-                } else {
-                    unreachable!("only two inputs");
-                }
-            }
-            Building::Manufacturer(f) => match input {
-                0 => {
-                    let max_input_speed = f
-                        .recipe
-                        .map(|r| r.input_material_speed().0)
-                        .unwrap_or_default();
-                    let material = f.input_material().map(|(a, _, _, _)| Resource::Material(a));
-                    let actual_input_speed = f
-                        .current_input_material_0
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_material =
-                        f.current_input_material_0.as_ref().map(|i| i.resource);
-                    single_input(
-                        material,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_material,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::square(),
-                    )
-                }
-                1 => {
-                    let max_input_speed = f
-                        .recipe
-                        .map(|r| r.input_material_speed().1)
-                        .unwrap_or_default();
-                    let material = f.input_material().map(|(_, b, _, _)| Resource::Material(b));
-                    let actual_input_speed = f
-                        .current_input_material_1
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_material =
-                        f.current_input_material_1.as_ref().map(|i| i.resource);
-                    single_input(
-                        material,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_material,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::square(),
-                    )
-                }
-                2 => {
-                    let max_input_speed = f
-                        .recipe
-                        .map(|r| r.input_material_speed().2)
-                        .unwrap_or_default();
-                    let material = f.input_material().map(|(_, _, c, _)| Resource::Material(c));
-                    let actual_input_speed = f
-                        .current_input_material_2
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_material =
-                        f.current_input_material_2.as_ref().map(|i| i.resource);
-                    single_input(
-                        material,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_material,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::square(),
-                    )
-                }
-                3 => {
-                    let max_input_speed = f
-                        .recipe
-                        .map(|r| r.input_material_speed().3)
-                        .unwrap_or_default();
-                    let material = f
-                        .input_material()
-                        .and_then(|(_, _, _, d)| d.map(Resource::Material));
-                    let actual_input_speed = f
-                        .current_input_material_3
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_material =
-                        f.current_input_material_3.as_ref().map(|i| i.resource);
-                    single_input(
-                        material,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_material,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::square(),
-                    )
-                }
-                _ => unreachable!("only four inputs"),
-            },
-            Building::Refinery(p) => {
-                if input == 0 {
-                    let max_input_speed =
-                        p.recipe.map(|r| r.input_fluid_speed()).unwrap_or_default();
-                    let fluid = p.input_fluid().map(Resource::Fluid);
-                    let actual_input_speed = p
-                        .current_input_fluid
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_fluid = p.current_input_fluid.as_ref().map(|i| i.resource);
-                    single_input(
-                        fluid,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_fluid,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::circle(),
-                    )
-                } else if input == 1 {
-                    let max_input_speed = p
-                        .recipe
-                        .map(|r| r.input_material_speed())
-                        .unwrap_or_default();
-                    let material = p.input_material().map(Resource::Material);
-                    let actual_input_speed = p
-                        .current_input_material
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_material =
-                        p.current_input_material.as_ref().map(|i| i.resource);
-                    single_input(
-                        material,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_material,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::square(),
-                    )
-                } else {
-                    unreachable!("only two inputs");
-                }
-            }
-            Building::Smelter(ref s) => {
-                assert_eq!(input, 0, "Smelter node has only one input");
-
-                let material = s.input_material().map(Resource::Material);
-                let max_input_speed = s.input_speed();
-                let actual_input_speed = s
-                    .current_input
-                    .as_ref()
-                    .map(|i| i.speed)
-                    .unwrap_or_default();
-                let actual_input_material = s.current_input.as_ref().map(|i| i.resource);
-                single_input(
-                    material,
-                    max_input_speed,
-                    actual_input_speed,
-                    actual_input_material,
-                    ui,
-                    scale,
-                    snarl,
-                    PinInfo::square(),
-                )
-            }
-            Building::PipelineJunction(s) => {
-                // 4 inputs
-
-                let current_input = match input {
-                    0 => &s.current_input_0,
-                    1 => &s.current_input_1,
-                    2 => &s.current_input_2,
-                    3 => &s.current_input_3,
-                    _ => unreachable!("4 inputs"),
-                };
-
-                let input_fluid = current_input.as_ref().map(|i| i.resource);
-                let color = input_fluid.map(|r| r.color()).unwrap_or(BUILDING_COLOR);
-                let actual_input_speed =
-                    current_input.as_ref().map(|r| r.speed).unwrap_or_default();
-
-                ui.horizontal(|ui| {
-                    add_resource_image(ui, scale, &input_fluid);
-                    ui.label(format!("{}/m^3", actual_input_speed,));
-                });
-
-                PinInfo::circle().with_fill(color)
-            }
-            Building::Splitter(s) => {
-                assert_eq!(input, 0, "Splitter node has only one input");
-
-                let (actual_input_speed, material) = match s.current_input {
-                    Some(ref input) => (input.speed, Some(input.resource)),
-                    None => (0., None),
-                };
-
-                let color = material
-                    .as_ref()
-                    .map(|m: &Resource| m.color())
-                    .unwrap_or(BUILDING_COLOR);
-
-                ui.horizontal(|ui| {
-                    add_resource_image(ui, scale, &material);
-                    ui.label(format!("{}/min", actual_input_speed,));
-                });
-
-                PinInfo::square().with_fill(color)
-            }
-            Building::Merger(m) => {
-                // 3 inputs
-
-                let current_input = match input {
-                    0 => &m.current_input_0,
-                    1 => &m.current_input_1,
-                    2 => &m.current_input_2,
-                    _ => unreachable!("3 inputs"),
-                };
-
-                let resource = current_input.as_ref().map(|i| i.resource);
-                let color = resource.map(|r| r.color()).unwrap_or(BUILDING_COLOR);
-                let actual_input_speed =
-                    current_input.as_ref().map(|r| r.speed).unwrap_or_default();
-
-                ui.horizontal(|ui| {
-                    add_resource_image(ui, scale, &resource);
-                    ui.label(format!("{}/min", actual_input_speed));
-                });
-
-                PinInfo::square().with_fill(color)
-            }
-            Building::Constructor(ref s) => {
-                assert_eq!(input, 0, "Constructor node has only one input");
-
-                let material = s.input_material().map(Resource::Material);
-                let max_input_speed = s.input_speed();
-
-                let actual_input_speed = s
-                    .current_input
-                    .as_ref()
-                    .map(|i| i.speed)
-                    .unwrap_or_default();
-                let actual_input_material = s.current_input.as_ref().map(|i| i.resource);
-
-                single_input(
-                    material,
-                    max_input_speed,
-                    actual_input_speed,
-                    actual_input_material,
-                    ui,
-                    scale,
-                    snarl,
-                    PinInfo::square(),
-                )
-            }
-            Building::AwesomeSink(ref s) => {
-                assert_eq!(input, 0, "Awesome sink node has only one input");
-
-                let color = match s.current_input {
-                    Some(ref input) => {
-                        let color = input.resource.color();
-
-                        ui.horizontal(|ui| {
-                            add_resource_image(ui, scale, &Some(input.resource));
-                            ui.label(format!("{}/min", input.speed));
-                        });
-                        color
-                    }
-                    None => BUILDING_COLOR,
-                };
-
-                PinInfo::square().with_fill(color)
-            }
-            Building::Blender(b) => match input {
-                0 => {
-                    let max_input_speed = b.recipe.map(|r| r.input_speed().0).unwrap_or_default();
-                    let fluid = b.input_material().map(|b| Resource::Fluid(b.0));
-
-                    let actual_input_speed = b
-                        .current_input_fluid_0
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_fluid = b.current_input_fluid_0.as_ref().map(|i| i.resource);
-                    single_input(
-                        fluid,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_fluid,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::circle(),
-                    )
-                }
-                1 => {
-                    let max_input_speed = b.recipe.map(|r| r.input_speed().1).unwrap_or_default();
-                    let fluid = b.input_material().and_then(|b| b.1).map(Resource::Fluid);
-
-                    let actual_input_speed = b
-                        .current_input_fluid_1
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_fluid = b.current_input_fluid_1.as_ref().map(|i| i.resource);
-                    single_input(
-                        fluid,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_fluid,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::circle(),
-                    )
-                }
-                2 => {
-                    let max_input_speed = b.recipe.map(|r| r.input_speed().2).unwrap_or_default();
-                    let material = b.input_material().and_then(|b| b.2).map(Resource::Material);
-
-                    let actual_input_speed = b
-                        .current_input_material_0
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_material =
-                        b.current_input_material_0.as_ref().map(|i| i.resource);
-                    single_input(
-                        material,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_material,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::square(),
-                    )
-                }
-                3 => {
-                    let max_input_speed = b.recipe.map(|r| r.input_speed().3).unwrap_or_default();
-                    let material = b.input_material().and_then(|b| b.3).map(Resource::Material);
-
-                    let actual_input_speed = b
-                        .current_input_material_1
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_material =
-                        b.current_input_material_1.as_ref().map(|i| i.resource);
-                    single_input(
-                        material,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_material,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::square(),
-                    )
-                }
-                _ => unreachable!("4 inputs"),
-            },
-            Building::ParticleAccelerator(b) => match input {
-                0 => {
-                    let max_input_speed = b.recipe.map(|r| r.input_speed().0).unwrap_or_default();
-                    let fluid = b.input_material().and_then(|b| b.0).map(Resource::Fluid);
-
-                    let actual_input_speed = b
-                        .current_input_fluid_0
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_fluid = b.current_input_fluid_0.as_ref().map(|i| i.resource);
-                    single_input(
-                        fluid,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_fluid,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::circle(),
-                    )
-                }
-                1 => {
-                    let max_input_speed = b.recipe.map(|r| r.input_speed().1).unwrap_or_default();
-                    let material = b.input_material().and_then(|b| b.1).map(Resource::Material);
-
-                    let actual_input_speed = b
-                        .current_input_material_0
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_material =
-                        b.current_input_material_0.as_ref().map(|i| i.resource);
-                    single_input(
-                        material,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_material,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::square(),
-                    )
-                }
-                2 => {
-                    let max_input_speed = b.recipe.map(|r| r.input_speed().2).unwrap_or_default();
-                    let material = b.input_material().and_then(|b| b.2).map(Resource::Material);
-
-                    let actual_input_speed = b
-                        .current_input_material_1
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_material =
-                        b.current_input_material_1.as_ref().map(|i| i.resource);
-                    single_input(
-                        material,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_material,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::square(),
-                    )
-                }
-                _ => unreachable!("3 inputs"),
-            },
-            Building::QuantumEncoder(b) => match input {
-                0 => {
-                    let max_input_speed = b.recipe.map(|r| r.input_speed().0).unwrap_or_default();
-                    let fluid = b.input_material().map(|b| Resource::Fluid(b.0));
-
-                    let actual_input_speed = b
-                        .current_input_fluid_0
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_fluid = b.current_input_fluid_0.as_ref().map(|i| i.resource);
-                    single_input(
-                        fluid,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_fluid,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::circle(),
-                    )
-                }
-                1 => {
-                    let max_input_speed = b.recipe.map(|r| r.input_speed().1).unwrap_or_default();
-                    let material = b.input_material().map(|b| b.1).map(Resource::Material);
-
-                    let actual_input_speed = b
-                        .current_input_material_0
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_material =
-                        b.current_input_material_0.as_ref().map(|i| i.resource);
-                    single_input(
-                        material,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_material,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::square(),
-                    )
-                }
-                2 => {
-                    let max_input_speed = b.recipe.map(|r| r.input_speed().2).unwrap_or_default();
-                    let material = b.input_material().map(|b| b.2).map(Resource::Material);
-
-                    let actual_input_speed = b
-                        .current_input_material_1
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_material =
-                        b.current_input_material_1.as_ref().map(|i| i.resource);
-                    single_input(
-                        material,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_material,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::square(),
-                    )
-                }
-                3 => {
-                    let max_input_speed = b.recipe.map(|r| r.input_speed().3).unwrap_or_default();
-                    let material = b.input_material().map(|b| b.3).map(Resource::Material);
-
-                    let actual_input_speed = b
-                        .current_input_material_2
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_material =
-                        b.current_input_material_2.as_ref().map(|i| i.resource);
-                    single_input(
-                        material,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_material,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::square(),
-                    )
-                }
-                _ => unreachable!("4 inputs"),
-            },
-            Building::Converter(b) => match input {
-                0 => {
-                    let max_input_speed = b.recipe.map(|r| r.input_speed().0).unwrap_or_default();
-                    let material = b.input_material().and_then(|b| b.0).map(Resource::Material);
-
-                    let actual_input_speed = b
-                        .current_input_material_0
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_material =
-                        b.current_input_material_0.as_ref().map(|i| i.resource);
-                    single_input(
-                        material,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_material,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::square(),
-                    )
-                }
-                1 => {
-                    let max_input_speed = b.recipe.map(|r| r.input_speed().1).unwrap_or_default();
-                    let material = b.input_material().and_then(|b| b.1).map(Resource::Material);
-
-                    let actual_input_speed = b
-                        .current_input_material_1
-                        .as_ref()
-                        .map(|i| i.speed)
-                        .unwrap_or_default();
-                    let actual_input_material =
-                        b.current_input_material_1.as_ref().map(|i| i.resource);
-                    single_input(
-                        material,
-                        max_input_speed,
-                        actual_input_speed,
-                        actual_input_material,
-                        ui,
-                        scale,
-                        snarl,
-                        PinInfo::square(),
-                    )
-                }
-                _ => unreachable!("3 inputs"),
-            },
+        let copies = copies as f32;
+        let typ = b.input_resource(input);
+        let (speed, resource) = match b.current_input(input) {
+            Some(input) => (round(input.speed * copies), Some(input.resource)),
+            None => (0., None),
+        };
+        if let Some(radiation) = resource.and_then(|r| r.radiation(speed)) {
+            radiation_ui(ui, radiation);
+        }
+        let spec = b.machine().input_spec(input).map(|spec| PinSpec {
+            max_speed: round(spec.max_speed * copies),
+            ..spec
+        });
+        match spec {
+            Some(spec) => single_input(spec, speed, resource, typ, ui, scale),
+            None => pass_through(speed, resource, typ, ui, scale),
         }
     }
 
+    /// `copies` of the building feed this pin.
     fn show_output_building(
         &self,
         b: &Building,
         output: usize,
+        copies: u32,
         ui: &mut Ui,
         scale: f32,
-        _snarl: &Snarl,
     ) -> PinInfo {
-        match b {
-            Building::Miner(m) => {
-                assert_eq!(output, 0, "Miner has only one output");
-
-                let speed = m.output_speed();
-                let material = m.output_material().map(Resource::Material);
-                let color = material
-                    .as_ref()
-                    .map(|m| m.color())
-                    .unwrap_or(BUILDING_COLOR);
-
-                ui.horizontal(|ui| {
-                    add_resource_image(ui, scale, &material);
-                    ui.label(format!("{}/min", speed));
-                });
-
-                PinInfo::square().with_fill(color)
-            }
-            Building::Packager(p) => {
-                if output == 0 {
-                    // Fluid
-                    let fluid = p.output_fluid();
-                    let max_speed = p
-                        .recipe
-                        .as_ref()
-                        .map(|r| r.max_output_speed_fluid())
-                        .unwrap_or_default();
-
-                    fluid_output(fluid, max_speed, p.current_output_fluid(), ui, scale)
-                } else if output == 1 {
-                    // Material
-                    let material = p.output_material();
-                    let max_speed = p
-                        .recipe
-                        .as_ref()
-                        .map(|r| r.max_output_speed_material())
-                        .unwrap_or_default();
-
-                    material_output(material, max_speed, p.current_output_material(), ui, scale)
-                } else {
-                    unreachable!("only two outputs");
-                }
-            }
-            Building::Refinery(p) => {
-                if output == 0 {
-                    // Fluid
-                    let fluid = p.output_fluid();
-                    let max_speed = p
-                        .recipe
-                        .as_ref()
-                        .map(|r| r.max_output_speed_fluid())
-                        .unwrap_or_default();
-
-                    fluid_output(fluid, max_speed, p.current_output_fluid(), ui, scale)
-                } else if output == 1 {
-                    // Material
-                    let material = p.output_material();
-                    let max_speed = p
-                        .recipe
-                        .as_ref()
-                        .map(|r| r.max_output_speed_material())
-                        .unwrap_or_default();
-
-                    material_output(material, max_speed, p.current_output_material(), ui, scale)
-                } else {
-                    unreachable!("only two outputs");
-                }
-            }
-            Building::WaterExtractor(w) => {
-                assert_eq!(output, 0, "Water Extractor has only one output");
-                let speed = w.output_speed();
-                let fluid = Resource::Fluid(w.output_fluid());
-                let color = fluid.color();
-
-                ui.horizontal(|ui| {
-                    add_resource_image(ui, scale, &Some(fluid));
-                    ui.label(format!("{}/m^3", speed));
-                });
-
-                PinInfo::circle().with_fill(color)
-            }
-            Building::OilExtractor(o) => {
-                assert_eq!(output, 0, "Oil Extractor has only one output");
-                let speed = o.output_speed();
-                let fluid = Resource::Fluid(o.output_fluid());
-                let color = fluid.color();
-
-                ui.horizontal(|ui| {
-                    add_resource_image(ui, scale, &Some(fluid));
-                    ui.label(format!("{}/m^3", speed));
-                });
-
-                PinInfo::circle().with_fill(color)
-            }
-            Building::StorageContainer(s) => {
-                assert_eq!(output, 0, "Storage Container has only one output");
-                let speed = s.output_speed();
-                let material = s.output_material().map(Resource::Material);
-                let color = material
-                    .as_ref()
-                    .map(|m| m.color())
-                    .unwrap_or(BUILDING_COLOR);
-
-                ui.horizontal(|ui| {
-                    add_resource_image(ui, scale, &material);
-                    ui.label(format!("{}/min", speed));
-                });
-
-                PinInfo::square().with_fill(color)
-            }
-            Building::Smelter(s) => {
-                assert_eq!(output, 0, "Smelter node has only one output");
-
-                let material = s.output_material();
-                let max_speed = s
-                    .recipe
-                    .as_ref()
-                    .map(|r| r.max_output_speed())
-                    .unwrap_or_default();
-
-                material_output(material, max_speed, s.current_output(), ui, scale)
-            }
-            Building::Foundry(f) => {
-                assert_eq!(output, 0, "Foundry node has only one output");
-
-                let material = f.output_material();
-                let max_speed = f
-                    .recipe
-                    .as_ref()
-                    .map(|r| r.max_output_speed_material())
-                    .unwrap_or_default();
-
-                material_output(material, max_speed, f.current_output(), ui, scale)
-            }
-            Building::Assembler(f) => {
-                assert_eq!(output, 0, "Assembler node has only one output");
-
-                let material = f.output_material();
-                let max_speed = f
-                    .recipe
-                    .as_ref()
-                    .map(|r| r.max_output_speed_material())
-                    .unwrap_or_default();
-
-                material_output(material, max_speed, f.current_output(), ui, scale)
-            }
-            Building::Manufacturer(f) => {
-                assert_eq!(output, 0, "Assembler node has only one output");
-
-                let material = f.output_material();
-                let max_speed = f
-                    .recipe
-                    .as_ref()
-                    .map(|r| r.max_output_speed_material())
-                    .unwrap_or_default();
-
-                material_output(material, max_speed, f.current_output(), ui, scale)
-            }
-            Building::PipelineJunction(s) => {
-                let output = match output {
-                    0 => s.current_output_0(),
-                    1 => s.current_output_1(),
-                    2 => s.current_output_2(),
-                    3 => s.current_output_3(),
-                    _ => unreachable!("4 outputs"),
-                };
-                let (speed, fluid) = match output {
+        let copies = copies as f32;
+        let typ = b.output_resource(output);
+        let current = b.current_output(output).map(|o| Output {
+            speed: round(o.speed * copies),
+            resource: o.resource,
+        });
+        let limited = current.is_some() && b.machine().is_output_limited(output);
+        if let Some(radiation) = current.as_ref().and_then(|o| o.resource.radiation(o.speed)) {
+            radiation_ui(ui, radiation);
+        }
+        let spec = b.machine().output_spec(output).map(|spec| PinSpec {
+            max_speed: round(spec.max_speed * copies),
+            ..spec
+        });
+        let pin = match spec {
+            Some(spec) => rated_output(spec, current, typ, ui, scale),
+            None => {
+                let (speed, resource) = match current {
                     Some(output) => (output.speed, Some(output.resource)),
                     None => (0., None),
                 };
-
-                ui.horizontal(|ui| {
-                    add_resource_image(ui, scale, &fluid);
-                    ui.label(format!("{}/m^3", speed));
-                });
-
-                let color = fluid.map(|m| m.color()).unwrap_or(BUILDING_COLOR);
-                PinInfo::circle().with_fill(color)
+                pass_through(speed, resource, typ, ui, scale)
             }
-            Building::Splitter(s) => {
-                let output = match output {
-                    0 => s.current_output_0(),
-                    1 => s.current_output_1(),
-                    2 => s.current_output_2(),
-                    _ => unreachable!("3 outputs"),
-                };
-                let (speed, material) = match output {
-                    Some(output) => (output.speed, Some(output.resource)),
-                    None => (0., None),
-                };
+        };
+        if limited {
+            pin.with_fill(INVALID_COLOR)
+        } else {
+            pin
+        }
+    }
 
-                ui.horizontal(|ui| {
-                    add_resource_image(ui, scale, &material);
-                    ui.label(format!("{}/min", speed));
-                });
+    /// Edit the height of the pipes going into this input, and warn if
+    /// they climb higher than the upstream pump or building can push.
+    fn show_head_lift(&mut self, node: NodeId, input: usize, ui: &mut Ui, snarl: &Snarl) {
+        let node_idx = snarl[node];
+        let edges: Vec<_> = self
+            .graph
+            .edges_directed(node_idx, petgraph::Direction::Incoming)
+            .filter(|e| e.weight().input == input)
+            .map(|e| (e.id(), e.source()))
+            .collect();
 
-                let color = material.map(|m| m.color()).unwrap_or(BUILDING_COLOR);
-                PinInfo::square().with_fill(color)
-            }
-            Building::Merger(m) => {
-                let color = match m.current_output() {
-                    Some(output) => {
-                        let color = output.resource.color();
-                        ui.horizontal(|ui| {
-                            add_resource_image(ui, scale, &Some(output.resource));
-                            ui.label(format!("{}/min", output.speed));
-                        });
-                        color
-                    }
-                    None => BUILDING_COLOR,
+        for (edge, source) in edges {
+            let weight = self.graph.edge_weight_mut(edge).unwrap();
+            let before = weight.height;
+            let changed = ui
+                .horizontal(|ui| {
+                    let response = ui.add(
+                        egui::DragValue::new(&mut weight.height)
+                            .speed(0.5)
+                            .suffix("m"),
+                    );
+                    ui.label("Height");
+                    response.changed()
+                })
+                .inner;
+            if changed {
+                let (source_id, _) = snarl.node_ids().find(|(_, idx)| **idx == source).unwrap();
+                let command = Command::SetHeight {
+                    from: OutPinId {
+                        node: source_id,
+                        output: weight.output,
+                    },
+                    to: InPinId { node, input },
+                    before,
+                    after: weight.height,
                 };
-
-                PinInfo::square().with_fill(color)
-            }
-            Building::Constructor(s) => {
-                assert_eq!(output, 0, "Constructor node has only one output");
-                let material = s.output_material();
-                let max_speed = s
-                    .recipe
-                    .as_ref()
-                    .map(|r| r.max_output_speed())
-                    .unwrap_or_default();
-
-                material_output(material, max_speed, s.current_output(), ui, scale)
-            }
-            Building::AwesomeSink(_) => {
-                unreachable!("no outputs");
-            }
-            Building::Blender(b) => {
-                let (max_speed_fluid, max_speed_material) = b
-                    .recipe
-                    .as_ref()
-                    .map(|r| r.max_output_speed())
-                    .unwrap_or_default();
-
-                match output {
-                    0 => {
-                        let fluid = b.output_fluid();
-                        let max_speed = max_speed_fluid;
-
-                        fluid_output(fluid, max_speed, b.current_output_fluid(), ui, scale)
-                    }
-                    1 => {
-                        let material = b.output_material();
-                        let max_speed = max_speed_material;
-
-                        material_output(material, max_speed, b.current_output_material(), ui, scale)
-                    }
-                    _ => unreachable!("2 outputs"),
-                }
-            }
-            Building::ParticleAccelerator(b) => {
-                let max_speed_material = b
-                    .recipe
-                    .as_ref()
-                    .map(|r| r.max_output_speed())
-                    .unwrap_or_default();
-
-                match output {
-                    0 => {
-                        let material = b.output_material();
-
-                        material_output(
-                            material,
-                            max_speed_material,
-                            b.current_output_material(),
-                            ui,
-                            scale,
-                        )
-                    }
-                    _ => unreachable!("1 output"),
-                }
+                let time = ui.input(|i| i.time);
+                self.history.push_merged("Edit Height", time, command);
             }
-            Building::QuantumEncoder(b) => {
-                let (max_speed_fluid, max_speed_material) = b
-                    .recipe
-                    .as_ref()
-                    .map(|r| r.max_output_speed())
-                    .unwrap_or_default();
-
-                match output {
-                    0 => {
-                        let fluid = b.output_fluid();
-                        let max_speed = max_speed_fluid;
-
-                        fluid_output(fluid, max_speed, b.current_output_fluid(), ui, scale)
-                    }
-                    1 => {
-                        let material = b.output_material();
-                        let max_speed = max_speed_material;
+        }
 
-                        material_output(material, max_speed, b.current_output_material(), ui, scale)
-                    }
-                    _ => unreachable!("2 outputs"),
-                }
-            }
-            Building::Converter(b) => {
-                let (max_speed_fluid, max_speed_material) = b
-                    .recipe
-                    .as_ref()
-                    .map(|r| r.max_output_speed())
-                    .unwrap_or_default();
-
-                match output {
-                    0 => fluid_output(
-                        b.output_fluid(),
-                        max_speed_fluid,
-                        b.current_output_fluid(),
-                        ui,
-                        scale,
-                    ),
-                    1 => material_output(
-                        b.output_material(),
-                        max_speed_material,
-                        b.current_output_material(),
-                        ui,
-                        scale,
-                    ),
-                    _ => unreachable!("2 outputs"),
-                }
+        let mut visited = HashSet::new();
+        if let Some(margin) = head_lift_margin(self.graph, node_idx, input, 0., &mut visited) {
+            if margin < 0. {
+                ui.colored_label(
+                    INVALID_COLOR,
+                    format!("Exceeds head lift by {:.1}m", -margin),
+                );
             }
         }
     }
 
-    fn refresh_node(&mut self, node_idx: GraphIdx) {
-        use petgraph::prelude::NodeIndex;
-
-        // Find all paths
-        fn all_paths_dfs(
-            graph: &NodeGraph,
-            start: NodeIndex,
-            end: NodeIndex,
-            visited: &mut HashMap<NodeIndex, Vec<NodeIndex>>,
-            path: &mut Vec<NodeIndex>,
-            all_paths: &mut Vec<Vec<NodeIndex>>,
-        ) {
-            path.push(start); // Add the current node to the path
-            if start == end {
-                // If the current node is the destination
-                all_paths.push(path.clone()); // Add a copy of the path to the results
-            } else {
-                // Explore neighbors
-                for neighbor in
-                    graph.neighbors_directed(start, petgraph::prelude::Direction::Outgoing)
-                {
-                    let count = path.iter().filter(|n| *n == &neighbor).count();
-                    // This number is..special
-                    // A tradeoff between accuracy and calculation time.
-                    const MAX_COUNT: usize = 5;
-                    if count < MAX_COUNT {
-                        all_paths_dfs(graph, neighbor, end, visited, path, all_paths);
-                    }
-                }
-            }
-            // Backtrack to explore other paths
-            path.pop();
+    /// Remove all wires from an output that no longer exists.
+    fn drop_output(&mut self, node: NodeId, output: usize, snarl: &mut Snarl) {
+        let graph_idx = snarl[node];
+        let edges: Vec<_> = self
+            .graph
+            .edges_directed(graph_idx, petgraph::Direction::Outgoing)
+            .filter(|e| e.weight().output == output)
+            .map(|e| (e.id(), e.target(), e.weight().input))
+            .collect();
+
+        for (edge, target, input) in edges {
+            self.graph.remove_edge(edge);
+            self.graph
+                .node_weight_mut(target)
+                .unwrap()
+                .clear_current_input(input);
+            self.refresh_node(target);
         }
+        snarl.drop_outputs(OutPinId { node, output });
+    }
 
-        let externals: Vec<_> = self
+    /// Remove all wires going into an input that changed its type.
+    fn drop_input(&mut self, node: NodeId, input: usize, snarl: &mut Snarl) {
+        let graph_idx = snarl[node];
+        let edges: Vec<_> = self
             .graph
-            .externals(petgraph::Direction::Outgoing)
+            .edges_directed(graph_idx, petgraph::Direction::Incoming)
+            .filter(|e| e.weight().input == input)
+            .map(|e| (e.id(), e.source(), e.weight().output))
             .collect();
-        for target in externals {
-            let mut path = Vec::new();
-            let mut paths = Vec::new();
-            let mut visited = HashMap::new();
-            all_paths_dfs(
-                &*self.graph,
-                node_idx,
-                target,
-                &mut visited,
-                &mut path,
-                &mut paths,
-            );
-            for path in paths {
-                let mut start_idx = path[0];
-                for next_node_idx in path.into_iter().skip(1) {
-                    let edges: Vec<_> = self
-                        .graph
-                        .edges_connecting(start_idx, next_node_idx)
-                        .map(|e| e.weight().clone())
-                        .collect();
-                    for edge in edges {
-                        let output = self
-                            .graph
-                            .node_weight(start_idx)
-                            .unwrap()
-                            .current_output(edge.output);
-                        let node = self.graph.node_weight_mut(next_node_idx).unwrap();
-                        if let Some(output) = output {
-                            node.set_current_input(output, edge.input);
-                        } else {
-                            node.clear_current_input(edge.input);
-                        }
-                    }
-                    // step forward
-                    start_idx = next_node_idx;
-                }
-            }
+
+        for (edge, source, output) in edges {
+            self.graph.remove_edge(edge);
+            self.graph
+                .node_weight_mut(source)
+                .unwrap()
+                .set_current_output_disconnected(output);
+            self.refresh_node(source);
         }
+        self.graph
+            .node_weight_mut(graph_idx)
+            .unwrap()
+            .clear_current_input(input);
+        snarl.drop_inputs(InPinId { node, input });
+    }
+
+    fn refresh_node(&mut self, node_idx: GraphIdx) {
+        refresh_node(self.graph, node_idx);
+    }
+
+    /// Adds a node that can be undone with the `label`.
+    fn add_node(&mut self, label: String, node: Node, pos: egui::Pos2, snarl: &mut Snarl) {
+        let graph_idx = self.graph.add_node(node);
+        let id = snarl.insert_node(pos, graph_idx);
+        self.history
+            .push(label, Command::added(self.graph, snarl, &[id]));
     }
 }
 
@@ -1200,11 +230,78 @@ impl SnarlViewer<GraphIdx> for Viewer<'_> {
         snarl: &mut Snarl,
     ) {
         let mut changed = false;
+        let mut cx = BodyContext {
+            logistics: self.logistics,
+            unlocks: self.unlocks,
+            removed_input: None,
+            removed_output: None,
+        };
+        let node_id = node;
         let graph_idx = snarl[node];
+        let wired_inputs: HashSet<_> = self
+            .graph
+            .edges_directed(graph_idx, petgraph::Direction::Incoming)
+            .map(|e| e.weight().input)
+            .collect();
+        let wired_outputs: HashSet<_> = self
+            .graph
+            .edges_directed(graph_idx, petgraph::Direction::Outgoing)
+            .map(|e| e.weight().output)
+            .collect();
+        let mut old_pins = None;
+        let mut old_copies = None;
         let node = self.graph.node_weight_mut(graph_idx).unwrap();
+        // widgets only change anything in frames with input, only then the
+        // building is kept to undo the change
+        let before = match node {
+            Node::Building(b) if ui.input(|i| !i.events.is_empty() || i.pointer.any_down()) => {
+                Some(b.clone())
+            }
+            _ => None,
+        };
+        let totals = match node {
+            Node::Group { .. } => group_totals(node),
+            Node::Building(_) => Vec::new(),
+        };
 
         ui.vertical(|ui| match node {
-            Node::Group { snarl, graph, .. } => {
+            Node::Group {
+                snarl,
+                graph,
+                inputs,
+                outputs,
+                copies,
+                ..
+            } => {
+                ui.horizontal(|ui| {
+                    let old = *copies;
+                    if ui
+                        .add(egui::DragValue::new(copies).range(1..=1000).prefix("x "))
+                        .changed()
+                    {
+                        old_copies = Some(old);
+                    }
+                    ui.label("Copies");
+                });
+                if *copies > 1 {
+                    let n = *copies as f32;
+                    egui::Grid::new(("group_totals", node_id))
+                        .num_columns(3)
+                        .show(ui, |ui| {
+                            ui.label("");
+                            ui.label("Per copy");
+                            ui.label("Total");
+                            ui.end_row();
+                            for (label, total) in &totals {
+                                ui.label(label);
+                                ui.label(format!("{}", round(total / n)));
+                                ui.label(format!("{}", round(*total)));
+                                ui.end_row();
+                            }
+                        });
+                }
+                ui.add_space(5. * scale);
+
                 for node_idx in snarl.nodes() {
                     let node = &graph[*node_idx];
                     ui.horizontal(|ui| {
@@ -1222,144 +319,91 @@ impl SnarlViewer<GraphIdx> for Viewer<'_> {
                     });
                     ui.add_space(5. * scale);
                 }
-            }
-            Node::Building(b) => match b {
-                Building::Miner(m) => {
-                    changed |= general_selector(ui, scale, &mut m.resource).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= level_selector(ui, scale, &mut m.level).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= purity_selector(ui, scale, &mut m.resource_purity).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_speed_ui(ui, &mut m.speed).changed;
-                }
-                Building::OilExtractor(m) => {
-                    changed |= pipe_selector(ui, scale, &mut m.output_pipe).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= purity_selector(ui, scale, &mut m.resource_purity).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_speed_ui(ui, &mut m.speed).changed;
-                }
-                Building::Packager(p) => {
-                    changed |= general_selector(ui, scale, &mut p.recipe).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_speed_ui(ui, &mut p.speed).changed;
-                }
-                Building::Foundry(f) => {
-                    changed |= general_selector(ui, scale, &mut f.recipe).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_speed_ui(ui, &mut f.speed).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_somersloop2_ui(ui, &mut f.amplified).changed;
-                }
-                Building::Assembler(f) => {
-                    changed |= general_selector(ui, scale, &mut f.recipe).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_speed_ui(ui, &mut f.speed).changed;
-
-                    ui.add_space(10.0 * scale);
-                    changed |= add_somersloop2_ui(ui, &mut f.amplified).changed;
-                }
-                Building::Manufacturer(f) => {
-                    changed |= general_selector(ui, scale, &mut f.recipe).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_speed_ui(ui, &mut f.speed).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_somersloop4_ui(ui, &mut f.amplified).changed;
-                }
-                Building::Refinery(p) => {
-                    changed |= general_selector(ui, scale, &mut p.recipe).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_speed_ui(ui, &mut p.speed).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_somersloop2_ui(ui, &mut p.amplified).changed;
-                }
-                Building::WaterExtractor(m) => {
-                    changed |= pipe_selector(ui, scale, &mut m.output_pipe).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_speed_ui(ui, &mut m.speed).changed;
-                }
-                Building::StorageContainer(s) => {
-                    changed |= general_selector(ui, scale, &mut s.material).changed;
-                    ui.add_space(10.0 * scale);
 
-                    changed |= general_selector(ui, scale, &mut s.output_belt).changed;
-                }
-                Building::Smelter(s) => {
-                    changed |= general_selector(ui, scale, &mut s.recipe).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_speed_ui(ui, &mut s.speed).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_somersloop1_ui(ui, &mut s.amplified).changed;
-                }
-                Building::PipelineJunction(_) => {}
-                Building::Splitter(_) => {}
-                Building::Merger(_) => {}
-                Building::AwesomeSink(_) => {}
-                Building::Constructor(s) => {
-                    changed |= general_selector(ui, scale, &mut s.recipe).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_speed_ui(ui, &mut s.speed).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_somersloop1_ui(ui, &mut s.amplified).changed;
-                }
-                Building::Blender(b) => {
-                    changed |= general_selector(ui, scale, &mut b.recipe).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_speed_ui(ui, &mut b.speed).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_somersloop4_ui(ui, &mut b.amplified).changed;
-                }
-                Building::ParticleAccelerator(b) => {
-                    changed |= general_selector(ui, scale, &mut b.recipe).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_speed_ui(ui, &mut b.speed).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_somersloop4_ui(ui, &mut b.amplified).changed;
-                }
-                Building::QuantumEncoder(b) => {
-                    changed |= general_selector(ui, scale, &mut b.recipe).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_speed_ui(ui, &mut b.speed).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_somersloop4_ui(ui, &mut b.amplified).changed;
-                }
-                Building::Converter(b) => {
-                    changed |= general_selector(ui, scale, &mut b.recipe).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_speed_ui(ui, &mut b.speed).changed;
-                    ui.add_space(10.0 * scale);
-
-                    changed |= add_somersloop2_ui(ui, &mut b.amplified).changed;
+                egui::CollapsingHeader::new("Pins")
+                    .id_salt(node_id)
+                    .show(ui, |ui| {
+                        let old = (inputs.clone(), outputs.clone());
+                        ui.label("Inputs");
+                        let changed = group_pins_ui(ui, graph, inputs, &wired_inputs);
+                        ui.label("Outputs");
+                        if changed | group_pins_ui(ui, graph, outputs, &wired_outputs) {
+                            old_pins = Some(old);
+                        }
+                    });
+            }
+            Node::Building(b) => {
+                changed |= b.machine_mut().show_body(ui, scale, &mut cx);
+                if let Some(unlock) = self.unlocks.missing(b) {
+                    ui.colored_label(INVALID_COLOR, format!("Needs {unlock}"));
                 }
-            },
+            }
         });
 
+        let time = ui.input(|i| i.time);
+        let label = format!("Edit {}", self.graph[graph_idx].name());
+        if let Some(before) = old_copies {
+            let Node::Group { copies, .. } = self.graph[graph_idx] else {
+                unreachable!("only groups have copies");
+            };
+            let command = Command::SetCopies {
+                node: node_id,
+                before,
+                after: copies,
+            };
+            self.history.push_merged(&label, time, command);
+
+            // share what comes in between the new number of copies
+            let sources: Vec<_> = self
+                .graph
+                .neighbors_directed(graph_idx, petgraph::Direction::Incoming)
+                .collect();
+            for source in sources {
+                self.refresh_node(source);
+            }
+            changed = true;
+        }
+        if let Some(before) = old_pins {
+            let Node::Group {
+                inputs, outputs, ..
+            } = &self.graph[graph_idx]
+            else {
+                unreachable!("only groups have pins");
+            };
+            let after = (inputs.clone(), outputs.clone());
+            rewire(self.graph, snarl, node_id, &before.0, &before.1);
+            let command = Command::SetPins {
+                node: node_id,
+                before,
+                after,
+            };
+            self.history.push(label.clone(), command);
+        }
+        if let (true, Some(before)) = (changed, before) {
+            let removed_inputs: Vec<_> = cx.removed_input.into_iter().collect();
+            let removed_outputs: Vec<_> = cx.removed_output.into_iter().collect();
+            let dropped = wires_at(
+                self.graph,
+                snarl,
+                node_id,
+                &removed_inputs,
+                &removed_outputs,
+            );
+            let command = Command::SetNode {
+                node: node_id,
+                before: Node::Building(before),
+                after: self.graph[graph_idx].clone(),
+            };
+            self.history
+                .push_merged(&label, time, command.after_dropping(dropped));
+        }
+        if let Some(input) = cx.removed_input {
+            self.drop_input(node_id, input, snarl);
+        }
+        if let Some(output) = cx.removed_output {
+            self.drop_output(node_id, output, snarl);
+        }
+
         if changed {
             self.refresh_node(graph_idx);
         }
@@ -1396,13 +440,21 @@ impl SnarlViewer<GraphIdx> for Viewer<'_> {
                     ui.add_space(5. * scale);
                 }
 
-                let title = node.name();
+                let title = match node.blueprint().and_then(|id| self.library.get(id)) {
+                    Some(blueprint) => format!("{} ({})", blueprint.name, node.name()),
+                    None => node.name(),
+                };
                 let text = RichText::new(title).font(FontId::proportional(15.0 * scale));
                 ui.label(text).on_hover_ui(|ui| {
                     let text =
                         RichText::new(node.description()).font(FontId::proportional(15.0 * scale));
                     ui.label(text);
                 });
+                if let Node::Building(b) = node {
+                    if let Some(radiation) = b.radiation() {
+                        radiation_ui(ui, radiation);
+                    }
+                }
                 ui.add_space(5. * scale);
             });
         });
@@ -1420,64 +472,32 @@ impl SnarlViewer<GraphIdx> for Viewer<'_> {
             return;
         }
 
-        // Update graph
-
-        // connect graph
-        self.graph.add_edge(
-            node_from_idx,
-            node_to_idx,
-            EdgeDetails {
-                input: to.id.input,
-                output: from.id.output,
-            },
-        );
-
-        // connect snarl
-        snarl.connect(from.id, to.id);
-
-        // Update cached values
-        self.graph
-            .node_weight_mut(node_from_idx)
-            .unwrap()
-            .set_current_output_connected(from.id.output);
-        self.refresh_node(node_from_idx);
+        let wire = Wire {
+            from: from.id,
+            to: to.id,
+            height: 0.,
+        };
+        self.history
+            .apply("Connect", Command::Connect(wire), self.graph, snarl);
     }
 
     fn disconnect(&mut self, from: &OutPin, to: &InPin, snarl: &mut egui_snarl::Snarl<GraphIdx>) {
-        // Update graph
         let node_from_idx = snarl[from.id.node];
         let node_to_idx = snarl[to.id.node];
-
-        // disconnect graph
-        let edge = self
+        let height = self
             .graph
             .edges_connecting(node_from_idx, node_to_idx)
-            .find(|e| {
-                let weight = e.weight();
-                weight.output == from.id.output && weight.input == to.id.input
-            });
-
-        if let Some(edge) = edge {
-            self.graph.remove_edge(edge.id());
-        }
-
-        // disconnect snarl
-        snarl.disconnect(from.id, to.id);
-
-        // Update cached values
-
-        // Clear input
-        self.graph
-            .node_weight_mut(node_from_idx)
-            .unwrap()
-            .set_current_output_disconnected(from.id.output);
-        self.graph
-            .node_weight_mut(node_to_idx)
-            .unwrap()
-            .clear_current_input(to.id.input);
-
-        self.refresh_node(node_from_idx);
-        self.refresh_node(node_to_idx);
+            .map(|e| e.weight())
+            .find(|weight| weight.output == from.id.output && weight.input == to.id.input)
+            .map_or(0., |weight| weight.height);
+
+        let wire = Wire {
+            from: from.id,
+            to: to.id,
+            height,
+        };
+        self.history
+            .apply("Disconnect", Command::Disconnect(wire), self.graph, snarl);
     }
 
     fn title(&mut self, graph_idx: &GraphIdx) -> String {
@@ -1498,51 +518,30 @@ impl SnarlViewer<GraphIdx> for Viewer<'_> {
     fn show_input(&mut self, pin: &InPin, ui: &mut Ui, scale: f32, snarl: &mut Snarl) -> PinInfo {
         let graph_idx = snarl[pin.id.node];
         let node = self.graph.node_weight(graph_idx).unwrap();
-        match node {
-            Node::Group {
-                ref snarl,
-                graph,
-                inputs,
-                ..
-            } => {
-                let (_node_id, node_idx, input_id, _current_input) = &inputs[pin.id.input];
-
-                let building = graph.node_weight(*node_idx).unwrap();
-
-                let building = match building {
-                    Node::Building(b) => b,
-                    Node::Group { .. } => todo!("nested groups are not supported yet"),
-                };
+        let is_fluid = node.input_resource(pin.id.input) == ResourceType::Fluid;
+        if let Some(name) = node.input_name(pin.id.input) {
+            ui.label(name);
+        }
+        let (building, input) = node.input_building(pin.id.input);
+        let copies = node.input_copies(pin.id.input);
+        let pin_info = self.show_input_building(building, input, copies, ui, scale);
 
-                self.show_input_building(building, *input_id, ui, scale, snarl)
-            }
-            Node::Building(ref b) => self.show_input_building(b, pin.id.input, ui, scale, snarl),
+        if is_fluid {
+            self.show_head_lift(pin.id.node, pin.id.input, ui, snarl);
         }
+
+        pin_info
     }
 
     fn show_output(&mut self, pin: &OutPin, ui: &mut Ui, scale: f32, snarl: &mut Snarl) -> PinInfo {
         let graph_idx = snarl[pin.id.node];
         let node = self.graph.node_weight(graph_idx).unwrap();
-        match node {
-            Node::Group {
-                snarl,
-                graph,
-                outputs,
-                ..
-            } => {
-                let (_node_id, node_idx, output_id, _current_output) = &outputs[pin.id.output];
-
-                let building = graph.node_weight(*node_idx).unwrap();
-
-                let building = match building {
-                    Node::Building(b) => b,
-                    Node::Group { .. } => todo!("nested groups are not supported yet"),
-                };
-
-                self.show_output_building(building, *output_id, ui, scale, snarl)
-            }
-            Node::Building(ref b) => self.show_output_building(b, pin.id.output, ui, scale, snarl),
+        if let Some(name) = node.output_name(pin.id.output) {
+            ui.label(name);
         }
+        let (building, output) = node.output_building(pin.id.output);
+        let copies = node.output_copies(pin.id.output);
+        self.show_output_building(building, output, copies, ui, scale)
     }
 
     fn has_graph_menu(&mut self, _pos: egui::Pos2, _snarl: &mut Snarl) -> bool {
@@ -1559,6 +558,7 @@ impl SnarlViewer<GraphIdx> for Viewer<'_> {
             MenuItem::Building(Building::Miner(Default::default())),
             MenuItem::Building(Building::WaterExtractor(Default::default())),
             MenuItem::Building(Building::OilExtractor(Default::default())),
+            MenuItem::Building(Building::ResourceWellPressurizer(Default::default())),
             MenuItem::Sep,
             MenuItem::Building(Building::Smelter(Default::default())),
             MenuItem::Building(Building::Foundry(Default::default())),
@@ -1576,114 +576,111 @@ impl SnarlViewer<GraphIdx> for Viewer<'_> {
             MenuItem::Building(Building::Merger(Default::default())),
             MenuItem::Sep,
             MenuItem::Building(Building::PipelineJunction(Default::default())),
+            MenuItem::Building(Building::PipelinePump(Default::default())),
+            MenuItem::Sep,
+            MenuItem::Building(Building::NuclearPowerPlant(Default::default())),
             MenuItem::Sep,
             MenuItem::Building(Building::AwesomeSink(Default::default())),
+            MenuItem::Building(Building::DimensionalDepot(Default::default())),
+            MenuItem::Building(Building::CentralStorage(Default::default())),
+            MenuItem::Sep,
+            MenuItem::Building(Building::ExportPort(Default::default())),
+            MenuItem::Building(Building::ImportPort(Default::default())),
             MenuItem::Sep,
             MenuItem::Building(Building::StorageContainer(Default::default())),
             MenuItem::Sep,
             MenuItem::Group,
         ];
 
+        if let Some(b) = search_ui(ui, self.search, self.unlocks) {
+            self.add_node(format!("Add {}", b.name()), Node::Building(b), pos, snarl);
+            self.search.clear();
+            ui.close_menu();
+        }
+        if !self.search.is_empty() {
+            return;
+        }
+        ui.separator();
+
         for item in items {
             match item {
                 MenuItem::Building(b) => {
-                    if ui.button(format!("Add {}", b.name())).clicked() {
-                        let graph_idx = self.graph.add_node(Node::Building(b));
-                        snarl.insert_node(pos, graph_idx);
+                    let missing = self.unlocks.missing(&b);
+                    let mut button = ui.add_enabled(
+                        missing.is_none(),
+                        egui::Button::new(format!("Add {}", b.name())),
+                    );
+                    if let Some(unlock) = missing {
+                        button = button.on_disabled_hover_text(format!("Needs {unlock}"));
+                    }
+                    if button.clicked() {
+                        self.add_node(format!("Add {}", b.name()), Node::Building(b), pos, snarl);
                         ui.close_menu();
                     }
                 }
                 MenuItem::Group => {
-                    if ui.button("Group").clicked() {
-                        if let Some(snarl_ui_id) = self.snarl_ui_id {
-                            let selected = Snarl::get_selected_nodes_at(
-                                &self.snarl_id_source,
-                                snarl_ui_id,
-                                ui.ctx(),
-                            );
-                            let mut selected = selected
-                                .into_iter()
-                                .map(|id| (id, &snarl[id]))
-                                .collect::<Vec<_>>();
-
-                            selected.sort_by_key(|(id, _)| *id);
-
-                            let mut buildings = Snarl::new();
-                            let mut sub_graph = NodeGraph::default();
-                            let mut to_remove = Vec::new();
-                            let mut inputs = vec![];
-                            let mut outputs = vec![];
-
-                            let mut graph_idx_map = HashMap::new();
-
-                            // TODO: preserve connections outside the group
-
-                            for (id, graph_idx) in selected {
-                                let node = self.graph.node_weight(*graph_idx).unwrap();
-                                let info = snarl.get_node_info(id).unwrap();
-                                let new_graph_idx = sub_graph.add_node(node.clone());
-                                buildings.insert_node(info.pos, new_graph_idx);
-                                graph_idx_map.insert(graph_idx, new_graph_idx);
-
-                                for i in 0..node.outputs() {
-                                    outputs.push((id, new_graph_idx, i, node.current_output(i)));
-                                }
-                                for i in 0..node.inputs() {
-                                    inputs.push((id, new_graph_idx, i, node.current_input(i)));
-                                }
+                    let selected = match self.snarl_ui_id {
+                        Some(snarl_ui_id) => Snarl::get_selected_nodes_at(
+                            &self.snarl_id_source,
+                            snarl_ui_id,
+                            ui.ctx(),
+                        ),
+                        None => Vec::new(),
+                    };
 
-                                to_remove.push((id, *graph_idx));
-                            }
-
-                            // copy wires
-                            let mut connections = Vec::new();
-                            for (node_id_a, idx_a) in &to_remove {
-                                for (node_id_b, idx_b) in &to_remove {
-                                    for edge in self.graph.edges_connecting(*idx_a, *idx_b) {
-                                        connections.push((
-                                            *node_id_a,
-                                            *node_id_b,
-                                            *idx_a,
-                                            *idx_b,
-                                            edge.id(),
-                                        ));
-                                    }
-                                }
-                            }
-
-                            for (node_id_a, node_id_b, idx_a, idx_b, edge_id) in connections {
-                                let new_idx_a = graph_idx_map.get(&idx_a).unwrap();
-                                let new_idx_b = graph_idx_map.get(&idx_b).unwrap();
-                                let edge_details = self.graph.edge_weight(edge_id).unwrap();
-                                sub_graph.add_edge(*new_idx_a, *new_idx_b, edge_details.clone());
-
-                                let wire = snarl
-                                    .wires()
-                                    .find(|(input, output)| {
-                                        output.node == node_id_a && input.node == node_id_b
-                                    })
-                                    .unwrap();
-                                buildings.connect(wire.0, wire.1);
-                            }
-
-                            // Remove nodes from snarl and the graph;
-                            for (id, graph_idx) in &to_remove {
-                                snarl.remove_node(*id);
-                                self.graph.remove_node(*graph_idx);
-                            }
-
-                            let node = Node::Group {
-                                graph: sub_graph,
-                                snarl: buildings,
-                                inputs,
-                                outputs,
-                            };
-                            let graph_idx = self.graph.add_node(node);
-                            snarl.insert_node(pos, graph_idx);
+                    if ui.button("Group").clicked() {
+                        if !selected.is_empty() {
+                            let command = Command::group(self.graph, snarl, &selected, pos);
+                            self.history.push("Group", command);
                         }
 
                         ui.close_menu();
                     }
+
+                    ui.separator();
+                    if ui
+                        .add_enabled(!selected.is_empty(), egui::Button::new("Copy"))
+                        .clicked()
+                    {
+                        let clipboard = Clipboard::copy(self.graph, snarl, &selected);
+                        ui.ctx().copy_text(clipboard.to_json());
+                        *self.clipboard = Some(clipboard);
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(!selected.is_empty(), egui::Button::new("Cut"))
+                        .clicked()
+                    {
+                        let (clipboard, removal) = Clipboard::cut(self.graph, snarl, &selected);
+                        self.history.push("Cut", removal);
+                        ui.ctx().copy_text(clipboard.to_json());
+                        *self.clipboard = Some(clipboard);
+                        ui.close_menu();
+                    }
+                    if let Some(clipboard) = self.clipboard {
+                        if ui.button("Paste").clicked() {
+                            let ids = clipboard.paste(self.graph, snarl, pos);
+                            self.history
+                                .push("Paste", Command::added(self.graph, snarl, &ids));
+                            ui.close_menu();
+                        }
+                    }
+
+                    ui.add_enabled_ui(!self.library.blueprints.is_empty(), |ui| {
+                        ui.menu_button("Blueprints", |ui| {
+                            for blueprint in &self.library.blueprints {
+                                if ui.button(&blueprint.name).clicked() {
+                                    let graph_idx = self.graph.add_node(blueprint.instance());
+                                    let id = snarl.insert_node(pos, graph_idx);
+                                    self.history.push(
+                                        format!("Add {}", blueprint.name),
+                                        Command::added(self.graph, snarl, &[id]),
+                                    );
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                    });
                 }
                 MenuItem::Sep => {
                     ui.separator();
@@ -1693,9 +690,9 @@ impl SnarlViewer<GraphIdx> for Viewer<'_> {
 
         ui.separator();
         if ui.button("Clear All").clicked() {
-            // TODO: add warning
-            *snarl = Snarl::default();
-            self.graph.clear();
+            let ids: Vec<_> = snarl.node_ids().map(|(id, _)| id).collect();
+            let command = Command::remove(self.graph, snarl, &ids);
+            self.history.apply("Clear All", command, self.graph, snarl);
             ui.close_menu();
         }
     }
@@ -1732,17 +729,46 @@ impl SnarlViewer<GraphIdx> for Viewer<'_> {
         let node_info = snarl.get_node_info(node_id).unwrap();
         let graph_idx = node_info.value;
         let node = self.graph.node_weight(graph_idx).unwrap();
-        ui.label(node.name());
+        let name = node.name();
+        ui.label(&name);
 
+        let mut ungroup_clicked = false;
+        let mut link = None;
         match node {
             Node::Building(_) => {}
-            Node::Group { snarl, graph, .. } => {
+            Node::Group {
+                snarl,
+                graph,
+                blueprint,
+                copies,
+                ..
+            } => {
+                if ui
+                    .add_enabled(*copies == 1, egui::Button::new("Ungroup"))
+                    .on_disabled_hover_text("Set the copies back to 1 first")
+                    .clicked()
+                {
+                    ungroup_clicked = true;
+                }
+                match blueprint {
+                    Some(_) => {
+                        if ui.button("Unlink from Blueprint").clicked() {
+                            link = Some(None);
+                            ui.close_menu();
+                        }
+                    }
+                    None => {
+                        if ui.button("Save as Blueprint").clicked() {
+                            let name = format!("Blueprint {}", self.library.blueprints.len() + 1);
+                            link = Some(Some(self.library.add(name, node)));
+                            ui.close_menu();
+                        }
+                    }
+                }
                 if ui.button("Edit").clicked() {
                     self.group_edits.push((
                         GroupEdit {
-                            surface: self.index.0,
-                            node_idx: self.index.1,
-                            source_tab: self.index.2,
+                            source_tab: self.tab_id,
                             node_id,
                         },
                         graph.clone(),
@@ -1756,113 +782,174 @@ impl SnarlViewer<GraphIdx> for Viewer<'_> {
 
         if ui.button("Duplicate").clicked() {
             let pos = node_info.pos + Vec2::new(5., 5.);
-            let new_graph_idx = self.graph.add_node(node.clear_clone());
-            snarl.insert_node(pos, new_graph_idx);
+            self.add_node(format!("Duplicate {name}"), node.clear_clone(), pos, snarl);
             ui.close_menu();
         }
 
         if ui.button("Remove").clicked() {
-            self.graph.remove_node(graph_idx);
-            snarl.remove_node(node_id);
+            let command = Command::remove(self.graph, snarl, &[node_id]);
+            self.history
+                .apply(format!("Remove {name}"), command, self.graph, snarl);
+            ui.close_menu();
+        }
+
+        if let Some(blueprint) = link {
+            let label = match blueprint {
+                Some(_) => "Save as Blueprint",
+                None => "Unlink from Blueprint",
+            };
+            let command = Command::SetBlueprint {
+                node: node_id,
+                before: self.graph[graph_idx].blueprint(),
+                after: blueprint,
+            };
+            self.history.apply(label, command, self.graph, snarl);
+        }
+
+        if ungroup_clicked {
+            let command = Command::ungroup(self.graph, snarl, node_id);
+            self.history.push("Ungroup", command);
             ui.close_menu();
         }
     }
 }
 
-fn level_selector(ui: &mut Ui, _scale: f32, level: &mut MinerLevel) -> Response {
-    let r = egui::ComboBox::from_label("Level")
-        .selected_text(level.name())
-        .show_ui(ui, |ui| {
-            MinerLevel::VARIANTS
-                .into_iter()
-                .map(|l| {
-                    let name = l.name();
-                    ui.selectable_value(level, *l, name)
-                })
-                .reduce(|acc, r| acc | r)
-                .unwrap()
-        });
-    r.inner.unwrap_or(r.response)
-}
+/// Returns the smallest remaining head lift in meters over all pipes feeding
+/// the given input, walking upstream through junctions until a pump or
+/// fluid producing building is reached.
+fn head_lift_margin(
+    graph: &NodeGraph,
+    node_idx: GraphIdx,
+    input: usize,
+    elevation: f32,
+    visited: &mut HashSet<GraphIdx>,
+) -> Option<f32> {
+    let mut margin: Option<f32> = None;
+    for edge in graph.edges_directed(node_idx, petgraph::Direction::Incoming) {
+        let weight = edge.weight();
+        if weight.input != input {
+            continue;
+        }
+        let source = graph.node_weight(edge.source()).unwrap();
+        if source.output_resource(weight.output) != ResourceType::Fluid {
+            continue;
+        }
 
-fn purity_selector(ui: &mut Ui, _scale: f32, purity: &mut ResourcePurity) -> Response {
-    let r = egui::ComboBox::from_label("Purity")
-        .selected_text(purity.name())
-        .show_ui(ui, |ui| {
-            ResourcePurity::VARIANTS
-                .into_iter()
-                .map(|p| {
-                    let name = p.name();
-                    ui.selectable_value(purity, *p, name)
-                })
-                .reduce(|acc, r| acc | r)
-                .unwrap()
-        });
+        let elevation = elevation + weight.height;
+        let current = if source.is_fluid_passthrough() {
+            if !visited.insert(edge.source()) {
+                continue;
+            }
+            (0..source.inputs())
+                .filter_map(|i| head_lift_margin(graph, edge.source(), i, elevation, visited))
+                .reduce(f32::min)
+        } else {
+            Some(source.head_lift(weight.output) - elevation)
+        };
 
-    r.inner.unwrap_or(r.response)
+        if let Some(current) = current {
+            margin = Some(margin.map_or(current, |m| m.min(current)));
+        }
+    }
+    margin
 }
 
-fn pipe_selector(ui: &mut Ui, _scale: f32, pipe: &mut Option<Pipe>) -> Response {
-    let text = match pipe {
-        Some(p) => p.name(),
-        None => "Select Pipe".to_string(),
-    };
+/// What all copies of a group build, make and take, with units.
+///
+/// Construction costs are not listed, buildings have no build cost data yet.
+fn group_totals(node: &Node) -> Vec<(String, f32)> {
+    let mut totals = vec![
+        ("Buildings".to_string(), node.building_count() as f32),
+        ("Power (MW)".to_string(), node.power()),
+    ];
+    for output in 0..node.outputs() {
+        if let Some(current) = node.current_output(output) {
+            let unit = unit(current.resource.typ());
+            totals.push((
+                format!("{} (/{})", current.resource.name(), unit),
+                current.speed,
+            ));
+        }
+    }
+    for input in 0..node.inputs() {
+        let (building, inner_input) = node.input_building(input);
+        if let Some(current) = building.current_input(inner_input) {
+            let unit = unit(current.resource.typ());
+            let speed = round(current.speed * node.input_copies(input) as f32);
+            totals.push((format!("{} in (/{})", current.resource.name(), unit), speed));
+        }
+    }
+    totals
+}
 
-    let r = egui::ComboBox::from_label("Pipe")
-        .selected_text(text)
-        .show_ui(ui, |ui| {
-            Pipe::VARIANTS
-                .into_iter()
-                .map(|p| {
-                    let name = p.name();
-                    ui.selectable_value(pipe, Some(*p), name)
-                })
-                .reduce(|acc, r| acc | r)
-                .unwrap()
-        });
+/// Name, order and visibility of a group's pins, `wired` are the shown pins
+/// with wires, which can't be hidden. Returns true if anything changed.
+fn group_pins_ui(
+    ui: &mut Ui,
+    graph: &NodeGraph,
+    pins: &mut [GroupPin],
+    wired: &HashSet<usize>,
+) -> bool {
+    let mut changed = false;
+    let mut swap = None;
+    let mut shown = 0;
+    let count = pins.len();
+    for (i, pin) in pins.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            let is_wired = !pin.hidden && wired.contains(&shown);
+            let mut show = !pin.hidden;
+            let response = ui
+                .add_enabled(!is_wired, egui::Checkbox::without_text(&mut show))
+                .on_hover_text("Show on the group")
+                .on_disabled_hover_text("Disconnect it to hide it");
+            if response.changed() {
+                pin.hidden = !show;
+                changed = true;
+            }
 
-    r.inner.unwrap_or(r.response)
-}
+            let hint = format!("{} #{}", graph[pin.node_idx].name(), pin.pin + 1);
+            let mut name = pin.name.clone().unwrap_or_default();
+            let edit = egui::TextEdit::singleline(&mut name)
+                .hint_text(hint)
+                .desired_width(150.);
+            if ui.add(edit).changed() {
+                pin.name = (!name.is_empty()).then_some(name);
+                changed = true;
+            }
 
-fn general_selector<S: Selectable>(ui: &mut Ui, scale: f32, resource: &mut Option<S>) -> Response {
-    ui.horizontal(|ui| {
-        let x = 20. * scale;
-        if let Some(ref resource) = resource {
-            let image = egui::Image::new(resource.image())
-                .fit_to_exact_size(vec2(x, x))
-                .show_loading_spinner(true);
-            ui.add(image);
-        } else {
-            ui.add_space(x);
+            if ui.add_enabled(i > 0, egui::Button::new("⏶")).clicked() {
+                swap = Some((i - 1, i));
+            }
+            if ui
+                .add_enabled(i + 1 < count, egui::Button::new("⏷"))
+                .clicked()
+            {
+                swap = Some((i, i + 1));
+            }
+        });
+        if !pin.hidden {
+            shown += 1;
         }
+    }
+    if let Some((a, b)) = swap {
+        pins.swap(a, b);
+        changed = true;
+    }
+    changed
+}
 
-        let text = match resource {
-            Some(r) => r.name(),
-            None => format!("Select {}", S::NAME),
-        };
+fn pin_shape(typ: ResourceType) -> PinInfo {
+    match typ {
+        ResourceType::Fluid => PinInfo::circle(),
+        ResourceType::Material => PinInfo::square(),
+    }
+}
 
-        let r = egui::ComboBox::from_id_salt(egui::Id::new(format!("{}_resource", S::NAME)))
-            .selected_text(text)
-            .show_ui(ui, |ui| {
-                S::VARIANTS
-                    .into_iter()
-                    .map(|r| {
-                        let name = r.name();
-                        ui.horizontal(|ui| {
-                            let image = egui::Image::new(r.image())
-                                .fit_to_exact_size(vec2(20., 20.))
-                                .show_loading_spinner(true);
-                            ui.add(image);
-                            ui.selectable_value(resource, Some(r.clone()), name)
-                        })
-                        .inner
-                    })
-                    .reduce(|acc, r| acc | r)
-                    .unwrap()
-            });
-        r.inner.unwrap_or(r.response)
-    })
-    .inner
+fn unit(typ: ResourceType) -> &'static str {
+    match typ {
+        ResourceType::Fluid => "m^3",
+        ResourceType::Material => "min",
+    }
 }
 
 fn add_resource_image(ui: &mut Ui, scale: f32, material: &Option<Resource>) {
@@ -1880,30 +967,20 @@ fn add_resource_image(ui: &mut Ui, scale: f32, material: &Option<Resource>) {
     }
 }
 
-fn add_speed_ui(ui: &mut Ui, value: &mut f32) -> Response {
-    ui.horizontal(|ui| {
-        let overclock = egui::DragValue::new(value).range(0.0..=250.0).suffix("%");
-        let response = ui.add(overclock);
-        ui.label("Speed");
-        response
-    })
-    .inner
-}
-
 fn single_input(
-    resource: Option<Resource>,
-    max_input_speed: f32,
+    spec: PinSpec,
     actual_input_speed: f32,
     actual_input_material: Option<Resource>,
+    typ: ResourceType,
     ui: &mut Ui,
     scale: f32,
-    _snarl: &Snarl,
-    pin_info: PinInfo,
 ) -> PinInfo {
-    let color = match (resource, actual_input_material) {
+    let unit = unit(typ);
+    let max_input_speed = spec.max_speed;
+    let color = match (spec.resource, actual_input_material) {
         (Some(resource), Some(actual_input_material)) => {
             let (actual_input_speed, color) = if resource == actual_input_material {
-                let v = format!("{}/min", actual_input_speed);
+                let v = format!("{}/{}", actual_input_speed, unit);
                 let color = resource.color();
 
                 (v, color)
@@ -1912,21 +989,24 @@ fn single_input(
             };
             ui.horizontal(|ui| {
                 add_resource_image(ui, scale, &Some(resource));
-                ui.label(format!("{} ({}/min)", actual_input_speed, max_input_speed));
+                ui.label(format!(
+                    "{} ({}/{})",
+                    actual_input_speed, max_input_speed, unit
+                ));
             });
             color
         }
         (Some(resource), None) => {
             ui.horizontal(|ui| {
                 add_resource_image(ui, scale, &Some(resource));
-                ui.label(format!("NA ({}/min)", max_input_speed));
+                ui.label(format!("NA ({}/{})", max_input_speed, unit));
             });
             INVALID_COLOR
         }
         (None, Some(_actual_input_material)) => {
             ui.horizontal(|ui| {
                 add_resource_image(ui, scale, &None);
-                ui.label(format!("{}/min (NA)", actual_input_speed));
+                ui.label(format!("{}/{} (NA)", actual_input_speed, unit));
             });
 
             INVALID_COLOR
@@ -1934,114 +1014,141 @@ fn single_input(
         (None, None) => BUILDING_COLOR,
     };
 
-    pin_info.with_fill(color)
+    pin_shape(typ).with_fill(color)
 }
 
-fn fluid_output(
-    fluid: Option<Fluid>,
-    max_speed: f32,
-    current_output: Option<Output>,
+/// A pin that takes or passes on whatever arrives.
+fn pass_through(
+    speed: f32,
+    resource: Option<Resource>,
+    typ: ResourceType,
     ui: &mut Ui,
     scale: f32,
 ) -> PinInfo {
-    let color = fluid.as_ref().map(|m| m.color()).unwrap_or(BUILDING_COLOR);
-    if let Some(fluid) = fluid {
-        let fluid = Resource::Fluid(fluid);
-        match current_output {
-            Some(current_output) => {
-                ui.horizontal(|ui| {
-                    add_resource_image(ui, scale, &Some(current_output.resource));
-                    ui.label(format!("{}/m^3 ({}/m^3)", current_output.speed, max_speed));
-                });
-            }
-            None => {
-                ui.horizontal(|ui| {
-                    add_resource_image(ui, scale, &Some(fluid));
-                    ui.label(format!("NA ({}/m^3)", max_speed));
-                });
-            }
-        }
-    }
+    ui.horizontal(|ui| {
+        add_resource_image(ui, scale, &resource);
+        ui.label(format!("{}/{}", speed, unit(typ)));
+    });
 
-    PinInfo::circle().with_fill(color)
+    let color = resource.map(|r| r.color()).unwrap_or(BUILDING_COLOR);
+    pin_shape(typ).with_fill(color)
 }
 
-fn material_output(
-    material: Option<Material>,
-    max_speed: f32,
+fn rated_output(
+    spec: PinSpec,
     current_output: Option<Output>,
+    typ: ResourceType,
     ui: &mut Ui,
     scale: f32,
 ) -> PinInfo {
-    let color = material
+    let unit = unit(typ);
+    let color = spec
+        .resource
         .as_ref()
-        .map(|m| m.color())
+        .map(|r| r.color())
         .unwrap_or(BUILDING_COLOR);
-    if let Some(material) = material {
-        let material = Resource::Material(material);
+    if let Some(resource) = spec.resource {
         match current_output {
             Some(current_output) => {
                 ui.horizontal(|ui| {
                     add_resource_image(ui, scale, &Some(current_output.resource));
-                    ui.label(format!("{}/min ({}/min)", current_output.speed, max_speed));
+                    ui.label(format!(
+                        "{}/{} ({}/{})",
+                        current_output.speed, unit, spec.max_speed, unit
+                    ));
                 });
             }
             None => {
                 ui.horizontal(|ui| {
-                    add_resource_image(ui, scale, &Some(material));
-                    ui.label(format!("NA ({}/min)", max_speed));
+                    add_resource_image(ui, scale, &Some(resource));
+                    ui.label(format!("NA ({}/{})", spec.max_speed, unit));
                 });
             }
         }
     }
 
-    PinInfo::square().with_fill(color)
+    pin_shape(typ).with_fill(color)
 }
 
-fn add_somersloop1_ui(ui: &mut Ui, amplified: &mut SomersloopSlot1) -> Response {
-    let r = egui::ComboBox::from_id_salt(egui::Id::new("amplification1"))
-        .selected_text(amplified.name())
-        .show_ui(ui, |ui| {
-            SomersloopSlot1::VARIANTS
-                .into_iter()
-                .map(|var| {
-                    let name = var.name();
-                    ui.selectable_value(amplified, *var, name)
-                })
-                .reduce(|acc, r| acc | r)
-                .unwrap()
-        });
-    r.inner.unwrap_or(r.response)
-}
+#[cfg(test)]
+mod tests {
+    use petgraph::stable_graph::EdgeIndex;
 
-fn add_somersloop2_ui(ui: &mut Ui, amplified: &mut SomersloopSlot2) -> Response {
-    let r = egui::ComboBox::from_id_salt(egui::Id::new("amplification2"))
-        .selected_text(amplified.name())
-        .show_ui(ui, |ui| {
-            SomersloopSlot2::VARIANTS
-                .into_iter()
-                .map(|var| {
-                    let name = var.name();
-                    ui.selectable_value(amplified, *var, name)
-                })
-                .reduce(|acc, r| acc | r)
-                .unwrap()
-        });
-    r.inner.unwrap_or(r.response)
-}
+    use super::*;
+    use crate::app::EdgeDetails;
+    use crate::buildings::{PipelineJunction, PipelinePump, WaterExtractor};
 
-fn add_somersloop4_ui(ui: &mut Ui, amplified: &mut SomersloopSlot4) -> Response {
-    let r = egui::ComboBox::from_id_salt(egui::Id::new("amplification4"))
-        .selected_text(amplified.name())
-        .show_ui(ui, |ui| {
-            SomersloopSlot4::VARIANTS
-                .into_iter()
-                .map(|var| {
-                    let name = var.name();
-                    ui.selectable_value(amplified, *var, name)
-                })
-                .reduce(|acc, r| acc | r)
-                .unwrap()
-        });
-    r.inner.unwrap_or(r.response)
+    fn pipe(
+        graph: &mut NodeGraph,
+        from: GraphIdx,
+        to: GraphIdx,
+        input: usize,
+        height: f32,
+    ) -> EdgeIndex {
+        let details = EdgeDetails {
+            input,
+            output: 0,
+            height,
+        };
+        graph.add_edge(from, to, details)
+    }
+
+    fn margin(graph: &NodeGraph, node_idx: GraphIdx) -> Option<f32> {
+        head_lift_margin(graph, node_idx, 0, 0., &mut HashSet::new())
+    }
+
+    #[test]
+    fn test_head_lift_through_junction() {
+        let mut graph = NodeGraph::default();
+        let extractor = graph.add_node(Node::Building(Building::WaterExtractor(
+            WaterExtractor::default(),
+        )));
+        let pump = graph.add_node(Node::Building(Building::PipelinePump(
+            PipelinePump::default(),
+        )));
+        let junction = graph.add_node(Node::Building(Building::PipelineJunction(
+            PipelineJunction::default(),
+        )));
+        let target = graph.add_node(Node::Building(Building::PipelinePump(
+            PipelinePump::default(),
+        )));
+        pipe(&mut graph, extractor, pump, 0, 5.);
+        pipe(&mut graph, pump, junction, 0, 8.);
+        let up = pipe(&mut graph, junction, target, 0, 7.);
+
+        // the extractor pushes 10m, the Mk.1 pump 22m
+        assert_eq!(margin(&graph, pump), Some(5.));
+        assert_eq!(margin(&graph, target), Some(7.));
+
+        graph[up].height = 25.;
+        assert_eq!(margin(&graph, target), Some(-11.));
+
+        // going down gives the height back
+        graph[up].height = -10.;
+        assert_eq!(margin(&graph, target), Some(24.));
+    }
+
+    #[test]
+    fn test_head_lift_junction_cycle() {
+        let mut graph = NodeGraph::default();
+        let pump = graph.add_node(Node::Building(Building::PipelinePump(
+            PipelinePump::default(),
+        )));
+        let a = graph.add_node(Node::Building(Building::PipelineJunction(
+            PipelineJunction::default(),
+        )));
+        let b = graph.add_node(Node::Building(Building::PipelineJunction(
+            PipelineJunction::default(),
+        )));
+        let target = graph.add_node(Node::Building(Building::PipelinePump(
+            PipelinePump::default(),
+        )));
+        pipe(&mut graph, pump, a, 0, 4.);
+        pipe(&mut graph, a, b, 0, 2.);
+        pipe(&mut graph, b, a, 1, 2.);
+        pipe(&mut graph, b, target, 0, 4.);
+
+        // the loop back into the first junction is only walked once
+        assert_eq!(margin(&graph, target), Some(12.));
+    }
 }
@@ -0,0 +1,293 @@
+//! Input widgets shared by the node bodies of the buildings.
+
+use egui::{vec2, Color32, Response, RichText, Ui};
+use strum::VariantArray;
+
+use crate::buildings::{
+    power_shards, MinerLevel, Pipe, ResourcePurity, Selectable, SomersloopSlot1, SomersloopSlot2,
+    SomersloopSlot4, Transport, MAX_CLOCK_SPEED, MIN_CLOCK_SPEED,
+};
+use crate::unlocks::{Gated, Unlocks};
+
+/// Marks invalid connections and warnings.
+pub const INVALID_COLOR: Color32 = Color32::from_rgb(144, 20, 0);
+
+/// Flags nodes and belts carrying radioactive items.
+pub const RADIATION_COLOR: Color32 = Color32::from_rgb(120, 200, 40);
+
+pub fn level_selector(
+    ui: &mut Ui,
+    _scale: f32,
+    level: &mut MinerLevel,
+    unlocks: &Unlocks,
+) -> Response {
+    let r = egui::ComboBox::from_label("Level")
+        .selected_text(gated_text(level.name().to_string(), &*level, unlocks))
+        .show_ui(ui, |ui| {
+            MinerLevel::VARIANTS
+                .iter()
+                .filter(|l| unlocks.missing(*l).is_none())
+                .map(|l| {
+                    let name = l.name();
+                    ui.selectable_value(level, *l, name)
+                })
+                .reduce(|acc, r| acc | r)
+                .unwrap_or_else(|| ui.label("Nothing unlocked"))
+        });
+    r.inner.unwrap_or(r.response)
+}
+
+pub fn purity_selector(ui: &mut Ui, _scale: f32, purity: &mut ResourcePurity) -> Response {
+    let r = egui::ComboBox::from_label("Purity")
+        .selected_text(purity.name())
+        .show_ui(ui, |ui| {
+            ResourcePurity::VARIANTS
+                .iter()
+                .map(|p| {
+                    let name = p.name();
+                    ui.selectable_value(purity, *p, name)
+                })
+                .reduce(|acc, r| acc | r)
+                .unwrap()
+        });
+
+    r.inner.unwrap_or(r.response)
+}
+
+pub fn pipe_selector(
+    ui: &mut Ui,
+    _scale: f32,
+    pipe: &mut Option<Pipe>,
+    unlocks: &Unlocks,
+) -> Response {
+    let text = match pipe {
+        Some(p) => gated_text(p.name(), p, unlocks),
+        None => RichText::new("Select Pipe"),
+    };
+
+    let r = egui::ComboBox::from_label("Pipe")
+        .selected_text(text)
+        .show_ui(ui, |ui| {
+            Pipe::VARIANTS
+                .iter()
+                .filter(|p| unlocks.missing(*p).is_none())
+                .map(|p| {
+                    let name = p.name();
+                    ui.selectable_value(pipe, Some(*p), name)
+                })
+                .reduce(|acc, r| acc | r)
+                .unwrap_or_else(|| ui.label("Nothing unlocked"))
+        });
+
+    r.inner.unwrap_or(r.response)
+}
+
+pub fn general_selector<S: Selectable>(
+    ui: &mut Ui,
+    scale: f32,
+    resource: &mut Option<S>,
+) -> Response {
+    filtered_selector(ui, scale, resource, |_| true)
+}
+
+/// Only offers the recipes the project has unlocked. A locked recipe that is
+/// already selected stays, but is marked.
+pub fn recipe_selector<S: Selectable>(
+    ui: &mut Ui,
+    scale: f32,
+    recipe: &mut Option<S>,
+    unlocks: &Unlocks,
+) -> Response {
+    filtered_selector(ui, scale, recipe, |r| unlocks.allows(r))
+}
+
+/// Only offers what is available at the project's tier.
+pub fn gated_selector<S: Selectable + Gated>(
+    ui: &mut Ui,
+    scale: f32,
+    value: &mut Option<S>,
+    unlocks: &Unlocks,
+) -> Response {
+    filtered_selector(ui, scale, value, |v| unlocks.missing(v).is_none())
+}
+
+/// Marks a selection that is not unlocked yet.
+fn gated_text(name: String, gated: &impl Gated, unlocks: &Unlocks) -> RichText {
+    match unlocks.missing(gated) {
+        Some(_) => RichText::new(name).color(INVALID_COLOR),
+        None => RichText::new(name),
+    }
+}
+
+fn filtered_selector<S: Selectable>(
+    ui: &mut Ui,
+    scale: f32,
+    resource: &mut Option<S>,
+    available: impl Fn(&S) -> bool,
+) -> Response {
+    ui.horizontal(|ui| {
+        let x = 20. * scale;
+        if let Some(ref resource) = resource {
+            let image = egui::Image::new(resource.image())
+                .fit_to_exact_size(vec2(x, x))
+                .show_loading_spinner(true);
+            ui.add(image);
+        } else {
+            ui.add_space(x);
+        }
+
+        let text = match resource {
+            Some(r) if !available(r) => RichText::new(r.name()).color(INVALID_COLOR),
+            Some(r) => RichText::new(r.name()),
+            None => RichText::new(format!("Select {}", S::NAME)),
+        };
+
+        let r = egui::ComboBox::from_id_salt(egui::Id::new(format!("{}_resource", S::NAME)))
+            .selected_text(text)
+            .show_ui(ui, |ui| {
+                S::VARIANTS
+                    .iter()
+                    .filter(|r| available(r))
+                    .map(|r| {
+                        let name = r.name();
+                        ui.horizontal(|ui| {
+                            let image = egui::Image::new(r.image())
+                                .fit_to_exact_size(vec2(20., 20.))
+                                .show_loading_spinner(true);
+                            ui.add(image);
+                            ui.selectable_value(resource, Some(r.clone()), name)
+                        })
+                        .inner
+                    })
+                    .reduce(|acc, r| acc | r)
+                    .unwrap_or_else(|| ui.label("Nothing unlocked"))
+            });
+        r.inner.unwrap_or(r.response)
+    })
+    .inner
+}
+
+/// Radiation intensity of a belt, decay per item times items/min.
+pub fn radiation_ui(ui: &mut Ui, radiation: f32) -> Response {
+    ui.colored_label(RADIATION_COLOR, format!("☢ {radiation:.1}"))
+        .on_hover_text("Radioactive, wear a Hazmat Suit")
+}
+
+/// What an extractor could produce, and what its output belt or pipe lets through.
+pub fn extraction_ui(ui: &mut Ui, theoretical: f32, effective: f32, unit: &str) {
+    ui.label(format!("Extracting: {theoretical}/{unit}"));
+    if effective < theoretical {
+        ui.colored_label(
+            INVALID_COLOR,
+            format!("Output limited to {effective}/{unit}"),
+        );
+    }
+}
+
+pub fn add_transport_ui(ui: &mut Ui, transport: &mut Transport) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        changed |= ui
+            .add(egui::DragValue::new(&mut transport.vehicles).range(1..=1000))
+            .changed();
+        ui.label("Vehicles");
+    });
+    ui.horizontal(|ui| {
+        changed |= ui
+            .add(egui::DragValue::new(&mut transport.capacity).range(0.0..=f32::MAX))
+            .changed();
+        ui.label("Capacity per trip");
+    });
+    ui.horizontal(|ui| {
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut transport.round_trip)
+                    .range(1.0..=f32::MAX)
+                    .suffix("s"),
+            )
+            .changed();
+        ui.label("Round trip");
+    });
+    ui.label(format!("Throughput: {}/min", transport.throughput()));
+    changed
+}
+
+/// Clock speed, with the power shards it needs and, if given, a button to set
+/// the speed that exactly consumes the supplied inputs.
+pub fn add_speed_ui(ui: &mut Ui, value: &mut f32, match_input: Option<f32>) -> Response {
+    let mut response = ui
+        .horizontal(|ui| {
+            let overclock = egui::DragValue::new(value)
+                .range(MIN_CLOCK_SPEED..=MAX_CLOCK_SPEED)
+                .suffix("%");
+            let response = ui.add(overclock);
+            ui.label("Speed");
+            response
+        })
+        .inner;
+
+    let shards = power_shards(*value);
+    if shards > 0 {
+        ui.label(format!("Power Shards: {shards}"));
+    }
+
+    if let Some(matching) = match_input {
+        let button = ui
+            .add_enabled(*value != matching, egui::Button::new("Match input"))
+            .on_hover_text(format!("Set the speed to {matching}%"));
+        if button.clicked() {
+            *value = matching;
+            response.mark_changed();
+        }
+    }
+
+    response
+}
+
+pub fn add_somersloop1_ui(ui: &mut Ui, amplified: &mut SomersloopSlot1) -> Response {
+    let r = egui::ComboBox::from_id_salt(egui::Id::new("amplification1"))
+        .selected_text(amplified.name())
+        .show_ui(ui, |ui| {
+            SomersloopSlot1::VARIANTS
+                .iter()
+                .map(|var| {
+                    let name = var.name();
+                    ui.selectable_value(amplified, *var, name)
+                })
+                .reduce(|acc, r| acc | r)
+                .unwrap()
+        });
+    r.inner.unwrap_or(r.response)
+}
+
+pub fn add_somersloop2_ui(ui: &mut Ui, amplified: &mut SomersloopSlot2) -> Response {
+    let r = egui::ComboBox::from_id_salt(egui::Id::new("amplification2"))
+        .selected_text(amplified.name())
+        .show_ui(ui, |ui| {
+            SomersloopSlot2::VARIANTS
+                .iter()
+                .map(|var| {
+                    let name = var.name();
+                    ui.selectable_value(amplified, *var, name)
+                })
+                .reduce(|acc, r| acc | r)
+                .unwrap()
+        });
+    r.inner.unwrap_or(r.response)
+}
+
+pub fn add_somersloop4_ui(ui: &mut Ui, amplified: &mut SomersloopSlot4) -> Response {
+    let r = egui::ComboBox::from_id_salt(egui::Id::new("amplification4"))
+        .selected_text(amplified.name())
+        .show_ui(ui, |ui| {
+            SomersloopSlot4::VARIANTS
+                .iter()
+                .map(|var| {
+                    let name = var.name();
+                    ui.selectable_value(amplified, *var, name)
+                })
+                .reduce(|acc, r| acc | r)
+                .unwrap()
+        });
+    r.inner.unwrap_or(r.response)
+}